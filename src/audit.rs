@@ -0,0 +1,66 @@
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::client_ip::ClientIp;
+use crate::sites::SiteId;
+
+/// Actor recorded for changes made by the server itself.
+pub const SYSTEM_ACTOR: &str = "system";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    pub action: String,
+    pub site_id: Option<SiteId>,
+    /// Client the change came from, when made through a request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip: Option<IpAddr>,
+}
+
+/// Append-only log of changes made to sites.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog(Arc<Mutex<Vec<AuditEntry>>>);
+
+impl AuditLog {
+    pub fn lock(&self) -> MutexGuard<'_, Vec<AuditEntry>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn record(
+        &self,
+        at: DateTime<Utc>,
+        actor: impl Into<String>,
+        action: impl Into<String>,
+        site_id: Option<SiteId>,
+    ) {
+        self.lock().push(AuditEntry {
+            at,
+            actor: actor.into(),
+            action: action.into(),
+            site_id,
+            ip: None,
+        });
+    }
+
+    /// Records a change made by a client, at `ip` when its address is known.
+    pub fn record_request(
+        &self,
+        at: DateTime<Utc>,
+        actor: impl Into<String>,
+        action: impl Into<String>,
+        site_id: Option<SiteId>,
+        ip: Option<ClientIp>,
+    ) {
+        self.lock().push(AuditEntry {
+            at,
+            actor: actor.into(),
+            action: action.into(),
+            site_id,
+            ip: ip.map(|ClientIp(ip)| ip),
+        });
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+
+/// Private cookie holding the logged in user.
+pub const USER_COOKIE: &str = "user";
+/// Cookie holding the CSRF token issued on login; clients echo it in [`CSRF_HEADER`].
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Action a role may be allowed to take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Permission {
+    CreateSite,
+    /// Attach, reserve and maintain vehicles, and assign workers.
+    ManageResources,
+    /// Change the role of any user, themselves included.
+    ManageRoles,
+    ReportAnomaly,
+    SetStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, FromFormField)]
+pub enum AppRole {
+    /// Creates sites and manages every site's resources.
+    SitesGlobalManager,
+    /// Follows the sites they manage: reports anomalies and sets their status.
+    SiteManager,
+}
+
+impl AppRole {
+    pub const ALL: [AppRole; 2] = [AppRole::SitesGlobalManager, AppRole::SiteManager];
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            AppRole::SitesGlobalManager => &[
+                Permission::CreateSite,
+                Permission::ManageResources,
+                Permission::ManageRoles,
+            ],
+            AppRole::SiteManager => &[Permission::ReportAnomaly, Permission::SetStatus],
+        }
+    }
+
+    pub fn can(&self, action: Permission) -> bool {
+        self.permissions().contains(&action)
+    }
+}
+
+/// A user logged in through GitHub.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    pub name: String,
+    pub role: AppRole,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for User {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        #[cfg(feature = "test-auth")]
+        let user = test_user(request);
+        #[cfg(not(feature = "test-auth"))]
+        let user: Option<User> = None;
+
+        let user = user.or_else(|| {
+            request
+                .cookies()
+                .get_private(USER_COOKIE)
+                .and_then(|cookie| serde_json::from_str(cookie.value()).ok())
+        });
+
+        match user {
+            Some(mut user) => {
+                if let Some(users) = request.rocket().state::<Users>() {
+                    user.role = users.granted_role(&user.name).unwrap_or(user.role);
+                    users.record(&user);
+                }
+                Outcome::Success(user)
+            }
+            None => Outcome::Forward(Status::Unauthorized),
+        }
+    }
+}
+
+/// Users who made a request since the server started, with the role of their latest one, e.g.
+/// to check who a site can be handed over to, and the roles granted to users.
+#[derive(Debug, Clone, Default)]
+pub struct Users {
+    seen: Arc<RwLock<HashMap<String, AppRole>>>,
+    /// Roles overriding the one in a user's cookie, until the server restarts.
+    granted: Arc<RwLock<HashMap<String, AppRole>>>,
+}
+
+impl Users {
+    /// Records `user`'s role, only taking the write lock when it changed.
+    pub fn record(&self, user: &User) {
+        if self.role_of(&user.name) != Some(user.role) {
+            write(&self.seen).insert(user.name.clone(), user.role);
+        }
+    }
+
+    pub fn role_of(&self, name: &str) -> Option<AppRole> {
+        read(&self.seen).get(name).copied()
+    }
+
+    /// Gives `name` the `role` on their next requests, whatever their cookie says.
+    pub fn grant(&self, name: &str, role: AppRole) {
+        write(&self.granted).insert(name.to_owned(), role);
+        write(&self.seen).insert(name.to_owned(), role);
+    }
+
+    pub fn granted_role(&self, name: &str) -> Option<AppRole> {
+        read(&self.granted).get(name).copied()
+    }
+}
+
+fn read(roles: &RwLock<HashMap<String, AppRole>>) -> RwLockReadGuard<'_, HashMap<String, AppRole>> {
+    roles.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write(
+    roles: &RwLock<HashMap<String, AppRole>>,
+) -> RwLockWriteGuard<'_, HashMap<String, AppRole>> {
+    roles.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(feature = "test-auth")]
+pub const TEST_USER_HEADER: &str = "X-Test-User";
+#[cfg(feature = "test-auth")]
+pub const TEST_ROLE_HEADER: &str = "X-Test-Role";
+
+/// User named by the [`TEST_USER_HEADER`], with the [`TEST_ROLE_HEADER`] role, e.g.
+/// `SiteManager`. Both headers are required.
+#[cfg(feature = "test-auth")]
+fn test_user(request: &Request<'_>) -> Option<User> {
+    let name = request.headers().get_one(TEST_USER_HEADER)?;
+    let role = request.headers().get_one(TEST_ROLE_HEADER)?;
+    let role = serde_json::from_value(serde_json::Value::String(role.to_owned())).ok()?;
+    Some(User {
+        name: name.to_owned(),
+        role,
+    })
+}
+
+/// Fresh random value for the [`CSRF_COOKIE`].
+pub fn new_csrf_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Proof that a state-changing request carries the CSRF token of its session. Every
+/// `POST`, `PUT`, `PATCH` and `DELETE` route takes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsrfChecked;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfChecked {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Test users come from headers, which another site cannot make a browser send.
+        #[cfg(feature = "test-auth")]
+        if test_user(request).is_some() {
+            return Outcome::Success(CsrfChecked);
+        }
+
+        let expected = request
+            .cookies()
+            .get(CSRF_COOKIE)
+            .map(|cookie| cookie.value());
+        let sent = request.headers().get_one(CSRF_HEADER);
+
+        match (expected, sent) {
+            (Some(expected), Some(sent)) if !expected.is_empty() && expected == sent => {
+                Outcome::Success(CsrfChecked)
+            }
+            _ => Outcome::Error((Status::Forbidden, ())),
+        }
+    }
+}
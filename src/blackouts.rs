@@ -0,0 +1,79 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use rocket::request::FromParam;
+use serde::{Deserialize, Serialize};
+
+use crate::error::BlackoutErr;
+use crate::sites::ReservedDate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BlackoutId(pub u32);
+
+impl fmt::Display for BlackoutId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for BlackoutId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(BlackoutId)
+    }
+}
+
+/// A period, e.g. company holidays, in which no vehicle of the fleet can be reserved. Its
+/// note gives the reason.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blackout {
+    pub id: BlackoutId,
+    pub period: ReservedDate,
+}
+
+impl Blackout {
+    /// Checks that `date` is outside every one of `blackouts`.
+    pub fn check(blackouts: &[Blackout], date: &ReservedDate) -> Result<(), BlackoutErr> {
+        match blackouts
+            .iter()
+            .find(|blackout| !blackout.period.compatible_with(date))
+        {
+            Some(blackout) => Err(BlackoutErr {
+                blackout: blackout.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fleet-wide blackouts, sorted by period.
+#[derive(Debug, Clone, Default)]
+pub struct Blackouts(Arc<RwLock<Vec<Blackout>>>);
+
+impl Blackouts {
+    pub fn read(&self) -> RwLockReadGuard<'_, Vec<Blackout>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Vec<Blackout>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Copy of the blackouts, so the fleet can be locked without holding them.
+    pub fn current(&self) -> Vec<Blackout> {
+        self.read().clone()
+    }
+
+    pub fn next_id(blackouts: &[Blackout]) -> BlackoutId {
+        BlackoutId(
+            blackouts
+                .iter()
+                .map(|blackout| blackout.id.0)
+                .max()
+                .unwrap_or(0)
+                + 1,
+        )
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use chrono::{Datelike, Days, NaiveDate, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{LabeledPeriod, PeriodLabels};
+use crate::error::DayOutOfRangeErr;
+use crate::sites::DayPeriod;
+
+/// Calendar day counted from the project start, which is day `0`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct DayIndex(pub usize);
+
+impl DayIndex {
+    /// Date of the day, an error when it is past the last date chrono can represent.
+    pub fn to_date(self, project_start: NaiveDate) -> Result<NaiveDate, DayOutOfRangeErr> {
+        project_start
+            .checked_add_days(Days::new(self.0 as u64))
+            .ok_or(DayOutOfRangeErr(self))
+    }
+
+    /// Index of `date`, dates before the project start mapping to day `0`.
+    pub fn from_date(date: NaiveDate, project_start: NaiveDate) -> DayIndex {
+        DayIndex((date - project_start).num_days().max(0) as usize)
+    }
+}
+
+impl fmt::Display for DayIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Days on which sites are worked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calendar {
+    /// Date of day index `0`, which site `start_day`s are counted from.
+    pub project_start: NaiveDate,
+    pub working_weekdays: Vec<Weekday>,
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar {
+            project_start: NaiveDate::from_ymd_opt(2024, 1, 1).expect("valid date"),
+            working_weekdays: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl Calendar {
+    pub fn is_working_day(&self, date: NaiveDate) -> bool {
+        self.working_weekdays.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    pub fn date_of_day(&self, day: DayIndex) -> Result<NaiveDate, DayOutOfRangeErr> {
+        day.to_date(self.project_start)
+    }
+
+    pub fn day_of_date(&self, date: NaiveDate) -> DayIndex {
+        DayIndex::from_date(date, self.project_start)
+    }
+
+    /// The first `count` working half-days from `from`, included if it is worked. Fewer when
+    /// the last representable date comes first.
+    pub fn working_periods(
+        &self,
+        from: (NaiveDate, DayPeriod),
+        count: usize,
+    ) -> Vec<(NaiveDate, DayPeriod)> {
+        if self.working_weekdays.is_empty() {
+            return Vec::new();
+        }
+
+        std::iter::successors(Some(from), |(date, period)| {
+            let (days, next) = period.advance();
+            Some((date.checked_add_signed(TimeDelta::days(days))?, next))
+        })
+        .filter(|(date, _)| self.is_working_day(*date))
+        .take(count)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub working: bool,
+    pub periods: [LabeledPeriod; 2],
+}
+
+impl CalendarDay {
+    pub fn new(date: NaiveDate, calendar: &Calendar, labels: &PeriodLabels) -> Self {
+        CalendarDay {
+            date,
+            working: calendar.is_working_day(date),
+            periods: [DayPeriod::Morning, DayPeriod::Afternoon]
+                .map(|period| labels.labeled(period)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn day_indices_map_to_dates_across_a_month_boundary() {
+        let start = date("2024-01-30");
+
+        assert_eq!(DayIndex(0).to_date(start), Ok(date("2024-01-30")));
+        assert_eq!(DayIndex(1).to_date(start), Ok(date("2024-01-31")));
+        assert_eq!(DayIndex(2).to_date(start), Ok(date("2024-02-01")));
+        // 2024 is a leap year.
+        assert_eq!(DayIndex(31).to_date(start), Ok(date("2024-03-01")));
+    }
+
+    #[test]
+    fn dates_map_back_to_their_day_index() {
+        let start = date("2024-01-30");
+
+        for day in [0, 1, 2, 31, 400] {
+            let date = DayIndex(day).to_date(start).unwrap();
+            assert_eq!(DayIndex::from_date(date, start), DayIndex(day));
+        }
+        assert_eq!(DayIndex::from_date(date("2024-01-01"), start), DayIndex(0));
+    }
+
+    #[test]
+    fn days_past_the_last_date_are_out_of_range() {
+        let day = DayIndex(usize::MAX);
+
+        assert_eq!(day.to_date(date("2024-01-30")), Err(DayOutOfRangeErr(day)));
+    }
+}
@@ -0,0 +1,94 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+
+use crate::config::AppConfig;
+
+/// Address of the client that made the request, read from `X-Forwarded-For` when the
+/// request came through one of the configured `trusted_proxies`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Walks `forwarded_for` back from the proxy that reached us, and stops on the first hop that is
+/// not a trusted proxy: hops before it could have been forged by the client.
+pub fn resolve<'a>(
+    remote: IpAddr,
+    forwarded_for: impl IntoIterator<Item = &'a str>,
+    trusted_proxies: &[IpNet],
+) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+    if !is_trusted(&remote) {
+        return remote;
+    }
+
+    let hops: Vec<IpAddr> = forwarded_for
+        .into_iter()
+        .flat_map(|header| header.split(','))
+        .map_while(|hop| hop.trim().parse().ok())
+        .collect();
+    let mut client = remote;
+    for hop in hops.into_iter().rev() {
+        client = hop;
+        if !is_trusted(&hop) {
+            break;
+        }
+    }
+    client
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(remote) = request.remote().map(|addr| addr.ip()) else {
+            return Outcome::Forward(Status::InternalServerError);
+        };
+        let trusted_proxies = request
+            .rocket()
+            .state::<AppConfig>()
+            .map(|config| config.trusted_proxies.as_slice())
+            .unwrap_or_default();
+        let forwarded_for = request.headers().get("X-Forwarded-For");
+
+        Outcome::Success(ClientIp(resolve(remote, forwarded_for, trusted_proxies)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn proxies() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap()]
+    }
+
+    #[test]
+    fn forwarded_addresses_are_ignored_from_untrusted_peers() {
+        let client = resolve(ip("203.0.113.7"), ["198.51.100.1"], &proxies());
+
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn the_last_untrusted_hop_is_the_client() {
+        // The client forged the first hop, which comes before the one our proxy saw.
+        let forwarded = ["198.51.100.1, 203.0.113.7", "10.0.0.2"];
+
+        assert_eq!(
+            resolve(ip("10.0.0.1"), forwarded, &proxies()),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            resolve(ip("10.0.0.1"), ["10.0.0.3"], &proxies()),
+            ip("10.0.0.3")
+        );
+        assert_eq!(resolve(ip("10.0.0.1"), [], &proxies()), ip("10.0.0.1"));
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{NaiveTime, TimeDelta};
+use ipnet::IpNet;
+use rocket::fairing::AdHoc;
+use rocket::http::ContentType;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AppRole;
+use crate::calendar::Calendar;
+use crate::github::RetryConfig;
+use crate::sites::DayPeriod;
+use crate::storage::StorageFormat;
+
+/// Application settings, read from `Rocket.toml` / `ROCKET_*` environment variables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub calendar: Calendar,
+    /// Seconds between two overdue sites scans, `0` disables the scan.
+    pub overdue_check_interval_secs: u64,
+    /// Role given to users on their first login.
+    pub default_role: AppRole,
+    pub github: GitHubConfig,
+    pub period_labels: PeriodLabels,
+    pub work_hours: WorkHours,
+    /// Proxies, e.g. `["10.0.0.0/8"]`, whose `X-Forwarded-For` header is trusted.
+    pub trusted_proxies: Vec<IpNet>,
+    /// Encoding of the state when it is stored.
+    pub storage_format: StorageFormat,
+    /// File the sites, the fleet, the workers and the blackouts are saved to on shutdown and
+    /// loaded from on startup, if any.
+    pub sites_path: Option<PathBuf>,
+    /// What `/` answers to a browser that is not logged in. JSON clients always get a 401.
+    pub anonymous_landing: AnonymousLanding,
+    /// Whether assigning a worker without a site's required certifications is refused.
+    pub certification_check: CertificationCheck,
+    /// Whether the user creating a site is added to its managers.
+    pub creator_manages_site: bool,
+    /// Minutes of work time required between two reservations of a vehicle, e.g. to bring
+    /// it from one site to another. `0` allows back-to-back reservations.
+    pub turnaround_buffer_mins: u32,
+    /// Sites not completed a user may manage at once, enforced when adding managers through
+    /// `POST /sites/<id>/managers`. `None` for no limit.
+    pub max_sites_per_manager: Option<usize>,
+    /// Calendar days from the project start within which sites must be planned, e.g. so that a
+    /// typo in `start_day` or `duration` does not plan centuries of half-days.
+    pub planning_horizon_days: usize,
+    /// Longest a single vehicle reservation, hold or waitlisted request may span, in calendar
+    /// days, to catch typos in the year.
+    pub max_reservation_days: u32,
+    /// Key signing the links of `POST /sites/<id>/share`, which is refused while unset.
+    /// Changing it revokes every link handed out.
+    #[serde(skip_serializing)]
+    pub share_secret: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            calendar: Calendar::default(),
+            overdue_check_interval_secs: 3600,
+            default_role: AppRole::SiteManager,
+            github: GitHubConfig::default(),
+            period_labels: PeriodLabels::default(),
+            work_hours: WorkHours::default(),
+            trusted_proxies: Vec::new(),
+            storage_format: StorageFormat::default(),
+            sites_path: None,
+            anonymous_landing: AnonymousLanding::default(),
+            certification_check: CertificationCheck::default(),
+            creator_manages_site: true,
+            turnaround_buffer_mins: 0,
+            max_sites_per_manager: None,
+            planning_horizon_days: 3650,
+            max_reservation_days: 730,
+            share_secret: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnonymousLanding {
+    /// A message pointing to `/login/github`.
+    #[default]
+    Prompt,
+    /// A redirect to `/login/github`.
+    Redirect,
+    /// A 401 error.
+    Unauthorized,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CertificationCheck {
+    /// The assignment is refused.
+    #[default]
+    Block,
+    /// The assignment is made, with a warning.
+    Warn,
+}
+
+impl AppConfig {
+    /// Effective settings worth checking after a deployment, as `(name, value)` pairs.
+    ///
+    /// Holds no secret: the OAuth credentials and Rocket's `secret_key` are not part of
+    /// `AppConfig`, and `share_secret` is only said to be set or not. Keep it that way when
+    /// adding fields shown here.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let hours = &self.work_hours;
+        vec![
+            (
+                "calendar",
+                format!(
+                    "starts {}, works {:?}, {} holidays",
+                    self.calendar.project_start,
+                    self.calendar.working_weekdays,
+                    self.calendar.holidays.len()
+                ),
+            ),
+            (
+                "work hours",
+                format!(
+                    "{}-{} and {}-{}",
+                    hours.morning_start,
+                    hours.morning_end,
+                    hours.afternoon_start,
+                    hours.afternoon_end
+                ),
+            ),
+            ("default role", format!("{:?}", self.default_role)),
+            (
+                "overdue check",
+                match self.overdue_check_interval_secs {
+                    0 => "disabled".to_string(),
+                    secs => format!("every {secs}s"),
+                },
+            ),
+            (
+                "github",
+                format!(
+                    "{}, {}ms timeout, {} attempts",
+                    self.github.api_url, self.github.timeout_ms, self.github.retry.attempts
+                ),
+            ),
+            ("trusted proxies", format!("{:?}", self.trusted_proxies)),
+            (
+                "storage",
+                match &self.sites_path {
+                    Some(path) => format!("{:?} at {}", self.storage_format, path.display()),
+                    None => "in memory only".to_string(),
+                },
+            ),
+            ("anonymous landing", format!("{:?}", self.anonymous_landing)),
+            (
+                "certification check",
+                format!("{:?}", self.certification_check),
+            ),
+            (
+                "creator manages site",
+                self.creator_manages_site.to_string(),
+            ),
+            (
+                "turnaround buffer",
+                match self.turnaround_buffer_mins {
+                    0 => "none".to_string(),
+                    mins => format!("{mins}min"),
+                },
+            ),
+            (
+                "max sites per manager",
+                match self.max_sites_per_manager {
+                    Some(max) => max.to_string(),
+                    None => "unlimited".to_string(),
+                },
+            ),
+            (
+                "planning horizon",
+                format!("{} days", self.planning_horizon_days),
+            ),
+            (
+                "max reservation span",
+                format!("{} days", self.max_reservation_days),
+            ),
+            (
+                "share links",
+                match self.share_secret {
+                    Some(_) => "enabled".to_string(),
+                    None => "disabled, no share_secret".to_string(),
+                },
+            ),
+        ]
+    }
+}
+
+/// Logs the effective [`AppConfig`] summary and the request size limits at liftoff.
+pub fn banner() -> AdHoc {
+    AdHoc::on_liftoff("Configuration banner", |rocket| {
+        Box::pin(async move {
+            let Some(config) = rocket.state::<AppConfig>() else {
+                return;
+            };
+            log::info!("Effective configuration:");
+            for (name, value) in config.summary() {
+                log::info!("  {name}: {value}");
+            }
+            let limits = &rocket.config().limits;
+            for limit in ["json", "string", "bytes"] {
+                if let Some(size) = limits.get(limit) {
+                    log::info!("  {limit} limit: {size}");
+                }
+            }
+        })
+    })
+}
+
+/// GitHub API settings; the OAuth app itself is configured under `oauth.github`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GitHubConfig {
+    pub api_url: String,
+    pub timeout_ms: u64,
+    pub retry: RetryConfig,
+}
+
+impl GitHubConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        GitHubConfig {
+            api_url: "https://api.github.com".to_string(),
+            timeout_ms: 5000,
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+/// Adds the configured display label of their periods, `start_period_label` and
+/// `end_period_label`, to the reservations of every JSON response.
+pub fn period_labels() -> AdHoc {
+    AdHoc::on_response("Period labels", |request, response| {
+        Box::pin(async move {
+            let Some(config) = request.rocket().state::<AppConfig>() else {
+                return;
+            };
+            if response.content_type() != Some(ContentType::JSON) {
+                return;
+            }
+            let Ok(body) = response.body_mut().to_bytes().await else {
+                return;
+            };
+            let labeled = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|mut value| {
+                    config.period_labels.label_reservations(&mut value);
+                    serde_json::to_vec(&value).ok()
+                })
+                .unwrap_or(body);
+            response.set_sized_body(labeled.len(), Cursor::new(labeled));
+        })
+    })
+}
+
+/// Display label of each half-day, e.g. `Morning = "Matin"`. Missing periods keep their English label.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PeriodLabels(pub BTreeMap<DayPeriod, String>);
+
+impl PeriodLabels {
+    pub fn label(&self, period: DayPeriod) -> &str {
+        self.0
+            .get(&period)
+            .map(String::as_str)
+            .unwrap_or(match period {
+                DayPeriod::Morning => "Morning",
+                DayPeriod::Afternoon => "Afternoon",
+            })
+    }
+
+    /// Adds `start_period_label` and `end_period_label` next to the periods of every
+    /// reservation in `value`, i.e. every object with dates and periods at both ends.
+    pub fn label_reservations(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Array(values) => {
+                values
+                    .iter_mut()
+                    .for_each(|value| self.label_reservations(value));
+            }
+            serde_json::Value::Object(fields) => {
+                fields
+                    .values_mut()
+                    .for_each(|value| self.label_reservations(value));
+                let period = |field| {
+                    fields
+                        .get(field)
+                        .and_then(|period| DayPeriod::deserialize(period).ok())
+                };
+                let (Some(start), Some(end)) = (period("start_period"), period("end_period"))
+                else {
+                    return;
+                };
+                if !fields.contains_key("start_date") || !fields.contains_key("end_date") {
+                    return;
+                }
+                let labeled = std::mem::take(fields)
+                    .into_iter()
+                    .flat_map(|(field, value)| {
+                        let label = match field.as_str() {
+                            "start_period" => Some(("start_period_label", start)),
+                            "end_period" => Some(("end_period_label", end)),
+                            _ => None,
+                        }
+                        .map(|(name, period)| (name.to_owned(), self.label(period).into()));
+                        std::iter::once((field, value)).chain(label)
+                    });
+                *fields = labeled.collect();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn labeled(&self, period: DayPeriod) -> LabeledPeriod {
+        LabeledPeriod {
+            period,
+            label: self.label(period).to_owned(),
+        }
+    }
+}
+
+/// A half-day as shown in responses: `period` is the value to send back, `label` is for display.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabeledPeriod {
+    pub period: DayPeriod,
+    pub label: String,
+}
+
+/// When work starts and stops in each half-day, e.g. `morning_start = "07:30:00"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkHours {
+    pub morning_start: NaiveTime,
+    pub morning_end: NaiveTime,
+    pub afternoon_start: NaiveTime,
+    pub afternoon_end: NaiveTime,
+}
+
+impl WorkHours {
+    /// When work starts and stops during `period`.
+    pub fn bounds(&self, period: DayPeriod) -> (NaiveTime, NaiveTime) {
+        match period {
+            DayPeriod::Morning => (self.morning_start, self.morning_end),
+            DayPeriod::Afternoon => (self.afternoon_start, self.afternoon_end),
+        }
+    }
+
+    /// Time worked during `period`, zero if it ends before it starts.
+    pub fn length_of(&self, period: DayPeriod) -> TimeDelta {
+        let (start, end) = self.bounds(period);
+        (end - start).max(TimeDelta::zero())
+    }
+}
+
+impl Default for WorkHours {
+    fn default() -> Self {
+        let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).expect("valid hour");
+        WorkHours {
+            morning_start: at(8),
+            morning_end: at(12),
+            afternoon_start: at(13),
+            afternoon_end: at(17),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share_links(config: &AppConfig) -> String {
+        config
+            .summary()
+            .into_iter()
+            .find(|(name, _)| *name == "share links")
+            .map(|(_, value)| value)
+            .expect("a share links entry")
+    }
+
+    #[test]
+    fn the_summary_never_shows_the_share_secret() {
+        let secret = "a-very-secret-key";
+        let config = AppConfig {
+            share_secret: Some(secret.to_owned()),
+            ..AppConfig::default()
+        };
+
+        assert!(config
+            .summary()
+            .iter()
+            .all(|(name, value)| !name.contains(secret) && !value.contains(secret)));
+        assert_eq!(share_links(&config), "enabled");
+        assert_eq!(
+            share_links(&AppConfig::default()),
+            "disabled, no share_secret"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::blackouts::Blackout;
+use crate::calendar::DayIndex;
+use crate::fleet::ConflictKind;
+use crate::sites::{DayPeriod, ReservedDate, SiteId};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DateParsedErr {
+    #[error("Start date is not a valid YYYY-MM-DD date")]
+    InvalidStartDate,
+    #[error("End date is not a valid YYYY-MM-DD date")]
+    InvalidEndDate,
+    #[error("Start date is after end date")]
+    StartAfterEnd,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlreadyReservedInThatPeriodErr {
+    pub reserved: ReservedDate,
+    pub kind: ConflictKind,
+    /// Half-days both the requested and the existing reservation cover.
+    pub overlapping: Vec<(NaiveDate, DayPeriod)>,
+}
+
+/// The requested period intersects a maintenance window scheduled for the vehicle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VehicleUnderMaintenanceErr {
+    pub window: ReservedDate,
+}
+
+/// The requested period intersects a fleet-wide blackout.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[error("No vehicle can be reserved during the blackout {}", blackout.id)]
+pub struct BlackoutErr {
+    pub blackout: Blackout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ReservationErr {
+    #[error("The vehicle is already reserved in that period")]
+    AlreadyReserved(Box<AlreadyReservedInThatPeriodErr>),
+    #[error("The vehicle is in maintenance")]
+    VehicleInMaintenance,
+    #[error("The vehicle has maintenance scheduled in that period")]
+    UnderMaintenance(VehicleUnderMaintenanceErr),
+    #[error("The site is completed")]
+    SiteCompleted,
+    #[error(transparent)]
+    Blackout(#[from] BlackoutErr),
+    #[error("The reservation spans {days} days, more than the {max} allowed")]
+    TooLong { days: i64, max: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ShortenErr {
+    /// The vehicle has no reservation over the given half-days.
+    #[error("The vehicle is not reserved in that period")]
+    NotReserved,
+    /// The new end is after the reservation's current end.
+    #[error("The new end is after the end of the reservation")]
+    NotShorter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HoldErr {
+    /// The vehicle has no such hold, or it expired.
+    #[error("The vehicle has no such hold, or it expired")]
+    NotHeld,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Day {0} is too far from the project start to be a date")]
+pub struct DayOutOfRangeErr(pub DayIndex);
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EstimateErr {
+    #[error("No site with id {0}")]
+    UnknownSite(SiteId),
+    #[error("Site {0} is part of a dependency cycle")]
+    DependencyCycle(SiteId),
+    #[error(transparent)]
+    DayOutOfRange(#[from] DayOutOfRangeErr),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ShareErr {
+    /// Malformed, or not signed with the configured secret.
+    #[error("The share link is invalid")]
+    Invalid,
+    #[error("The share link expired at {0}")]
+    Expired(DateTime<Utc>),
+}
+
+/// Any error of the sites domain, for code dealing with several kinds of them.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SiteError {
+    #[error(transparent)]
+    Date(#[from] DateParsedErr),
+    #[error(transparent)]
+    DayOutOfRange(#[from] DayOutOfRangeErr),
+    #[error(transparent)]
+    Reservation(#[from] ReservationErr),
+    #[error(transparent)]
+    Shorten(#[from] ShortenErr),
+    #[error(transparent)]
+    Hold(#[from] HoldErr),
+    #[error(transparent)]
+    Estimate(#[from] EstimateErr),
+    #[error(transparent)]
+    Share(#[from] ShareErr),
+}
+
+pub type Result<T, E = SiteError> = std::result::Result<T, E>;
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::calendar::Calendar;
+use crate::error::EstimateErr;
+use crate::sites::{DayPeriod, Site, SiteId, SiteStatus};
+
+/// When a site is expected to be worked, its dependencies taken into account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiteEstimate {
+    pub site_id: SiteId,
+    pub start: (NaiveDate, DayPeriod),
+    pub end: (NaiveDate, DayPeriod),
+}
+
+/// `ids` and the sites they transitively depend on, each after its dependencies.
+fn dependency_order(
+    sites: &HashMap<SiteId, Site>,
+    ids: &[SiteId],
+) -> Result<Vec<SiteId>, EstimateErr> {
+    fn visit(
+        sites: &HashMap<SiteId, Site>,
+        id: SiteId,
+        visiting: &mut HashSet<SiteId>,
+        order: &mut Vec<SiteId>,
+    ) -> Result<(), EstimateErr> {
+        if order.contains(&id) {
+            return Ok(());
+        }
+        if !visiting.insert(id) {
+            return Err(EstimateErr::DependencyCycle(id));
+        }
+        let site = sites.get(&id).ok_or(EstimateErr::UnknownSite(id))?;
+        for dependency in &site.depends_on {
+            visit(sites, *dependency, visiting, order)?;
+        }
+        visiting.remove(&id);
+        order.push(id);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    for id in ids {
+        visit(sites, *id, &mut HashSet::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Estimates `ids` and every site they depend on, in dependency order.
+///
+/// A site starts on its start day, or on the half-day after its last unfinished dependency
+/// ends if that is later, and lasts its duration in working half-days. Completed sites keep
+/// their planned window and hold nothing back; sites without any working half-day are left out
+/// and hold nothing back either.
+pub fn estimate(
+    sites: &HashMap<SiteId, Site>,
+    ids: &[SiteId],
+    calendar: &Calendar,
+) -> Result<Vec<SiteEstimate>, EstimateErr> {
+    let mut estimates: HashMap<SiteId, SiteEstimate> = HashMap::new();
+    let mut ordered = Vec::new();
+
+    for id in dependency_order(sites, ids)? {
+        let site = &sites[&id];
+        let planned = (calendar.date_of_day(site.start_day)?, DayPeriod::Morning);
+        let start = if site.status == SiteStatus::Completed {
+            planned
+        } else {
+            site.depends_on
+                .iter()
+                .filter(|dependency| sites[dependency].status != SiteStatus::Completed)
+                .filter_map(|dependency| estimates.get(dependency))
+                .filter_map(|dependency| {
+                    let (date, period) = dependency.end;
+                    let (days, next) = period.advance();
+                    Some((date.checked_add_signed(TimeDelta::days(days))?, next))
+                })
+                .fold(planned, Ord::max)
+        };
+
+        let periods = calendar.working_periods(start, site.duration.half_day);
+        if let (Some(first), Some(last)) = (periods.first(), periods.last()) {
+            let estimate = SiteEstimate {
+                site_id: id,
+                start: *first,
+                end: *last,
+            };
+            estimates.insert(id, estimate.clone());
+            ordered.push(estimate);
+        }
+    }
+    Ok(ordered)
+}
@@ -0,0 +1,750 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Duration, NaiveDate, TimeDelta, Utc};
+use rocket::http::Status;
+use rocket::request::{FromParam, FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::blackouts::{Blackout, Blackouts};
+use crate::config::{AppConfig, WorkHours};
+use crate::error::{
+    AlreadyReservedInThatPeriodErr, HoldErr, ReservationErr, Result, ShortenErr,
+    VehicleUnderMaintenanceErr,
+};
+use crate::sites::{DayPeriod, HalfDayIter, ReservedDate, SiteId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct VehicleId(pub u32);
+
+impl fmt::Display for VehicleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for VehicleId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(VehicleId)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HoldId(pub u32);
+
+impl fmt::Display for HoldId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for HoldId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(HoldId)
+    }
+}
+
+/// Fleet-wide constraints reservations are checked against, gathered for each request.
+#[derive(Debug, Clone, Default)]
+pub struct ReservationRules {
+    pub blackouts: Vec<Blackout>,
+    /// Minimum work time between two reservations of a vehicle.
+    pub turnaround_buffer: TimeDelta,
+    pub work_hours: WorkHours,
+    /// Most calendar days a reservation may span, `None` for no limit.
+    pub max_span_days: Option<u32>,
+}
+
+impl ReservationRules {
+    pub fn new(blackouts: Vec<Blackout>, config: &AppConfig) -> Self {
+        ReservationRules {
+            blackouts,
+            turnaround_buffer: TimeDelta::minutes(i64::from(config.turnaround_buffer_mins)),
+            work_hours: config.work_hours,
+            max_span_days: Some(config.max_reservation_days),
+        }
+    }
+
+    /// Checks that `date` does not span more days than allowed.
+    pub fn check_span(&self, date: &ReservedDate) -> Result<(), ReservationErr> {
+        match self.max_span_days {
+            Some(max) if date.span_days() > i64::from(max) => Err(ReservationErr::TooLong {
+                days: date.span_days(),
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ReservationRules {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let rocket = request.rocket();
+        match (rocket.state::<Blackouts>(), rocket.state::<AppConfig>()) {
+            (Some(blackouts), Some(config)) => {
+                Outcome::Success(ReservationRules::new(blackouts.current(), config))
+            }
+            _ => Outcome::Error((Status::InternalServerError, ())),
+        }
+    }
+}
+
+/// How badly a reservation overlaps an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// One of the reservations covers the other entirely.
+    Full,
+    /// They share some half-days, and each also has half-days of its own.
+    Partial,
+    /// They only share a single half-day, the last of one being the first of the other.
+    Touching,
+    /// They share no half-day, but leave less than the turnaround buffer between them.
+    TooClose,
+}
+
+impl ConflictKind {
+    /// Relationship between two reservations sharing at least one half-day.
+    pub fn between(requested: &ReservedDate, reserved: &ReservedDate) -> ConflictKind {
+        let (requested_start, requested_end) = requested.half_day_span();
+        let (reserved_start, reserved_end) = reserved.half_day_span();
+
+        if (requested_start >= reserved_start && requested_end <= reserved_end)
+            || (reserved_start >= requested_start && reserved_end <= requested_end)
+        {
+            ConflictKind::Full
+        } else if requested_end == reserved_start || reserved_end == requested_start {
+            ConflictKind::Touching
+        } else {
+            ConflictKind::Partial
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, FromFormField,
+)]
+pub enum VehicleKind {
+    Excavator,
+    Bulldozer,
+    Loader,
+    Crane,
+    Truck,
+    ConcreteMixer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vehicle {
+    pub id: VehicleId,
+    pub name: String,
+    pub kind: VehicleKind,
+    #[serde(default)]
+    pub reserved_dates: Vec<ReservedDate>,
+    /// A vehicle in maintenance cannot be reserved.
+    #[serde(default)]
+    pub in_maintenance: bool,
+    /// Planned maintenance, sorted like `reserved_dates`, in which the vehicle cannot be reserved.
+    #[serde(default)]
+    pub maintenance_windows: Vec<ReservedDate>,
+    /// Requests waiting for the vehicle to be free, first queued first.
+    #[serde(default)]
+    pub waitlist: Vec<WaitlistEntry>,
+    /// Tentative reservations, blocking the vehicle like reservations until they expire.
+    #[serde(default)]
+    pub holds: Vec<Hold>,
+    /// Id of the latest hold, stored so that ids of confirmed, released or expired holds are
+    /// never given again, even after a restart.
+    #[serde(default)]
+    pub last_hold_id: u32,
+}
+
+/// A slot kept for a booking in progress, see [`Vehicle::hold`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hold {
+    pub id: HoldId,
+    pub reservation: ReservedDate,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A reservation to make once the vehicle is free over its period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaitlistEntry {
+    pub reservation: ReservedDate,
+    pub queued_at: DateTime<Utc>,
+}
+
+/// A reservation given up, and the waitlisted requests that could be reserved in its place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Release {
+    pub released: ReservedDate,
+    pub served: Vec<WaitlistEntry>,
+}
+
+impl Vehicle {
+    /// A vehicle out of maintenance, without any reservation.
+    pub fn new(id: VehicleId, name: String, kind: VehicleKind) -> Self {
+        Vehicle {
+            id,
+            name,
+            kind,
+            reserved_dates: Vec::new(),
+            in_maintenance: false,
+            maintenance_windows: Vec::new(),
+            waitlist: Vec::new(),
+            holds: Vec::new(),
+            last_hold_id: 0,
+        }
+    }
+
+    /// Checks that `date` could be reserved under the fleet-wide `rules`, without reserving it.
+    pub fn check_available(
+        &self,
+        date: &ReservedDate,
+        rules: &ReservationRules,
+    ) -> Result<(), ReservationErr> {
+        rules.check_span(date)?;
+        Blackout::check(&rules.blackouts, date)?;
+        if self.in_maintenance {
+            return Err(ReservationErr::VehicleInMaintenance);
+        }
+        if let Some(window) = self
+            .maintenance_windows
+            .iter()
+            .find(|window| !window.compatible_with(date))
+        {
+            return Err(ReservationErr::UnderMaintenance(
+                VehicleUnderMaintenanceErr {
+                    window: window.clone(),
+                },
+            ));
+        }
+        self.check_not_reserved(date, rules)
+    }
+
+    /// Checks that `date` intersects no reservation, nor any hold, nor comes closer to one
+    /// than the turnaround buffer of `rules`.
+    fn check_not_reserved(
+        &self,
+        date: &ReservedDate,
+        rules: &ReservationRules,
+    ) -> Result<(), ReservationErr> {
+        match self
+            .reserved_dates
+            .iter()
+            .chain(self.holds.iter().map(|hold| &hold.reservation))
+            .find(|reserved| {
+                !reserved.compatible_with_buffer(date, rules.turnaround_buffer, &rules.work_hours)
+            }) {
+            Some(reserved) => Err(ReservationErr::AlreadyReserved(Box::new(
+                AlreadyReservedInThatPeriodErr {
+                    reserved: reserved.clone(),
+                    kind: if reserved.compatible_with(date) {
+                        ConflictKind::TooClose
+                    } else {
+                        ConflictKind::between(date, reserved)
+                    },
+                    overlapping: date.overlapping_half_days(reserved),
+                },
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn reserve(
+        &mut self,
+        date: ReservedDate,
+        rules: &ReservationRules,
+    ) -> Result<(), ReservationErr> {
+        self.check_available(&date, rules)?;
+        self.insert_reservation(date);
+        Ok(())
+    }
+
+    /// Drops the holds expired at `now`.
+    pub fn prune_holds(&mut self, now: DateTime<Utc>) {
+        self.holds.retain(|hold| hold.expires_at > now);
+    }
+
+    /// Keeps `date` for `ttl` from `now`: other reservations and holds are refused over it
+    /// until the hold is confirmed, released or expires.
+    pub fn hold(
+        &mut self,
+        date: ReservedDate,
+        ttl: Duration,
+        now: DateTime<Utc>,
+        rules: &ReservationRules,
+    ) -> Result<Hold, ReservationErr> {
+        self.prune_holds(now);
+        self.check_available(&date, rules)?;
+        self.last_hold_id += 1;
+        let hold = Hold {
+            id: HoldId(self.last_hold_id),
+            reservation: date,
+            expires_at: now + ttl,
+        };
+        self.holds.push(hold.clone());
+        Ok(hold)
+    }
+
+    /// Turns the hold `id` into a reservation, checked again in case the vehicle became
+    /// unavailable, e.g. went into maintenance, since it was made.
+    pub fn confirm_hold(
+        &mut self,
+        id: HoldId,
+        now: DateTime<Utc>,
+        rules: &ReservationRules,
+    ) -> Result<ReservedDate> {
+        self.prune_holds(now);
+        let index = self
+            .holds
+            .iter()
+            .position(|hold| hold.id == id)
+            .ok_or(HoldErr::NotHeld)?;
+        let hold = self.holds.remove(index);
+        if let Err(err) = self.reserve(hold.reservation.clone(), rules) {
+            self.holds.insert(index, hold);
+            return Err(err.into());
+        }
+        Ok(hold.reservation)
+    }
+
+    /// Gives up the hold `id`, `None` when there is no such hold or it expired.
+    pub fn release_hold(&mut self, id: HoldId, now: DateTime<Utc>) -> Option<Hold> {
+        self.prune_holds(now);
+        let index = self.holds.iter().position(|hold| hold.id == id)?;
+        Some(self.holds.remove(index))
+    }
+
+    /// Moves the end of the reservation covering the same half-days as `original` back to
+    /// `new_end_date` `new_end_period`, keeping its start, site, author and note.
+    pub fn shorten_reservation(
+        &mut self,
+        original: &ReservedDate,
+        new_end_date: &str,
+        new_end_period: DayPeriod,
+    ) -> Result<()> {
+        let span = original.half_day_span();
+        let reserved = self
+            .reserved_dates
+            .iter_mut()
+            .find(|reserved| reserved.half_day_span() == span)
+            .ok_or(ShortenErr::NotReserved)?;
+
+        let shortened = ReservedDate::new(
+            &reserved.first_date().to_string(),
+            reserved.start_period,
+            new_end_date,
+            new_end_period,
+        )?;
+        if shortened.half_day_span().1 > span.1 {
+            return Err(ShortenErr::NotShorter.into());
+        }
+        // The start is unchanged, so `reserved_dates` stays sorted.
+        reserved.end_date = shortened.end_date;
+        reserved.end_period = shortened.end_period;
+        Ok(())
+    }
+
+    /// Cancels the reservation covering the same half-days as `reservation`, then reserves
+    /// the waitlisted requests that now fit, see [`Vehicle::serve_waitlist`]. `None` when
+    /// there is no such reservation.
+    pub fn release(
+        &mut self,
+        reservation: &ReservedDate,
+        rules: &ReservationRules,
+    ) -> Option<Release> {
+        let span = reservation.half_day_span();
+        let index = self
+            .reserved_dates
+            .iter()
+            .position(|reserved| reserved.half_day_span() == span)?;
+        let released = self.reserved_dates.remove(index);
+        Some(Release {
+            released,
+            served: self.serve_waitlist(rules),
+        })
+    }
+
+    /// Cancels every reservation, hold and waitlisted request made for `site_id`. Returns the
+    /// number of reservations cancelled.
+    pub fn clear_site(&mut self, site_id: SiteId) -> usize {
+        let before = self.reserved_dates.len();
+        self.reserved_dates
+            .retain(|reserved| reserved.site_id != Some(site_id));
+        self.drop_pending(site_id);
+        before - self.reserved_dates.len()
+    }
+
+    /// Drops the holds and waitlisted requests made for `site_id`, keeping its reservations.
+    pub fn drop_pending(&mut self, site_id: SiteId) {
+        self.holds
+            .retain(|hold| hold.reservation.site_id != Some(site_id));
+        self.waitlist
+            .retain(|entry| entry.reservation.site_id != Some(site_id));
+    }
+
+    /// Reserves the waitlisted requests the vehicle is now available for, in the order they
+    /// were queued, a served request possibly taking the place of a later one. Returns the
+    /// served entries.
+    pub fn serve_waitlist(&mut self, rules: &ReservationRules) -> Vec<WaitlistEntry> {
+        let mut served = Vec::new();
+        let mut waiting = Vec::new();
+        for entry in std::mem::take(&mut self.waitlist) {
+            if self.reserve(entry.reservation.clone(), rules).is_ok() {
+                served.push(entry);
+            } else {
+                waiting.push(entry);
+            }
+        }
+        self.waitlist = waiting;
+        served
+    }
+
+    /// Plans maintenance over `window`, refused when it intersects a reservation or comes
+    /// closer to one than the turnaround buffer of `rules`.
+    pub fn schedule_maintenance(
+        &mut self,
+        window: ReservedDate,
+        rules: &ReservationRules,
+    ) -> Result<(), ReservationErr> {
+        self.check_not_reserved(&window, rules)?;
+        let index = self
+            .maintenance_windows
+            .partition_point(|scheduled| *scheduled <= window);
+        self.maintenance_windows.insert(index, window);
+        Ok(())
+    }
+
+    /// Takes the vehicle out of maintenance, then reserves the waitlisted requests that now
+    /// fit, see [`Vehicle::serve_waitlist`]. Its planned maintenance windows are kept.
+    pub fn end_maintenance(&mut self, rules: &ReservationRules) -> Vec<WaitlistEntry> {
+        self.in_maintenance = false;
+        self.serve_waitlist(rules)
+    }
+
+    /// Adds `date` without any availability check, keeping `reserved_dates` sorted.
+    pub fn insert_reservation(&mut self, date: ReservedDate) {
+        let index = self
+            .reserved_dates
+            .partition_point(|reserved| *reserved <= date);
+        self.reserved_dates.insert(index, date);
+    }
+
+    /// Share of the half-days between the dates of `from` and `to`, both included, in which
+    /// the vehicle is reserved.
+    pub fn utilization(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        let slots: Vec<ReservedDate> = HalfDayIter::days(from.date_naive(), to.date_naive())
+            .map(|(date, period)| ReservedDate::half_day(date, period))
+            .collect();
+        if slots.is_empty() {
+            return 0.0;
+        }
+        let reserved = slots
+            .iter()
+            .filter(|slot| {
+                self.reserved_dates
+                    .iter()
+                    .any(|reserved| !reserved.compatible_with(slot))
+            })
+            .count();
+        reserved as f64 / slots.len() as f64
+    }
+
+    /// Periods of `date` in which the vehicle could be reserved.
+    pub fn free_periods_on(&self, date: NaiveDate, rules: &ReservationRules) -> Vec<DayPeriod> {
+        HalfDayIter::days(date, date)
+            .filter(|(date, period)| {
+                self.check_available(&ReservedDate::half_day(*date, *period), rules)
+                    .is_ok()
+            })
+            .map(|(_, period)| period)
+            .collect()
+    }
+}
+
+/// Outcome of putting a vehicle into maintenance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    pub moved: Vec<MovedReservation>,
+    pub cancelled: Vec<ReservedDate>,
+    /// Reservations no other vehicle could take, left on the vehicle for manual handling.
+    pub failed: Vec<ReservedDate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MovedReservation {
+    pub reservation: ReservedDate,
+    pub to: VehicleId,
+}
+
+/// Registry of every vehicle, shared by the sites referencing them.
+#[derive(Debug, Clone, Default)]
+pub struct Fleet(Arc<Mutex<HashMap<VehicleId, Vehicle>>>);
+
+impl Fleet {
+    /// Locks the fleet. When the sites are needed too, lock them first.
+    pub fn lock(&self) -> MutexGuard<'_, HashMap<VehicleId, Vehicle>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the registry like [`Fleet::lock`], first dropping the holds expired at `now`.
+    /// Use it whenever availability matters.
+    pub fn lock_pruned(&self, now: DateTime<Utc>) -> MutexGuard<'_, HashMap<VehicleId, Vehicle>> {
+        let mut vehicles = self.lock();
+        for vehicle in vehicles.values_mut() {
+            vehicle.prune_holds(now);
+        }
+        vehicles
+    }
+
+    pub fn next_id(vehicles: &HashMap<VehicleId, Vehicle>) -> VehicleId {
+        VehicleId(vehicles.keys().map(|id| id.0 + 1).max().unwrap_or(1))
+    }
+}
+
+/// Vehicles other than `id` of the same kind that could take `date`, by id.
+pub fn alternatives(
+    vehicles: &HashMap<VehicleId, Vehicle>,
+    id: VehicleId,
+    date: &ReservedDate,
+    rules: &ReservationRules,
+) -> Vec<VehicleId> {
+    let Some(kind) = vehicles.get(&id).map(|vehicle| vehicle.kind) else {
+        return Vec::new();
+    };
+    let mut alternatives: Vec<VehicleId> = vehicles
+        .values()
+        .filter(|vehicle| vehicle.id != id && vehicle.kind == kind)
+        .filter(|vehicle| vehicle.check_available(date, rules).is_ok())
+        .map(|vehicle| vehicle.id)
+        .collect();
+    alternatives.sort();
+    alternatives
+}
+
+/// Puts a vehicle into maintenance, handling its reservations not yet over by `today`:
+/// they are either cancelled or, when `transfer` is set, moved to the first other
+/// vehicle of the same kind free for them.
+pub fn start_maintenance(
+    vehicles: &mut HashMap<VehicleId, Vehicle>,
+    id: VehicleId,
+    transfer: bool,
+    today: NaiveDate,
+    rules: &ReservationRules,
+) -> Option<MaintenanceReport> {
+    let vehicle = vehicles.get_mut(&id)?;
+    let kind = vehicle.kind;
+    vehicle.in_maintenance = true;
+    let (upcoming, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut vehicle.reserved_dates)
+        .into_iter()
+        .partition(|reserved| reserved.last_date() >= today);
+    vehicle.reserved_dates = kept;
+
+    let mut candidates: Vec<VehicleId> = vehicles
+        .values()
+        .filter(|vehicle| vehicle.id != id && vehicle.kind == kind)
+        .map(|vehicle| vehicle.id)
+        .collect();
+    candidates.sort();
+
+    let mut report = MaintenanceReport::default();
+    for reservation in upcoming {
+        if !transfer {
+            report.cancelled.push(reservation);
+            continue;
+        }
+
+        let target = candidates
+            .iter()
+            .find(|candidate| {
+                vehicles[candidate]
+                    .check_available(&reservation, rules)
+                    .is_ok()
+            })
+            .and_then(|candidate| vehicles.get_mut(candidate));
+        match target {
+            Some(vehicle) => {
+                vehicle.insert_reservation(reservation.clone());
+                report.moved.push(MovedReservation {
+                    reservation,
+                    to: vehicle.id,
+                });
+            }
+            None => {
+                if let Some(vehicle) = vehicles.get_mut(&id) {
+                    vehicle.insert_reservation(reservation.clone());
+                }
+                report.failed.push(reservation);
+            }
+        }
+    }
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+
+    fn truck() -> Vehicle {
+        Vehicle::new(VehicleId(1), "Truck 1".to_owned(), VehicleKind::Truck)
+    }
+
+    fn monday() -> ReservedDate {
+        ReservedDate::new(
+            "2024-03-04",
+            DayPeriod::Morning,
+            "2024-03-04",
+            DayPeriod::Afternoon,
+        )
+        .unwrap()
+    }
+
+    fn clock() -> MockClock {
+        MockClock::new(Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn a_hold_blocks_its_period_until_it_expires() {
+        let clock = clock();
+        let rules = ReservationRules::default();
+        let mut vehicle = truck();
+        vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+
+        clock.advance(Duration::minutes(14));
+        assert!(vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .is_err());
+
+        clock.advance(Duration::minutes(1));
+        vehicle.prune_holds(clock.now());
+        assert!(vehicle.holds.is_empty());
+        assert_eq!(vehicle.reserve(monday(), &rules), Ok(()));
+    }
+
+    #[test]
+    fn an_expired_hold_cannot_be_confirmed() {
+        let clock = clock();
+        let rules = ReservationRules::default();
+        let mut vehicle = truck();
+        let hold = vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+
+        clock.advance(Duration::minutes(20));
+        assert_eq!(
+            vehicle.confirm_hold(hold.id, clock.now(), &rules),
+            Err(HoldErr::NotHeld.into())
+        );
+        assert!(vehicle.reserved_dates.is_empty());
+    }
+
+    #[test]
+    fn a_confirmed_hold_becomes_a_reservation() {
+        let clock = clock();
+        let rules = ReservationRules::default();
+        let mut vehicle = truck();
+        let hold = vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+
+        assert_eq!(
+            vehicle.confirm_hold(hold.id, clock.now(), &rules),
+            Ok(monday())
+        );
+        assert!(vehicle.holds.is_empty());
+        assert_eq!(vehicle.reserved_dates, [monday()]);
+    }
+
+    #[test]
+    fn a_released_hold_frees_its_period() {
+        let clock = clock();
+        let rules = ReservationRules::default();
+        let mut vehicle = truck();
+        let hold = vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+
+        assert_eq!(
+            vehicle.release_hold(hold.id, clock.now()),
+            Some(hold.clone())
+        );
+        assert_eq!(vehicle.release_hold(hold.id, clock.now()), None);
+        assert_eq!(vehicle.reserve(monday(), &rules), Ok(()));
+    }
+
+    #[test]
+    fn expired_hold_ids_are_not_given_again() {
+        let clock = clock();
+        let rules = ReservationRules::default();
+        let mut vehicle = truck();
+        let first = vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+
+        clock.advance(Duration::hours(1));
+        let second = vehicle
+            .hold(monday(), Duration::minutes(15), clock.now(), &rules)
+            .unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(second.expires_at, clock.now() + Duration::minutes(15));
+    }
+
+    #[test]
+    fn the_fleet_drops_expired_holds_when_locked_pruned() {
+        let clock = clock();
+        let fleet = Fleet::default();
+        let mut vehicle = truck();
+        vehicle
+            .hold(
+                monday(),
+                Duration::minutes(15),
+                clock.now(),
+                &ReservationRules::default(),
+            )
+            .unwrap();
+        fleet.lock().insert(vehicle.id, vehicle);
+
+        assert_eq!(fleet.lock_pruned(clock.now())[&VehicleId(1)].holds.len(), 1);
+        clock.advance(Duration::minutes(15));
+        assert!(fleet.lock_pruned(clock.now())[&VehicleId(1)]
+            .holds
+            .is_empty());
+    }
+
+    #[test]
+    fn reservations_made_out_of_order_stay_sorted_and_conflicting() {
+        let rules = ReservationRules::default();
+        let day =
+            |date| ReservedDate::new(date, DayPeriod::Morning, date, DayPeriod::Afternoon).unwrap();
+        let mut vehicle = truck();
+        for date in ["2024-03-07", "2024-03-04", "2024-03-06", "2024-03-05"] {
+            vehicle.reserve(day(date), &rules).unwrap();
+        }
+
+        let starts: Vec<_> = vehicle
+            .reserved_dates
+            .iter()
+            .map(ReservedDate::first_date)
+            .collect();
+        let mut sorted = starts.clone();
+        sorted.sort();
+        assert_eq!(starts, sorted);
+        for date in ["2024-03-04", "2024-03-05", "2024-03-06", "2024-03-07"] {
+            assert!(vehicle.reserve(day(date), &rules).is_err(), "{date}");
+        }
+        assert_eq!(vehicle.reserve(day("2024-03-08"), &rules), Ok(()));
+    }
+}
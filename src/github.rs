@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use reqwest::header::{ACCEPT, USER_AGENT};
+use serde::{Deserialize, Serialize};
+
+/// How failed GitHub API calls are retried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Total number of attempts, the first one included.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled before each following one.
+    pub initial_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            attempts: 3,
+            initial_backoff_ms: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+/// Fetches the user owning `access_token`.
+///
+/// Timeouts, connection failures and 5xx responses are retried with exponential backoff,
+/// other 4xx responses fail straight away.
+pub async fn fetch_user(
+    client: &reqwest::Client,
+    api_url: &str,
+    access_token: &str,
+    retry: &RetryConfig,
+) -> Result<GitHubUser, reqwest::Error> {
+    let mut backoff = Duration::from_millis(retry.initial_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        let response = client
+            .get(format!("{}/user", api_url.trim_end_matches('/')))
+            .bearer_auth(access_token)
+            .header(USER_AGENT, env!("CARGO_PKG_NAME"))
+            .header(ACCEPT, "application/vnd.github+json")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        let retryable = match &response {
+            Ok(_) => false,
+            Err(err) => {
+                err.is_timeout()
+                    || err.is_connect()
+                    || err.status().is_some_and(|status| status.is_server_error())
+            }
+        };
+        if !retryable || attempt >= retry.attempts {
+            return response?.json().await;
+        }
+
+        rocket::tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use rocket::tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves `GET /user` on a local port, answering 503 to the first `failures` requests and
+    /// the user afterwards. Returns the API URL and the number of requests received.
+    async fn flaky_api(failures: u32) -> (String, Arc<AtomicU32>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicU32::new(0));
+        let received = Arc::clone(&requests);
+        rocket::tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).await;
+                let response = if received.fetch_add(1, Ordering::SeqCst) < failures {
+                    "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_owned()
+                } else {
+                    let body = r#"{"login":"octocat"}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, requests)
+    }
+
+    fn retry(attempts: u32) -> RetryConfig {
+        RetryConfig {
+            attempts,
+            initial_backoff_ms: 1,
+        }
+    }
+
+    #[rocket::async_test]
+    async fn server_errors_are_retried_until_an_attempt_succeeds() {
+        let (url, requests) = flaky_api(2).await;
+
+        let user = fetch_user(&reqwest::Client::new(), &url, "token", &retry(3)).await;
+
+        assert_eq!(user.unwrap().login, "octocat");
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[rocket::async_test]
+    async fn retries_give_up_after_the_configured_attempts() {
+        let (url, requests) = flaky_api(u32::MAX).await;
+
+        let error = fetch_user(&reqwest::Client::new(), &url, "token", &retry(3))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.status(),
+            Some(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+}
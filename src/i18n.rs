@@ -0,0 +1,171 @@
+use rocket::http::Status;
+use serde::Serialize;
+
+use crate::error::{DateParsedErr, ReservationErr};
+
+/// Languages error messages are available in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    fn from_tag(tag: &str) -> Option<Language> {
+        let primary = tag.split('-').next()?.trim();
+        if primary.eq_ignore_ascii_case("en") {
+            Some(Language::English)
+        } else if primary.eq_ignore_ascii_case("fr") {
+            Some(Language::French)
+        } else {
+            None
+        }
+    }
+
+    /// Supported language with the highest `q` in an `Accept-Language` header, e.g.
+    /// `fr-FR,fr;q=0.9,en;q=0.8`. English when none is supported.
+    pub fn negotiate(accept_language: &str) -> Language {
+        let mut best: Option<(Language, f32)> = None;
+        for range in accept_language.split(',') {
+            let mut parts = range.split(';');
+            let Some(language) = parts.next().and_then(Language::from_tag) else {
+                continue;
+            };
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((language, quality));
+            }
+        }
+        best.map(|(language, _)| language).unwrap_or_default()
+    }
+}
+
+/// Stable identifier of an error, for clients that should not parse its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    Gone,
+    Unprocessable,
+    Internal,
+    InvalidStartDate,
+    InvalidEndDate,
+    StartAfterEnd,
+    AlreadyReserved,
+    VehicleInMaintenance,
+    MaintenanceScheduled,
+    SiteCompleted,
+    Blackout,
+    ReservationTooLong,
+}
+
+impl ErrorCode {
+    /// Generic code of an error with `status`.
+    pub fn for_status(status: Status) -> ErrorCode {
+        match status.code {
+            401 => ErrorCode::Unauthorized,
+            403 => ErrorCode::Forbidden,
+            404 => ErrorCode::NotFound,
+            409 => ErrorCode::Conflict,
+            410 => ErrorCode::Gone,
+            422 => ErrorCode::Unprocessable,
+            500.. => ErrorCode::Internal,
+            _ => ErrorCode::BadRequest,
+        }
+    }
+
+    /// Message of the code in `language`, `None` for generic codes whose detail is specific
+    /// to each error.
+    pub fn message(self, language: Language) -> Option<&'static str> {
+        let (english, french) = match self {
+            ErrorCode::InvalidStartDate => (
+                "Start date is not a valid YYYY-MM-DD date",
+                "La date de début n'est pas une date AAAA-MM-JJ valide",
+            ),
+            ErrorCode::InvalidEndDate => (
+                "End date is not a valid YYYY-MM-DD date",
+                "La date de fin n'est pas une date AAAA-MM-JJ valide",
+            ),
+            ErrorCode::StartAfterEnd => (
+                "Start date is after end date",
+                "La date de début est après la date de fin",
+            ),
+            ErrorCode::AlreadyReserved => (
+                "The vehicle is already reserved in that period",
+                "Le véhicule est déjà réservé sur cette période",
+            ),
+            ErrorCode::VehicleInMaintenance => (
+                "The vehicle is in maintenance",
+                "Le véhicule est en maintenance",
+            ),
+            ErrorCode::MaintenanceScheduled => (
+                "The vehicle has maintenance scheduled in that period",
+                "Une maintenance du véhicule est prévue sur cette période",
+            ),
+            ErrorCode::SiteCompleted => ("The site is completed", "Le chantier est terminé"),
+            ErrorCode::Blackout => (
+                "No vehicle can be reserved in that period",
+                "Aucun véhicule ne peut être réservé sur cette période",
+            ),
+            ErrorCode::ReservationTooLong => (
+                "The reservation spans more days than allowed",
+                "La réservation couvre plus de jours que permis",
+            ),
+            _ => return None,
+        };
+        Some(match language {
+            Language::English => english,
+            Language::French => french,
+        })
+    }
+}
+
+impl From<&DateParsedErr> for ErrorCode {
+    fn from(err: &DateParsedErr) -> Self {
+        match err {
+            DateParsedErr::InvalidStartDate => ErrorCode::InvalidStartDate,
+            DateParsedErr::InvalidEndDate => ErrorCode::InvalidEndDate,
+            DateParsedErr::StartAfterEnd => ErrorCode::StartAfterEnd,
+        }
+    }
+}
+
+impl From<&ReservationErr> for ErrorCode {
+    fn from(err: &ReservationErr) -> Self {
+        match err {
+            ReservationErr::AlreadyReserved(_) => ErrorCode::AlreadyReserved,
+            ReservationErr::VehicleInMaintenance => ErrorCode::VehicleInMaintenance,
+            ReservationErr::UnderMaintenance(_) => ErrorCode::MaintenanceScheduled,
+            ReservationErr::SiteCompleted => ErrorCode::SiteCompleted,
+            ReservationErr::Blackout(_) => ErrorCode::Blackout,
+            ReservationErr::TooLong { .. } => ErrorCode::ReservationTooLong,
+        }
+    }
+}
+
+/// Title of `status` in `language`.
+pub fn status_title(status: Status, language: Language) -> String {
+    let french = match status.code {
+        400 => "Requête invalide",
+        401 => "Non authentifié",
+        403 => "Accès refusé",
+        404 => "Introuvable",
+        409 => "Conflit",
+        410 => "Plus disponible",
+        422 => "Entité non traitable",
+        500 => "Erreur interne",
+        _ => return status.reason_lossy().to_string(),
+    };
+    match language {
+        Language::English => status.reason_lossy().to_string(),
+        Language::French => french.to_string(),
+    }
+}
@@ -0,0 +1,170 @@
+#[macro_use]
+extern crate rocket;
+
+pub mod audit;
+pub mod auth;
+pub mod blackouts;
+pub mod calendar;
+pub mod client_ip;
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod estimate;
+pub mod fleet;
+pub mod github;
+pub mod i18n;
+pub mod overdue;
+pub mod persistence;
+pub mod phone;
+pub mod photos;
+pub mod pretty;
+pub mod routes;
+pub mod share;
+pub mod sites;
+pub mod storage;
+pub mod supplies;
+pub mod validation;
+pub mod versions;
+pub mod workers;
+
+use std::sync::Arc;
+
+use rocket::fairing::AdHoc;
+use rocket::{Build, Rocket};
+use rocket_oauth2::OAuth2;
+
+use audit::AuditLog;
+use auth::Users;
+use blackouts::Blackouts;
+use clock::{SharedClock, SystemClock};
+use config::AppConfig;
+use fleet::Fleet;
+use routes::auth::GitHub;
+use sites::Sites;
+use versions::{SiteVersioning, SiteVersions};
+use workers::Workers;
+
+pub fn rocket() -> Rocket<Build> {
+    rocket_with_clock(Arc::new(SystemClock))
+}
+
+/// The server reading the time from `clock`, e.g. a [`MockClock`](clock::MockClock) in tests.
+pub fn rocket_with_clock(clock: SharedClock) -> Rocket<Build> {
+    let rocket = rocket::build()
+        .manage(Sites::default())
+        .manage(Fleet::default())
+        .manage(Blackouts::default())
+        .manage(Workers::default())
+        .manage(SiteVersions::default())
+        .manage(AuditLog::default())
+        .manage(Users::default())
+        .manage(clock)
+        .attach(AdHoc::config::<AppConfig>())
+        .attach(config::period_labels())
+        .attach(OAuth2::<GitHub>::fairing("github"))
+        .attach(overdue::fairing())
+        .attach(config::banner())
+        .attach(persistence::StatePersistence)
+        .attach(SiteVersioning)
+        .attach(pretty::PrettyJson)
+        .mount(
+            "/",
+            routes![
+                routes::index,
+                routes::index_anonymous,
+                routes::auth::github_login,
+                routes::auth::github_login_url,
+                routes::auth::github_callback,
+                routes::auth::me,
+                routes::auth::roles,
+                routes::auth::set_role,
+                routes::auth::logout,
+                routes::blackouts::list_blackouts,
+                routes::blackouts::add_blackout,
+                routes::blackouts::delete_blackout,
+                routes::calendar::calendar,
+                routes::clients::list_clients,
+                routes::clients::set_client,
+                routes::clients::list_contacts,
+                routes::clients::add_contact,
+                routes::clients::remove_contact,
+                routes::resources::heatmap,
+                routes::resources::matrix,
+                routes::server::server_info,
+                routes::share::share_site,
+                routes::share::shared_site,
+                routes::projects::estimate_project,
+                routes::projects::gantt,
+                routes::reservations::import_reservations,
+                routes::reservations::clear_reservations,
+                routes::reservations::my_reservations,
+                routes::reservations::ending_soon,
+                routes::reservations::search_reservations,
+                routes::sites::create_site,
+                routes::sites::add_manager,
+                routes::sites::transfer_site,
+                routes::sites::list_sites,
+                routes::sites::reorder_sites,
+                routes::sites::sites_in_bounds,
+                routes::sites::get_site,
+                routes::sites::summary,
+                routes::sites::labor,
+                routes::sites::validate_site,
+                routes::sites::site_diff,
+                routes::sites::edit_site,
+                routes::sites::patch_site,
+                routes::sites::set_status,
+                routes::sites::report_anomaly,
+                routes::sites::interrupted_sites,
+                routes::sites::overdue_sites,
+                routes::sites::list_anomalies,
+                routes::sites::conflicts,
+                routes::sites::utilization,
+                routes::sites::attach_vehicle,
+                routes::sites::reserve,
+                routes::sites::assign_worker,
+                routes::sites::crew,
+                routes::photos::add_photo,
+                routes::photos::set_cover,
+                routes::supplies::add_tool,
+                routes::supplies::remove_tool,
+                routes::supplies::add_material,
+                routes::supplies::remove_material,
+                routes::vehicles::create_vehicle,
+                routes::vehicles::clone_vehicle,
+                routes::vehicles::get_vehicle,
+                routes::vehicles::list_vehicles,
+                routes::vehicles::start_maintenance,
+                routes::vehicles::end_maintenance,
+                routes::vehicles::schedule_maintenance,
+                routes::vehicles::shorten_reservation,
+                routes::vehicles::release_reservation,
+                routes::vehicles::join_waitlist,
+                routes::vehicles::place_hold,
+                routes::vehicles::confirm_hold,
+                routes::vehicles::release_hold,
+                routes::workers::create_worker,
+                routes::workers::list_workers,
+                routes::workers::trades,
+                routes::workers::matches,
+                routes::workers::get_worker,
+                routes::workers::edit_worker,
+                routes::workers::delete_worker,
+                routes::workers::move_worker,
+                routes::workers::assign_workers,
+            ],
+        )
+        .register(
+            "/",
+            catchers![
+                routes::error::not_found,
+                routes::error::unprocessable,
+                routes::error::fallback
+            ],
+        );
+
+    #[cfg(feature = "dev")]
+    let rocket = rocket.mount("/", routes![routes::dev::seed]);
+
+    rocket
+}
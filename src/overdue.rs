@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use rocket::fairing::AdHoc;
+
+use crate::audit::{AuditLog, SYSTEM_ACTOR};
+use crate::calendar::Calendar;
+use crate::clock::{Clock, SharedClock};
+use crate::config::AppConfig;
+use crate::sites::{Site, SiteId, Sites};
+
+/// Flags in-progress sites whose end day is before the clock's today, returning the newly flagged ids.
+pub fn flag_overdue_sites(
+    sites: &Sites,
+    audit: &AuditLog,
+    calendar: &Calendar,
+    clock: &dyn Clock,
+) -> Vec<SiteId> {
+    let today = clock.today();
+    let is_overdue = |site: &Site| !site.overdue && site.is_past_due(calendar, today);
+
+    // Most scans find nothing, they only need a read lock. The read guard is dropped before
+    // writing, and sites are checked again since they may have changed in between.
+    if !sites.read().values().any(is_overdue) {
+        return Vec::new();
+    }
+    let mut flagged: Vec<SiteId> = sites
+        .write()
+        .iter_mut()
+        .filter(|(_, site)| is_overdue(site))
+        .map(|(id, site)| {
+            site.overdue = true;
+            *id
+        })
+        .collect();
+    flagged.sort();
+
+    let now = clock.now();
+    for id in &flagged {
+        audit.record(now, SYSTEM_ACTOR, "flag_overdue", Some(*id));
+    }
+    flagged
+}
+
+/// Periodically flags overdue sites, every `overdue_check_interval_secs` (0 disables it, as
+/// the request tests do). The first scan runs at liftoff.
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Overdue sites monitor", |rocket| {
+        Box::pin(async move {
+            let (Some(sites), Some(audit), Some(config), Some(clock)) = (
+                rocket.state::<Sites>().cloned(),
+                rocket.state::<AuditLog>().cloned(),
+                rocket.state::<AppConfig>().cloned(),
+                rocket.state::<SharedClock>().cloned(),
+            ) else {
+                return;
+            };
+            if config.overdue_check_interval_secs == 0 {
+                return;
+            }
+
+            rocket::tokio::spawn(async move {
+                let period = Duration::from_secs(config.overdue_check_interval_secs);
+                let mut interval = rocket::tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    flag_overdue_sites(&sites, &audit, &config.calendar, clock.as_ref());
+                }
+            });
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone, Utc};
+    use serde_json::json;
+
+    use super::*;
+    use crate::calendar::DayIndex;
+    use crate::clock::MockClock;
+    use crate::sites::SiteStatus;
+
+    /// A site of `half_days` starting on day 0, the Monday 2024-01-01 of the default calendar.
+    fn site(id: u32, status: SiteStatus, half_days: usize) -> Site {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": format!("Site {id}"),
+            "coordinates": { "latitude": 48.1, "longitude": -1.6 },
+            "client": { "name": "Acme", "phone": "+33 2 99 00 00 00" },
+            "start_day": 0,
+            "duration": { "half_day": half_days },
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn flags_in_progress_sites_once_their_end_day_is_past() {
+        let sites = Sites::default();
+        // Ends on Tuesday 2024-01-02.
+        sites
+            .write()
+            .insert(SiteId(1), site(1, SiteStatus::InProgress, 4));
+        sites
+            .write()
+            .insert(SiteId(2), site(2, SiteStatus::NotCarried, 4));
+        let audit = AuditLog::default();
+        let calendar = Calendar::default();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap());
+
+        assert!(flag_overdue_sites(&sites, &audit, &calendar, &clock).is_empty());
+
+        clock.advance(TimeDelta::days(1));
+        assert_eq!(
+            flag_overdue_sites(&sites, &audit, &calendar, &clock),
+            [SiteId(1)]
+        );
+        assert!(sites.read()[&SiteId(1)].overdue);
+        assert!(!sites.read()[&SiteId(2)].overdue);
+        let entries = audit.lock();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].at, clock.now());
+        assert_eq!(entries[0].actor, SYSTEM_ACTOR);
+    }
+
+    #[test]
+    fn flags_a_site_only_once() {
+        let sites = Sites::default();
+        sites
+            .write()
+            .insert(SiteId(1), site(1, SiteStatus::InProgress, 2));
+        let audit = AuditLog::default();
+        let calendar = Calendar::default();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap());
+
+        clock.advance(TimeDelta::days(1));
+        assert_eq!(
+            flag_overdue_sites(&sites, &audit, &calendar, &clock),
+            [SiteId(1)]
+        );
+        clock.advance(TimeDelta::days(1));
+        assert!(flag_overdue_sites(&sites, &audit, &calendar, &clock).is_empty());
+        assert_eq!(audit.lock().len(), 1);
+    }
+
+    #[test]
+    fn completing_a_site_clears_its_overdue_flag() {
+        let sites = Sites::default();
+        sites
+            .write()
+            .insert(SiteId(1), site(1, SiteStatus::InProgress, 2));
+        let calendar = Calendar::default();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 1, 3, 12, 0, 0).unwrap());
+        flag_overdue_sites(&sites, &AuditLog::default(), &calendar, &clock);
+        assert!(sites.read()[&SiteId(1)].overdue);
+
+        let mut sites = sites.write();
+        let site = sites.get_mut(&SiteId(1)).unwrap();
+        site.set_status(SiteStatus::Completed, clock.now(), None);
+        assert!(!site.overdue);
+    }
+
+    #[test]
+    fn moving_a_site_into_the_future_clears_its_overdue_flag() {
+        let sites = Sites::default();
+        sites
+            .write()
+            .insert(SiteId(1), site(1, SiteStatus::InProgress, 2));
+        let calendar = Calendar::default();
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 1, 3, 12, 0, 0).unwrap());
+        flag_overdue_sites(&sites, &AuditLog::default(), &calendar, &clock);
+
+        let mut sites = sites.write();
+        let site = sites.get_mut(&SiteId(1)).unwrap();
+        site.refresh_overdue(&calendar, clock.today());
+        assert!(site.overdue, "still past due");
+
+        // Friday 2024-01-05, after the clock's today.
+        site.start_day = DayIndex(4);
+        site.refresh_overdue(&calendar, clock.today());
+        assert!(!site.overdue);
+    }
+}
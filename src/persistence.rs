@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::{Build, Orbit, Rocket};
+
+use serde::{Deserialize, Serialize};
+
+use crate::blackouts::{Blackout, Blackouts};
+use crate::config::AppConfig;
+use crate::fleet::{Fleet, Vehicle, VehicleId};
+use crate::sites::{Site, SiteId, Sites};
+use crate::storage::{StorageErr, StorageFormat};
+use crate::workers::{EmbeddedWorkers, Worker, WorkerId, Workers};
+
+#[derive(Debug)]
+pub enum PersistenceErr {
+    Io(io::Error),
+    Storage(StorageErr),
+}
+
+impl fmt::Display for PersistenceErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceErr::Io(err) => write!(f, "{err}"),
+            PersistenceErr::Storage(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for PersistenceErr {}
+
+/// Everything the server keeps across restarts, as written by [`save`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub sites: Vec<Site>,
+    #[serde(default)]
+    pub vehicles: Vec<Vehicle>,
+    #[serde(default)]
+    pub workers: Vec<Worker>,
+    #[serde(default)]
+    pub blackouts: Vec<Blackout>,
+}
+
+/// Layouts [`load`] reads: the whole state, or the bare list of sites saved before the fleet,
+/// the workers and the blackouts were.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored<T, S> {
+    State(T),
+    SitesOnly(Vec<S>),
+}
+
+/// The sites of a stored state, read apart to find the workers they embed.
+#[derive(Deserialize)]
+struct StoredSites {
+    sites: Vec<EmbeddedWorkers>,
+}
+
+/// Writes the sites, the fleet, the workers and the blackouts to `path`, replacing its previous
+/// content. Each list is sorted, so that saving the same state twice writes the same bytes.
+pub fn save(
+    path: &Path,
+    format: StorageFormat,
+    sites: &HashMap<SiteId, Site>,
+    vehicles: &HashMap<VehicleId, Vehicle>,
+    workers: &HashMap<WorkerId, Worker>,
+    blackouts: &[Blackout],
+) -> Result<(), PersistenceErr> {
+    let mut state = State {
+        sites: sites.values().cloned().collect(),
+        vehicles: vehicles.values().cloned().collect(),
+        workers: workers.values().cloned().collect(),
+        blackouts: blackouts.to_vec(),
+    };
+    state.sites.sort_by_key(|site| site.id);
+    state.vehicles.sort_by_key(|vehicle| vehicle.id);
+    state.workers.sort_by_key(|worker| worker.id);
+    let bytes = format.encode(&state).map_err(PersistenceErr::Storage)?;
+    fs::write(path, bytes).map_err(PersistenceErr::Io)
+}
+
+/// Reads back the state written by [`save`], or the sites alone saved by earlier versions.
+///
+/// Workers that sites embed, as they were stored before the [`Workers`] registry existed, are
+/// added to the registry unless it already has a worker with their id.
+pub fn load(path: &Path, format: StorageFormat) -> Result<State, PersistenceErr> {
+    let bytes = fs::read(path).map_err(PersistenceErr::Io)?;
+    let mut state = match format.decode(&bytes).map_err(PersistenceErr::Storage)? {
+        Stored::State(state) => state,
+        Stored::SitesOnly(sites) => State {
+            sites,
+            ..State::default()
+        },
+    };
+    let embedded = match format.decode(&bytes).map_err(PersistenceErr::Storage)? {
+        Stored::State(StoredSites { sites }) | Stored::SitesOnly(sites) => sites,
+    };
+    for worker in embedded.into_iter().flat_map(|site| site.workers) {
+        if state.workers.iter().all(|known| known.id != worker.id) {
+            state.workers.push(worker);
+        }
+    }
+    Ok(state)
+}
+
+/// Loads the sites, the fleet, the workers and the blackouts from `sites_path` on ignite and
+/// writes them back on shutdown.
+///
+/// A missing or unreadable file is logged and the server starts empty; an unreadable file is
+/// moved aside first. Loaded sites breaking an invariant are kept, with a warning.
+pub struct StatePersistence;
+
+#[rocket::async_trait]
+impl Fairing for StatePersistence {
+    fn info(&self) -> Info {
+        Info {
+            name: "State persistence",
+            kind: Kind::Ignite | Kind::Shutdown,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let (Some(config), Some(sites), Some(fleet), Some(workers), Some(blackouts)) = (
+            rocket.state::<AppConfig>(),
+            rocket.state::<Sites>(),
+            rocket.state::<Fleet>(),
+            rocket.state::<Workers>(),
+            rocket.state::<Blackouts>(),
+        ) else {
+            return Ok(rocket);
+        };
+        let Some(path) = &config.sites_path else {
+            return Ok(rocket);
+        };
+
+        if !path.exists() {
+            log::info!("No state file at {}, starting empty", path.display());
+            return Ok(rocket);
+        }
+        match load(path, config.storage_format) {
+            Ok(loaded) => {
+                log::info!(
+                    "Loaded {} sites, {} vehicles, {} workers and {} blackouts from {}",
+                    loaded.sites.len(),
+                    loaded.vehicles.len(),
+                    loaded.workers.len(),
+                    loaded.blackouts.len(),
+                    path.display()
+                );
+                for site in &loaded.sites {
+                    if let Err(problems) = site.validate() {
+                        for problem in problems {
+                            log::warn!("Site {}: {} {}", site.id, problem.field, problem.message);
+                        }
+                    }
+                }
+                *sites.write() = loaded
+                    .sites
+                    .into_iter()
+                    .map(|site| (site.id, site))
+                    .collect();
+                *fleet.lock() = loaded
+                    .vehicles
+                    .into_iter()
+                    .map(|vehicle| (vehicle.id, vehicle))
+                    .collect();
+                *workers.lock() = loaded
+                    .workers
+                    .into_iter()
+                    .map(|worker| (worker.id, worker))
+                    .collect();
+                *blackouts.write() = loaded.blackouts;
+            }
+            Err(err) => {
+                // Keep the unreadable file around rather than overwriting it on shutdown.
+                let aside = path.with_extension("corrupt");
+                log::warn!(
+                    "Could not load the state from {}, starting empty and moving it to {}: {err}",
+                    path.display(),
+                    aside.display()
+                );
+                if let Err(err) = fs::rename(path, &aside) {
+                    log::error!("Could not move {}: {err}", path.display());
+                }
+            }
+        }
+        Ok(rocket)
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        let (Some(config), Some(sites), Some(fleet), Some(workers), Some(blackouts)) = (
+            rocket.state::<AppConfig>(),
+            rocket.state::<Sites>(),
+            rocket.state::<Fleet>(),
+            rocket.state::<Workers>(),
+            rocket.state::<Blackouts>(),
+        ) else {
+            return;
+        };
+        let Some(path) = &config.sites_path else {
+            return;
+        };
+
+        let blackouts = blackouts.current();
+        let sites = sites.read();
+        let saved = save(
+            path,
+            config.storage_format,
+            &sites,
+            &fleet.lock(),
+            &workers.lock(),
+            &blackouts,
+        );
+        match saved {
+            Ok(()) => log::info!("Saved the state to {}", path.display()),
+            Err(err) => log::error!("Could not save the state to {}: {err}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+const MIN_DIGITS: usize = 6;
+const MAX_DIGITS: usize = 15;
+
+/// A phone number made of an optional leading `+` and 6 to 15 digits.
+///
+/// Spaces, dots, dashes and parentheses are accepted as separators and dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhoneNumberErr {
+    InvalidCharacter(char),
+    TooShort,
+    TooLong,
+}
+
+impl fmt::Display for PhoneNumberErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhoneNumberErr::InvalidCharacter(c) => {
+                write!(f, "must only contain digits, found {c:?}")
+            }
+            PhoneNumberErr::TooShort => write!(f, "must have at least {MIN_DIGITS} digits"),
+            PhoneNumberErr::TooLong => write!(f, "must have at most {MAX_DIGITS} digits"),
+        }
+    }
+}
+
+impl std::error::Error for PhoneNumberErr {}
+
+impl FromStr for PhoneNumber {
+    type Err = PhoneNumberErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (prefix, rest) = match s.strip_prefix('+') {
+            Some(rest) => ("+", rest),
+            None => ("", s),
+        };
+
+        let mut digits = String::with_capacity(rest.len());
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '.' | '-' | '(' | ')' => {}
+                _ => return Err(PhoneNumberErr::InvalidCharacter(c)),
+            }
+        }
+
+        if digits.len() < MIN_DIGITS {
+            Err(PhoneNumberErr::TooShort)
+        } else if digits.len() > MAX_DIGITS {
+            Err(PhoneNumberErr::TooLong)
+        } else {
+            Ok(PhoneNumber(format!("{prefix}{digits}")))
+        }
+    }
+}
+
+impl TryFrom<String> for PhoneNumber {
+    type Error = PhoneNumberErr;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PhoneNumber> for String {
+    fn from(phone: PhoneNumber) -> Self {
+        phone.0
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<PhoneNumber, PhoneNumberErr> {
+        s.parse()
+    }
+
+    #[test]
+    fn separators_are_dropped_and_a_leading_plus_kept() {
+        for (written, stored) in [
+            ("+33 2 99 00 00 00", "+33299000000"),
+            ("02-99-00-00-00", "0299000000"),
+            ("(02) 99.00.00.00", "0299000000"),
+            ("  123456  ", "123456"),
+            ("123456789012345", "123456789012345"),
+        ] {
+            assert_eq!(parse(written).map(String::from), Ok(stored.to_owned()));
+        }
+    }
+
+    #[test]
+    fn malformed_numbers_are_refused() {
+        for (written, err) in [
+            ("", PhoneNumberErr::TooShort),
+            ("12345", PhoneNumberErr::TooShort),
+            ("+1 (2) 3", PhoneNumberErr::TooShort),
+            ("1234567890123456", PhoneNumberErr::TooLong),
+            ("02 99 OO 00 00", PhoneNumberErr::InvalidCharacter('O')),
+            ("02 99 00 00 00+", PhoneNumberErr::InvalidCharacter('+')),
+            ("++33 2 99 00 00 00", PhoneNumberErr::InvalidCharacter('+')),
+        ] {
+            assert_eq!(parse(written), Err(err), "{written:?}");
+        }
+    }
+
+    #[test]
+    fn numbers_deserialize_parsed_and_serialize_as_stored() {
+        let phone: PhoneNumber = serde_json::from_str(r#""+33 2 99 00 00 00""#).unwrap();
+        assert_eq!(phone.as_str(), "+33299000000");
+
+        let json = serde_json::to_string(&phone).unwrap();
+        assert_eq!(json, r#""+33299000000""#);
+        assert_eq!(serde_json::from_str::<PhoneNumber>(&json).unwrap(), phone);
+        assert!(serde_json::from_str::<PhoneNumber>(r#""call me""#).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+use std::num::ParseIntError;
+
+use rocket::request::FromParam;
+use serde::{Deserialize, Serialize};
+
+/// Identifier of a photo within its site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PhotoId(pub u32);
+
+impl fmt::Display for PhotoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for PhotoId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(PhotoId)
+    }
+}
+
+/// A photo of a site, stored elsewhere and referenced by its URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Photo {
+    pub id: PhotoId,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    /// Whether the photo represents the site on dashboards. At most one photo per site is.
+    #[serde(default)]
+    pub is_cover: bool,
+}
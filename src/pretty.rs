@@ -0,0 +1,38 @@
+use std::io::Cursor;
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Method};
+use rocket::{Request, Response};
+
+/// Re-indents the JSON answer of `GET` requests carrying `?pretty=true`, for debugging.
+/// Responses are compact otherwise.
+pub struct PrettyJson;
+
+#[rocket::async_trait]
+impl Fairing for PrettyJson {
+    fn info(&self) -> Info {
+        Info {
+            name: "Pretty JSON",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let pretty = request.method() == Method::Get
+            && request
+                .query_value::<bool>("pretty")
+                .is_some_and(|pretty| pretty.unwrap_or(false));
+        if !pretty || response.content_type() != Some(ContentType::JSON) {
+            return;
+        }
+
+        let Ok(body) = response.body_mut().to_bytes().await else {
+            return;
+        };
+        let indented = serde_json::from_slice::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| serde_json::to_vec_pretty(&value).ok())
+            .unwrap_or(body);
+        response.set_sized_body(indented.len(), Cursor::new(indented));
+    }
+}
@@ -0,0 +1,163 @@
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::response::{self, Redirect, Responder};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+use rocket_oauth2::{OAuth2, TokenResponse};
+use serde::Serialize;
+
+use crate::auth::{self, AppRole, CsrfChecked, Permission, User, Users, CSRF_COOKIE, USER_COOKIE};
+use crate::config::AppConfig;
+use crate::github;
+use crate::routes::error::ApiError;
+
+/// Marker for the `oauth.github` provider configuration.
+pub struct GitHub;
+
+/// Scopes asked to GitHub, enough to read the user's login.
+const GITHUB_SCOPES: &[&str] = &["read:user"];
+
+#[get("/login/github")]
+pub fn github_login(oauth2: OAuth2<GitHub>, cookies: &CookieJar<'_>) -> Result<Redirect, ApiError> {
+    oauth2
+        .get_redirect(cookies, GITHUB_SCOPES)
+        .map_err(|err| ApiError::internal(format!("Could not start the GitHub login: {err}")))
+}
+
+/// The GitHub authorization URL, `{ "url": ... }`, for frontends navigating to it themselves.
+pub struct LoginUrl(Redirect);
+
+#[derive(Serialize)]
+struct LoginUrlBody<'a> {
+    url: &'a str,
+}
+
+impl<'r> Responder<'r, 'static> for LoginUrl {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let redirect = self.0.respond_to(request)?;
+        let url = redirect
+            .headers()
+            .get_one("Location")
+            .ok_or(Status::InternalServerError)?;
+        Json(LoginUrlBody { url }).respond_to(request)
+    }
+}
+
+/// Starts a GitHub login like [`github_login`], answering the URL to go to instead of
+/// redirecting to it.
+#[get("/login/github/url")]
+pub fn github_login_url(
+    oauth2: OAuth2<GitHub>,
+    cookies: &CookieJar<'_>,
+) -> Result<LoginUrl, ApiError> {
+    github_login(oauth2, cookies).map(LoginUrl)
+}
+
+#[get("/auth/github")]
+pub async fn github_callback(
+    token: TokenResponse<GitHub>,
+    cookies: &CookieJar<'_>,
+    config: &State<AppConfig>,
+) -> Result<Redirect, ApiError> {
+    let client = reqwest::Client::builder()
+        .timeout(config.github.timeout())
+        .build()
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    let github_user = github::fetch_user(
+        &client,
+        &config.github.api_url,
+        token.access_token(),
+        &config.github.retry,
+    )
+    .await
+    .map_err(|err| ApiError::new(Status::BadGateway, err.to_string()))?;
+
+    let user = User {
+        name: github_user.login,
+        role: config.default_role,
+    };
+    let user = serde_json::to_string(&user).map_err(|err| ApiError::internal(err.to_string()))?;
+    cookies.add_private(Cookie::new(USER_COOKIE, user));
+    cookies.add(
+        Cookie::build((CSRF_COOKIE, auth::new_csrf_token()))
+            .same_site(SameSite::Strict)
+            .http_only(false),
+    );
+
+    Ok(Redirect::to("/"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleInfo {
+    pub role: AppRole,
+    pub permissions: &'static [Permission],
+}
+
+/// Every role and what it permits.
+#[get("/roles")]
+pub fn roles() -> Json<Vec<RoleInfo>> {
+    Json(
+        AppRole::ALL
+            .into_iter()
+            .map(|role| RoleInfo {
+                role,
+                permissions: role.permissions(),
+            })
+            .collect(),
+    )
+}
+
+/// Rejects with 403 a user whose role does not permit `action`.
+pub fn require(user: &User, action: Permission) -> Result<(), ApiError> {
+    if user.role.can(action) {
+        Ok(())
+    } else {
+        Err(ApiError::forbidden(format!(
+            "A {:?} is not allowed to {action:?}",
+            user.role
+        )))
+    }
+}
+
+#[get("/me")]
+pub fn me(user: User) -> Json<User> {
+    Json(user)
+}
+
+/// Changes the role of `name`, the logged in user by default, from their next request on.
+/// Granted roles are kept in memory only, and forgotten when the server restarts.
+#[post("/role?<role>&<name>")]
+pub fn set_role(
+    role: AppRole,
+    name: Option<&str>,
+    user: User,
+    _csrf: CsrfChecked,
+    users: &State<Users>,
+    cookies: &CookieJar<'_>,
+) -> Result<Json<User>, ApiError> {
+    require(&user, Permission::ManageRoles)?;
+    let name = name.map(str::trim).unwrap_or(&user.name);
+    if name.is_empty() {
+        return Err(ApiError::unprocessable("The user name must not be empty"));
+    }
+    users.grant(name, role);
+
+    let changed = User {
+        name: name.to_owned(),
+        role,
+    };
+    if changed.name == user.name {
+        let value =
+            serde_json::to_string(&changed).map_err(|err| ApiError::internal(err.to_string()))?;
+        cookies.add_private(Cookie::new(USER_COOKIE, value));
+    }
+
+    Ok(Json(changed))
+}
+
+#[post("/logout")]
+pub fn logout(_csrf: CsrfChecked, cookies: &CookieJar<'_>) -> Redirect {
+    cookies.remove_private(USER_COOKIE);
+    cookies.remove(CSRF_COOKIE);
+
+    Redirect::to("/")
+}
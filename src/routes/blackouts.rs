@@ -0,0 +1,87 @@
+use rocket::response::status::{Created, NoContent};
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::audit::AuditLog;
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::blackouts::{Blackout, BlackoutId, Blackouts};
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::ReservationRequest;
+use crate::sites::ReservedDate;
+
+#[get("/blackouts")]
+pub fn list_blackouts(_user: User, blackouts: &State<Blackouts>) -> Json<Vec<Blackout>> {
+    Json(blackouts.current())
+}
+
+/// Blacks out a period for the whole fleet, its note giving the reason. Reservations already
+/// made in that period are kept.
+#[post("/blackouts", data = "<period>")]
+pub fn add_blackout(
+    period: Json<ReservationRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    blackouts: &State<Blackouts>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Created<Json<Blackout>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let ReservationRequest {
+        start_date,
+        start_period,
+        end_date,
+        end_period,
+        note,
+    } = period.into_inner();
+    let period = ReservedDate::new(&start_date, start_period, &end_date, end_period)?
+        .made_by(Some(user.name.clone()), note);
+
+    let mut blackouts = blackouts.write();
+    let blackout = Blackout {
+        id: Blackouts::next_id(&blackouts),
+        period,
+    };
+    blackouts.push(blackout.clone());
+    blackouts.sort_by(|a, b| (&a.period, a.id).cmp(&(&b.period, b.id)));
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("add_blackout {}", blackout.id),
+        None,
+        ip,
+    );
+    Ok(Created::new("/blackouts").body(Json(blackout)))
+}
+
+#[delete("/blackouts/<id>")]
+pub fn delete_blackout(
+    id: BlackoutId,
+    user: User,
+    _csrf: CsrfChecked,
+    blackouts: &State<Blackouts>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<NoContent, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut blackouts = blackouts.write();
+    let index = blackouts
+        .iter()
+        .position(|blackout| blackout.id == id)
+        .ok_or_else(|| ApiError::not_found(format!("No blackout with id {id}")))?;
+    blackouts.remove(index);
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("delete_blackout {id}"),
+        None,
+        ip,
+    );
+    Ok(NoContent)
+}
@@ -0,0 +1,29 @@
+use rocket::serde::json::Json;
+use rocket::State;
+
+use crate::auth::User;
+use crate::calendar::CalendarDay;
+use crate::config::AppConfig;
+use crate::routes::error::ApiError;
+use crate::routes::parse_date_range;
+
+/// Longest range, in days, a single calendar request may cover.
+const MAX_CALENDAR_DAYS: i64 = 366;
+
+#[get("/calendar?<from>&<to>")]
+pub fn calendar(
+    from: &str,
+    to: &str,
+    _user: User,
+    config: &State<AppConfig>,
+) -> Result<Json<Vec<CalendarDay>>, ApiError> {
+    let (from, to) = parse_date_range(from, to, MAX_CALENDAR_DAYS)?;
+
+    let days = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| CalendarDay::new(date, &config.calendar, &config.period_labels))
+        .collect();
+
+    Ok(Json(days))
+}
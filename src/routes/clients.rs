@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::audit::AuditLog;
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::{site_not_found, visible_site_mut, ClientFields, ContactFields};
+use crate::sites::{Client, Contact, Site, SiteId, SiteStatus, Sites};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientSites {
+    pub client: Client,
+    pub sites: Vec<SiteId>,
+    /// Number of the client's sites in each status; statuses with no site are left out.
+    pub status_counts: BTreeMap<SiteStatus, usize>,
+}
+
+/// Every distinct client of the sites the user may see, by name then phone number, with
+/// those sites.
+#[get("/clients")]
+pub fn list_clients(user: User, sites: &State<Sites>) -> Json<Vec<ClientSites>> {
+    let sites = sites.read();
+    let mut clients: BTreeMap<(&str, &str), ClientSites> = BTreeMap::new();
+    for site in sites.values().filter(|site| site.visible_to(&user)) {
+        let entry = clients
+            .entry((&site.client.name, site.client.phone.as_str()))
+            .or_insert_with(|| ClientSites {
+                client: site.client.clone(),
+                sites: Vec::new(),
+                status_counts: BTreeMap::new(),
+            });
+        entry.sites.push(site.id);
+        *entry.status_counts.entry(site.status).or_default() += 1;
+    }
+
+    let mut clients: Vec<ClientSites> = clients.into_values().collect();
+    for client in &mut clients {
+        client.sites.sort();
+    }
+    Json(clients)
+}
+
+/// Hands a site over to another client, e.g. after a change of ownership.
+#[patch("/sites/<id>/client", data = "<client>")]
+#[allow(clippy::too_many_arguments)]
+pub fn set_client(
+    id: SiteId,
+    client: Json<ClientFields>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let client = client.into_inner().parse()?;
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("set_client {}", client.name),
+        Some(id),
+        ip,
+    );
+    site.client = client;
+    Ok(Json(site.clone()))
+}
+
+/// Contacts of a site's client, besides its primary phone number.
+#[get("/sites/<id>/client/contacts")]
+pub fn list_contacts(
+    id: SiteId,
+    user: User,
+    sites: &State<Sites>,
+) -> Result<Json<Vec<Contact>>, ApiError> {
+    let sites = sites.read();
+    let site = sites
+        .get(&id)
+        .filter(|site| site.visible_to(&user))
+        .ok_or_else(|| site_not_found(id))?;
+    Ok(Json(site.client.contacts.clone()))
+}
+
+/// Adds a contact to a site's client, answering the contacts with the new one last.
+#[post("/sites/<id>/client/contacts", data = "<contact>")]
+#[allow(clippy::too_many_arguments)]
+pub fn add_contact(
+    id: SiteId,
+    contact: Json<ContactFields>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Created<Json<Vec<Contact>>>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let contact = contact.into_inner().parse("contact")?;
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("add_contact {}", contact.name),
+        Some(id),
+        ip,
+    );
+    site.client.contacts.push(contact);
+    Ok(Created::new(format!("/sites/{id}/client/contacts"))
+        .body(Json(site.client.contacts.clone())))
+}
+
+/// Removes the contact at `index` in a site's client contacts, shifting the later ones down.
+#[delete("/sites/<id>/client/contacts/<index>")]
+#[allow(clippy::too_many_arguments)]
+pub fn remove_contact(
+    id: SiteId,
+    index: usize,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Vec<Contact>>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    if index >= site.client.contacts.len() {
+        return Err(ApiError::not_found(format!(
+            "Site {id} has no client contact {index}"
+        )));
+    }
+    let contact = site.client.contacts.remove(index);
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("remove_contact {}", contact.name),
+        Some(id),
+        ip,
+    );
+    Ok(Json(site.client.contacts.clone()))
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::calendar::DayIndex;
+use crate::clock::SharedClock;
+use crate::config::AppConfig;
+use crate::fleet::{Fleet, ReservationRules, Vehicle, VehicleId, VehicleKind};
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::sites::DayPeriod::{Afternoon, Morning};
+use crate::sites::{
+    Anomaly, Client, Coordinates, ReservedDate, Site, SiteDuration, SiteId, SiteResource,
+    SiteStatus, Sites,
+};
+use crate::workers::{Trade, Worker, WorkerId, Workers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SeedReport {
+    pub sites: usize,
+    pub workers: usize,
+    pub vehicles: usize,
+    pub reservations: usize,
+}
+
+fn demo_site(id: u32, name: &str, start_day: usize, half_days: usize, created_by: &str) -> Site {
+    Site {
+        id: SiteId(id),
+        name: name.to_owned(),
+        coordinates: Coordinates {
+            latitude: 48.11 + f64::from(id) / 100.0,
+            longitude: -1.68,
+        },
+        client: Client {
+            name: format!("Client {id}"),
+            phone: format!("+33 2 99 00 00 0{id}")
+                .parse()
+                .expect("demo phone numbers are valid"),
+            contacts: Vec::new(),
+        },
+        start_day: DayIndex(start_day),
+        duration: SiteDuration {
+            half_day: half_days,
+        },
+        status: SiteStatus::NotCarried,
+        overdue: false,
+        resources: SiteResource::default(),
+        workers: Vec::new(),
+        depends_on: Vec::new(),
+        status_history: Vec::new(),
+        anomalies: Vec::new(),
+        required_certifications: Vec::new(),
+        required_trades: Vec::new(),
+        priority: id as i32,
+        photos: Vec::new(),
+        created_by: created_by.to_owned(),
+        managers: vec![created_by.to_owned()],
+        responsible: Some(created_by.to_owned()),
+    }
+}
+
+fn demo_vehicle(id: u32, name: &str, kind: VehicleKind) -> Vehicle {
+    Vehicle::new(VehicleId(id), name.to_owned(), kind)
+}
+
+fn demo_worker(id: u32, name: &str, trade: Trade) -> Worker {
+    Worker {
+        id: WorkerId(id),
+        name: name.to_owned(),
+        trade,
+        certifications: Vec::new(),
+    }
+}
+
+/// Fills empty registries with the same demo data every time: a site in each status, workers,
+/// vehicles and reservations, and a worker assigned to two overlapping sites. Only compiled
+/// with the `dev` feature.
+#[post("/dev/seed")]
+pub fn seed(
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<SeedReport>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let mut fleet = fleet.lock();
+    let mut workers = workers.lock();
+    if !sites.is_empty() || !fleet.is_empty() || !workers.is_empty() {
+        return Err(ApiError::conflict(
+            "Demo data only goes into empty registries",
+        ));
+    }
+    let now: DateTime<Utc> = clock.now();
+
+    let mut planned = demo_site(1, "Library extension", 10, 8, &user.name);
+    planned.required_trades = vec![Trade::Mason, Trade::Roofer];
+    let mut started = demo_site(2, "School roof", 0, 12, &user.name);
+    started.set_status(SiteStatus::InProgress, now, None);
+    let mut completed = demo_site(3, "Car park", 0, 4, &user.name);
+    completed.set_status(SiteStatus::InProgress, now, None);
+    completed.set_status(SiteStatus::Completed, now, None);
+    let mut interrupted = demo_site(4, "Bridge repair", 2, 10, &user.name);
+    interrupted.set_status(SiteStatus::InProgress, now, None);
+    let reason = "Cracks found in the deck";
+    interrupted.anomalies.push(Anomaly {
+        reason: reason.to_owned(),
+        at: now,
+    });
+    interrupted.set_status(SiteStatus::Interrupted, now, Some(reason.to_owned()));
+
+    for worker in [
+        demo_worker(1, "Alice", Trade::Mason),
+        demo_worker(2, "Bob", Trade::Roofer),
+        demo_worker(3, "Chloé", Trade::Operator),
+    ] {
+        workers.insert(worker.id, worker);
+    }
+    started.workers = vec![WorkerId(2), WorkerId(3)];
+    completed.workers = vec![WorkerId(1)];
+    // Both sites overlap on days 2 to 5, so Chloé shows up in their worker conflicts.
+    interrupted.workers = vec![WorkerId(3)];
+
+    for vehicle in [
+        demo_vehicle(1, "Truck 1", VehicleKind::Truck),
+        demo_vehicle(2, "Truck 2", VehicleKind::Truck),
+        demo_vehicle(3, "Excavator", VehicleKind::Excavator),
+    ] {
+        fleet.insert(vehicle.id, vehicle);
+    }
+    started.resources.vehicles = vec![VehicleId(1), VehicleId(3)];
+    interrupted.resources.vehicles = vec![VehicleId(1), VehicleId(2)];
+
+    let rules = ReservationRules::default();
+    let calendar = &config.calendar;
+    let at = |day, period| {
+        let date = calendar.date_of_day(DayIndex(day));
+        (
+            date.expect("demo days are close to the project start"),
+            period,
+        )
+    };
+    let reservations = [
+        (VehicleId(1), started.id, at(0, Morning), at(1, Afternoon)),
+        (VehicleId(3), started.id, at(0, Afternoon), at(2, Morning)),
+        (VehicleId(1), interrupted.id, at(2, Morning), at(3, Morning)),
+        (
+            VehicleId(2),
+            interrupted.id,
+            at(3, Afternoon),
+            at(4, Afternoon),
+        ),
+    ];
+    for (vehicle_id, site_id, (start, start_period), (end, end_period)) in reservations {
+        let date = ReservedDate::from_dates(start, start_period, end, end_period)?
+            .for_site(site_id)
+            .made_by(Some(user.name.clone()), None);
+        if let Some(vehicle) = fleet.get_mut(&vehicle_id) {
+            vehicle.reserve(date, &rules)?;
+        }
+    }
+
+    for site in [planned, started, completed, interrupted] {
+        sites.insert(site.id, site);
+    }
+    Ok(Json(SeedReport {
+        sites: sites.len(),
+        workers: workers.len(),
+        vehicles: fleet.len(),
+        reservations: reservations.len(),
+    }))
+}
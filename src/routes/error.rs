@@ -0,0 +1,489 @@
+use rocket::http::Status;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::Request;
+use serde::Serialize;
+
+use crate::error::{
+    DateParsedErr, DayOutOfRangeErr, EstimateErr, HoldErr, ReservationErr, ShareErr, ShortenErr,
+    SiteError,
+};
+use crate::fleet::ConflictKind;
+use crate::i18n::{status_title, ErrorCode, Language};
+use crate::validation::FieldError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorObject {
+    pub status: String,
+    pub code: ErrorCode,
+    pub title: String,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<ErrorSource>,
+    /// Machine-readable details about the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+}
+
+/// Part of the request an error is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorSource {
+    pub pointer: String,
+}
+
+/// Error response shared by every endpoint: `{ "errors": [{ "status", "code", "title", "detail" }] }`.
+///
+/// Titles, and the details of errors whose code has a fixed message, are translated to the
+/// language of the request's `Accept-Language` header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: Status,
+    pub errors: Vec<ErrorObject>,
+}
+
+impl ApiError {
+    pub fn new(status: Status, detail: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            errors: vec![ErrorObject {
+                status: status.code.to_string(),
+                code: ErrorCode::for_status(status),
+                title: status.reason_lossy().to_string(),
+                detail: detail.into(),
+                source: None,
+                meta: None,
+            }],
+        }
+    }
+
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::BadRequest, detail)
+    }
+
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::Unauthorized, detail)
+    }
+
+    pub fn forbidden(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::Forbidden, detail)
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::NotFound, detail)
+    }
+
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::Conflict, detail)
+    }
+
+    pub fn unprocessable(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::UnprocessableEntity, detail)
+    }
+
+    pub fn internal(detail: impl Into<String>) -> Self {
+        ApiError::new(Status::InternalServerError, detail)
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        for error in &mut self.errors {
+            error.code = code;
+        }
+        self
+    }
+
+    /// Sets `key` in the `meta` of every error, turning it into an object if needed.
+    pub fn with_meta(mut self, key: &str, value: serde_json::Value) -> Self {
+        for error in &mut self.errors {
+            let meta = error
+                .meta
+                .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+            if !meta.is_object() {
+                *meta = serde_json::Value::Object(Default::default());
+            }
+            if let Some(meta) = meta.as_object_mut() {
+                meta.insert(key.to_owned(), value.clone());
+            }
+        }
+        self
+    }
+
+    fn localize(&mut self, language: Language) {
+        for error in &mut self.errors {
+            error.title = status_title(self.status, language);
+            if let Some(message) = error.code.message(language) {
+                error.detail = message.to_owned();
+            }
+        }
+    }
+}
+
+impl From<DateParsedErr> for ApiError {
+    fn from(err: DateParsedErr) -> Self {
+        ApiError::unprocessable(err.to_string()).with_code(ErrorCode::from(&err))
+    }
+}
+
+impl From<DayOutOfRangeErr> for ApiError {
+    fn from(err: DayOutOfRangeErr) -> Self {
+        ApiError::unprocessable(err.to_string())
+    }
+}
+
+impl From<ShortenErr> for ApiError {
+    fn from(err: ShortenErr) -> Self {
+        match err {
+            ShortenErr::NotReserved => ApiError::not_found(err.to_string()),
+            ShortenErr::NotShorter => ApiError::unprocessable(err.to_string()),
+        }
+    }
+}
+
+impl From<HoldErr> for ApiError {
+    fn from(err: HoldErr) -> Self {
+        match err {
+            HoldErr::NotHeld => ApiError::not_found(err.to_string()),
+        }
+    }
+}
+
+impl From<ShareErr> for ApiError {
+    fn from(err: ShareErr) -> Self {
+        match err {
+            ShareErr::Invalid => ApiError::unauthorized(err.to_string()),
+            ShareErr::Expired(_) => ApiError::new(Status::Gone, err.to_string()),
+        }
+    }
+}
+
+impl From<EstimateErr> for ApiError {
+    fn from(err: EstimateErr) -> Self {
+        match err {
+            EstimateErr::UnknownSite(_) => ApiError::not_found(err.to_string()),
+            EstimateErr::DependencyCycle(_) => ApiError::unprocessable(err.to_string()),
+            EstimateErr::DayOutOfRange(err) => err.into(),
+        }
+    }
+}
+
+impl From<SiteError> for ApiError {
+    fn from(err: SiteError) -> Self {
+        match err {
+            SiteError::Date(err) => err.into(),
+            SiteError::DayOutOfRange(err) => err.into(),
+            SiteError::Reservation(err) => err.into(),
+            SiteError::Shorten(err) => err.into(),
+            SiteError::Hold(err) => err.into(),
+            SiteError::Estimate(err) => err.into(),
+            SiteError::Share(err) => err.into(),
+        }
+    }
+}
+
+/// One 422 error per invalid field, each pointing at its field.
+impl From<Vec<FieldError>> for ApiError {
+    fn from(errors: Vec<FieldError>) -> Self {
+        let status = Status::UnprocessableEntity;
+        ApiError {
+            status,
+            errors: errors
+                .into_iter()
+                .map(|error| ErrorObject {
+                    status: status.code.to_string(),
+                    code: ErrorCode::for_status(status),
+                    title: status.reason_lossy().to_string(),
+                    detail: format!("{} {}", error.field, error.message),
+                    source: Some(ErrorSource {
+                        pointer: format!("/{}", error.field.replace('.', "/")),
+                    }),
+                    meta: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Describes a refused reservation, with the dates it conflicts with if any.
+pub fn reservation_detail(err: &ReservationErr) -> String {
+    match err {
+        ReservationErr::AlreadyReserved(conflict) if conflict.kind == ConflictKind::TooClose => {
+            format!(
+                "{err}: reserved from {} {:?} to {} {:?}, within the turnaround buffer",
+                conflict.reserved.first_date(),
+                conflict.reserved.start_period,
+                conflict.reserved.last_date(),
+                conflict.reserved.end_period,
+            )
+        }
+        ReservationErr::AlreadyReserved(conflict) => {
+            let overlapping: Vec<String> = conflict
+                .overlapping
+                .iter()
+                .map(|(date, period)| format!("{date} {period:?}"))
+                .collect();
+            format!(
+                "{err}: already reserved from {} {:?} to {} {:?}, overlapping on {}",
+                conflict.reserved.first_date(),
+                conflict.reserved.start_period,
+                conflict.reserved.last_date(),
+                conflict.reserved.end_period,
+                overlapping.join(", "),
+            )
+        }
+        ReservationErr::UnderMaintenance(maintenance) => format!(
+            "{err}: maintenance from {} {:?} to {} {:?}",
+            maintenance.window.first_date(),
+            maintenance.window.start_period,
+            maintenance.window.last_date(),
+            maintenance.window.end_period,
+        ),
+        ReservationErr::Blackout(blackout) => format!(
+            "{err}: blacked out from {} {:?} to {} {:?}",
+            blackout.blackout.period.first_date(),
+            blackout.blackout.period.start_period,
+            blackout.blackout.period.last_date(),
+            blackout.blackout.period.end_period,
+        ),
+        ReservationErr::VehicleInMaintenance
+        | ReservationErr::SiteCompleted
+        | ReservationErr::TooLong { .. } => err.to_string(),
+    }
+}
+
+impl From<ReservationErr> for ApiError {
+    fn from(err: ReservationErr) -> Self {
+        let detail = reservation_detail(&err);
+        let mut api_error = match err {
+            // Asking for too long a span is a mistake in the request, not a clash with others.
+            ReservationErr::TooLong { .. } => ApiError::unprocessable(detail),
+            _ => ApiError::conflict(detail),
+        }
+        .with_code(ErrorCode::from(&err));
+        match &err {
+            ReservationErr::AlreadyReserved(conflict) => {
+                api_error.errors[0].meta = serde_json::to_value(conflict).ok();
+            }
+            ReservationErr::UnderMaintenance(maintenance) => {
+                api_error.errors[0].meta = serde_json::to_value(maintenance).ok();
+            }
+            ReservationErr::Blackout(blackout) => {
+                api_error.errors[0].meta = serde_json::to_value(blackout).ok();
+            }
+            ReservationErr::VehicleInMaintenance
+            | ReservationErr::SiteCompleted
+            | ReservationErr::TooLong { .. } => {}
+        }
+        api_error
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(mut self, request: &'r Request<'_>) -> response::Result<'static> {
+        let language = request
+            .headers()
+            .get_one("Accept-Language")
+            .map(Language::negotiate)
+            .unwrap_or_default();
+        if language != Language::English {
+            self.localize(language);
+        }
+
+        let status = self.status;
+        response::Response::build_from(Json(self).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
+/// Answers requests no route matched with the error envelope rather than Rocket's HTML page.
+#[catch(404)]
+pub fn not_found(request: &Request<'_>) -> ApiError {
+    ApiError::not_found(format!(
+        "Nothing at {} {}",
+        request.method(),
+        request.uri().path()
+    ))
+}
+
+/// Answers requests whose path parameters, e.g. the id of `/sites/abc`, or body did not parse
+/// into what the route expects.
+#[catch(422)]
+pub fn unprocessable(request: &Request<'_>) -> ApiError {
+    ApiError::unprocessable(format!(
+        "The path or body of {} {} could not be parsed",
+        request.method(),
+        request.uri().path()
+    ))
+}
+
+/// Answers every other error status, e.g. a 401 from a missing login or a 403 from a failed
+/// CSRF check, with the error envelope rather than Rocket's HTML page.
+#[catch(default)]
+pub fn fallback(status: Status, request: &Request<'_>) -> ApiError {
+    let detail = match status.code {
+        400 => format!(
+            "{} {} is not a valid request",
+            request.method(),
+            request.uri().path()
+        ),
+        401 => format!("Log in to {} {}", request.method(), request.uri().path()),
+        403 => format!(
+            "Not allowed to {} {}",
+            request.method(),
+            request.uri().path()
+        ),
+        _ => format!(
+            "{} {} failed: {}",
+            request.method(),
+            request.uri().path(),
+            status.reason_lossy()
+        ),
+    };
+    ApiError::new(status, detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::blackouts::{Blackout, BlackoutId};
+    use crate::calendar::DayIndex;
+    use crate::error::{AlreadyReservedInThatPeriodErr, BlackoutErr, VehicleUnderMaintenanceErr};
+    use crate::sites::{DayPeriod, ReservedDate, SiteId};
+
+    fn status(err: impl Into<ApiError>) -> Status {
+        err.into().status
+    }
+
+    fn reserved() -> ReservedDate {
+        ReservedDate::new(
+            "2024-03-04",
+            DayPeriod::Morning,
+            "2024-03-04",
+            DayPeriod::Afternoon,
+        )
+        .unwrap()
+    }
+
+    fn reservation_errors() -> Vec<(ReservationErr, Status)> {
+        vec![
+            (
+                ReservationErr::AlreadyReserved(Box::new(AlreadyReservedInThatPeriodErr {
+                    reserved: reserved(),
+                    kind: ConflictKind::Full,
+                    overlapping: reserved().half_days(),
+                })),
+                Status::Conflict,
+            ),
+            (ReservationErr::VehicleInMaintenance, Status::Conflict),
+            (
+                ReservationErr::UnderMaintenance(VehicleUnderMaintenanceErr { window: reserved() }),
+                Status::Conflict,
+            ),
+            (ReservationErr::SiteCompleted, Status::Conflict),
+            (
+                ReservationErr::Blackout(BlackoutErr {
+                    blackout: Blackout {
+                        id: BlackoutId(1),
+                        period: reserved(),
+                    },
+                }),
+                Status::Conflict,
+            ),
+            (
+                ReservationErr::TooLong {
+                    days: 800,
+                    max: 730,
+                },
+                Status::UnprocessableEntity,
+            ),
+        ]
+    }
+
+    #[test]
+    fn date_errors_are_unprocessable() {
+        for err in [
+            DateParsedErr::InvalidStartDate,
+            DateParsedErr::InvalidEndDate,
+            DateParsedErr::StartAfterEnd,
+        ] {
+            assert_eq!(status(err.clone()), Status::UnprocessableEntity, "{err:?}");
+        }
+        assert_eq!(
+            status(DayOutOfRangeErr(DayIndex(usize::MAX))),
+            Status::UnprocessableEntity
+        );
+    }
+
+    #[test]
+    fn reservation_errors_are_conflicts_but_for_too_long_a_span() {
+        for (err, expected) in reservation_errors() {
+            assert_eq!(status(err.clone()), expected, "{err:?}");
+        }
+    }
+
+    #[test]
+    fn shorten_errors_map_to_their_status() {
+        assert_eq!(status(ShortenErr::NotReserved), Status::NotFound);
+        assert_eq!(status(ShortenErr::NotShorter), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn hold_errors_map_to_their_status() {
+        assert_eq!(status(HoldErr::NotHeld), Status::NotFound);
+    }
+
+    #[test]
+    fn site_errors_keep_the_status_of_the_error_they_wrap() {
+        for (err, expected) in reservation_errors() {
+            assert_eq!(status(SiteError::from(err.clone())), expected, "{err:?}");
+        }
+        let expired = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        let others = [
+            (
+                SiteError::from(DateParsedErr::StartAfterEnd),
+                Status::UnprocessableEntity,
+            ),
+            (
+                SiteError::from(DayOutOfRangeErr(DayIndex(usize::MAX))),
+                Status::UnprocessableEntity,
+            ),
+            (SiteError::from(ShortenErr::NotReserved), Status::NotFound),
+            (SiteError::from(HoldErr::NotHeld), Status::NotFound),
+            (
+                SiteError::from(EstimateErr::DependencyCycle(SiteId(1))),
+                Status::UnprocessableEntity,
+            ),
+            (SiteError::from(ShareErr::Expired(expired)), Status::Gone),
+        ];
+        for (err, expected) in others {
+            assert_eq!(status(err.clone()), expected, "{err:?}");
+        }
+    }
+
+    #[test]
+    fn share_errors_map_to_their_status() {
+        assert_eq!(status(ShareErr::Invalid), Status::Unauthorized);
+        let expired = Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap();
+        assert_eq!(status(ShareErr::Expired(expired)), Status::Gone);
+    }
+
+    #[test]
+    fn estimate_errors_map_to_their_status() {
+        assert_eq!(
+            status(EstimateErr::UnknownSite(SiteId(1))),
+            Status::NotFound
+        );
+        assert_eq!(
+            status(EstimateErr::DependencyCycle(SiteId(1))),
+            Status::UnprocessableEntity
+        );
+        assert_eq!(
+            status(EstimateErr::DayOutOfRange(DayOutOfRangeErr(DayIndex(
+                usize::MAX
+            )))),
+            Status::UnprocessableEntity
+        );
+    }
+}
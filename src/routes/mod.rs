@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use rocket::form::{FromFormField, ValueField};
+use rocket::http::{ContentType, MediaType};
+use rocket::response::{self, Redirect, Responder};
+use rocket::serde::json::Json;
+use rocket::{Request, State};
+
+use crate::auth::User;
+use crate::config::{AnonymousLanding, AppConfig};
+use crate::routes::error::ApiError;
+
+pub mod auth;
+pub mod blackouts;
+pub mod calendar;
+pub mod clients;
+#[cfg(feature = "dev")]
+pub mod dev;
+pub mod error;
+pub mod photos;
+pub mod projects;
+pub mod reservations;
+pub mod resources;
+pub mod server;
+pub mod share;
+pub mod sites;
+pub mod supplies;
+pub mod vehicles;
+pub mod warnings;
+pub mod workers;
+
+#[get("/")]
+pub fn index(user: User) -> Index {
+    Index(user)
+}
+
+/// Greeting for a logged in user: an HTML page, the `/me` JSON payload or plain text, as
+/// preferred by the client's `Accept` header.
+#[derive(Debug, Clone)]
+pub struct Index(pub User);
+
+impl Index {
+    fn to_text(&self) -> String {
+        format!(
+            "Hello, {}! You are logged in as {:?}.",
+            self.0.name, self.0.role
+        )
+    }
+
+    fn to_html(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head><title>Construction sites</title></head>\n<body>\n\
+             <p>{}</p>\n<ul>\n\
+             <li><a href=\"/me\">Me</a></li>\n\
+             <li><a href=\"/sites\">Sites</a></li>\n\
+             <li><a href=\"/vehicles\">Vehicles</a></li>\n\
+             <li><a href=\"/clients\">Clients</a></li>\n\
+             </ul>\n</body>\n</html>\n",
+            html_escape(&self.to_text())
+        )
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Index {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let preferred = request
+            .accept()
+            .map(|accept| accept.preferred().media_type().clone());
+
+        match preferred {
+            Some(media_type) if media_type == MediaType::HTML => {
+                (ContentType::HTML, self.to_html()).respond_to(request)
+            }
+            Some(media_type) if media_type == MediaType::JSON => Json(self.0).respond_to(request),
+            _ => self.to_text().respond_to(request),
+        }
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[get("/", rank = 2)]
+pub fn index_anonymous(config: &State<AppConfig>) -> AnonymousIndex {
+    AnonymousIndex(config.anonymous_landing)
+}
+
+/// Answer of `/` to a client that is not logged in, see [`AnonymousLanding`].
+#[derive(Debug, Clone, Copy)]
+pub struct AnonymousIndex(pub AnonymousLanding);
+
+impl<'r> Responder<'r, 'static> for AnonymousIndex {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let wants_json = request
+            .accept()
+            .is_some_and(|accept| accept.preferred().media_type() == &MediaType::JSON);
+        let landing = if wants_json {
+            AnonymousLanding::Unauthorized
+        } else {
+            self.0
+        };
+
+        match landing {
+            AnonymousLanding::Prompt => {
+                "Hello! Please log in through /login/github.".respond_to(request)
+            }
+            AnonymousLanding::Redirect => Redirect::to(uri!("/login/github")).respond_to(request),
+            AnonymousLanding::Unauthorized => {
+                ApiError::unauthorized("log in through /login/github").respond_to(request)
+            }
+        }
+    }
+}
+
+/// Parses a `YYYY-MM-DD` query parameter named `name`.
+pub fn parse_date_param(name: &str, value: &str) -> Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        ApiError::unprocessable(format!("`{name}` is not a valid YYYY-MM-DD date: {value}"))
+    })
+}
+
+/// Parses a query parameter named `name` whose values are those of `T`, e.g. an enum
+/// deriving `FromFormField`. Unlike an `Option<T>` parameter, an unknown value is refused.
+pub fn parse_choice_param<'v, T: FromFormField<'v>>(
+    name: &str,
+    value: &'v str,
+) -> Result<T, ApiError> {
+    T::from_value(ValueField::from_value(value))
+        .map_err(|_| ApiError::unprocessable(format!("`{name}` has an unknown value: {value}")))
+}
+
+/// Parses a numeric query parameter named `name`.
+pub fn parse_number_param<T: FromStr>(name: &str, value: &str) -> Result<T, ApiError> {
+    value
+        .parse()
+        .map_err(|_| ApiError::unprocessable(format!("`{name}` is not a valid number: {value}")))
+}
+
+/// Parses the `from` and `to` query parameters of a range spanning at most `max_days` days.
+pub fn parse_date_range(
+    from: &str,
+    to: &str,
+    max_days: i64,
+) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let from = parse_date_param("from", from)?;
+    let to = parse_date_param("to", to)?;
+
+    if from > to {
+        return Err(ApiError::unprocessable("`from` is after `to`"));
+    }
+    if (to - from).num_days() >= max_days {
+        return Err(ApiError::unprocessable(format!(
+            "range is limited to {max_days} days"
+        )));
+    }
+    Ok((from, to))
+}
@@ -0,0 +1,83 @@
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Deserialize;
+
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::photos::{Photo, PhotoId};
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::visible_site_mut;
+use crate::sites::{SiteId, Sites};
+use crate::validation::FieldError;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewPhoto {
+    pub url: String,
+    #[serde(default)]
+    pub caption: Option<String>,
+}
+
+impl NewPhoto {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if !(self.url.starts_with("https://") || self.url.starts_with("http://")) {
+            errors.push(FieldError::new("url", "must be an http or https URL"));
+        }
+        errors
+    }
+}
+
+/// Adds a photo to a site. The first photo of a site becomes its cover.
+#[post("/sites/<id>/photos", data = "<photo>")]
+pub fn add_photo(
+    id: SiteId,
+    photo: Json<NewPhoto>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<Created<Json<Photo>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = photo.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let NewPhoto { url, caption } = photo.into_inner();
+    let photo = Photo {
+        id: site.next_photo_id(),
+        url,
+        caption,
+        is_cover: site.photos.is_empty(),
+    };
+    site.photos.push(photo.clone());
+
+    Ok(Created::new(format!("/sites/{id}/photos/{}", photo.id)).body(Json(photo)))
+}
+
+/// Makes a photo the site's cover, the previous cover no longer being one.
+#[post("/sites/<id>/photos/<photo_id>/cover")]
+pub fn set_cover(
+    id: SiteId,
+    photo_id: PhotoId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<Json<Photo>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let index = site
+        .photos
+        .iter()
+        .position(|photo| photo.id == photo_id)
+        .ok_or_else(|| ApiError::not_found(format!("No photo {photo_id} on site {id}")))?;
+    for photo in &mut site.photos {
+        photo.is_cover = photo.id == photo_id;
+    }
+
+    Ok(Json(site.photos[index].clone()))
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDate;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::auth::User;
+use crate::config::{AppConfig, LabeledPeriod};
+use crate::estimate::estimate;
+use crate::routes::error::ApiError;
+use crate::routes::sites::visible_site;
+use crate::sites::{SiteId, Sites};
+use crate::validation::FieldError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EstimatedSite {
+    pub site_id: SiteId,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub start_period: LabeledPeriod,
+    pub end_date: NaiveDate,
+    pub end_period: LabeledPeriod,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectEstimate {
+    /// Last half-day worked across the sites, `None` when none of them is worked at all.
+    pub completion_date: Option<NaiveDate>,
+    pub completion_period: Option<LabeledPeriod>,
+    /// The requested sites and those they depend on, each after its dependencies.
+    pub sites: Vec<EstimatedSite>,
+}
+
+/// Projected completion of the comma-separated `sites`, e.g. `?sites=1,2`, waiting for the
+/// sites they depend on. Only the sites the user may see are listed, but every one of them
+/// counts towards the completion date.
+#[get("/projects/estimate?<sites>")]
+pub fn estimate_project(
+    sites: &str,
+    user: User,
+    registry: &State<Sites>,
+    config: &State<AppConfig>,
+) -> Result<Json<ProjectEstimate>, ApiError> {
+    let ids = sites
+        .split(',')
+        .map(|id| id.trim().parse().map(SiteId))
+        .collect::<Result<Vec<SiteId>, _>>()
+        .map_err(|_| vec![FieldError::new("sites", "must be comma-separated site ids")])?;
+
+    let registry = registry.read();
+    for id in &ids {
+        visible_site(&registry, *id, &user)?;
+    }
+    let estimates = estimate(&registry, &ids, &config.calendar)?;
+
+    let completion = estimates.iter().map(|estimate| estimate.end).max();
+    Ok(Json(ProjectEstimate {
+        completion_date: completion.map(|(date, _)| date),
+        completion_period: completion.map(|(_, period)| config.period_labels.labeled(period)),
+        sites: estimates
+            .into_iter()
+            .filter(|estimate| registry[&estimate.site_id].visible_to(&user))
+            .map(|estimate| EstimatedSite {
+                site_id: estimate.site_id,
+                name: registry[&estimate.site_id].name.clone(),
+                start_date: estimate.start.0,
+                start_period: config.period_labels.labeled(estimate.start.1),
+                end_date: estimate.end.0,
+                end_period: config.period_labels.labeled(estimate.end.1),
+            })
+            .collect(),
+    }))
+}
+
+/// A site as Gantt chart libraries expect it.
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttTask {
+    pub id: SiteId,
+    pub name: String,
+    /// First day the site is worked.
+    pub start: NaiveDate,
+    /// Last day the site is worked.
+    pub end: NaiveDate,
+    /// See [`crate::sites::Site::progress`].
+    pub progress: u8,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GanttChart {
+    pub tasks: Vec<GanttTask>,
+}
+
+/// The sites the user may see as Gantt tasks by id, over their planned working days. Sites
+/// without duration are left out.
+#[get("/schedule/gantt")]
+pub fn gantt(user: User, sites: &State<Sites>, config: &State<AppConfig>) -> Json<GanttChart> {
+    let sites = sites.read();
+    let mut tasks: Vec<GanttTask> = sites
+        .values()
+        .filter(|site| site.visible_to(&user))
+        .filter_map(|site| {
+            let periods = site.working_periods(&config.calendar);
+            let ((start, _), (end, _)) = periods.first().zip(periods.last())?;
+            Some(GanttTask {
+                id: site.id,
+                name: site.name.clone(),
+                start: *start,
+                end: *end,
+                progress: site.progress(),
+            })
+        })
+        .collect();
+    tasks.sort_by_key(|task| task.id);
+    Json(GanttChart { tasks })
+}
@@ -0,0 +1,353 @@
+use chrono::TimeDelta;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::audit::AuditLog;
+use crate::auth::{AppRole, CsrfChecked, Permission, User};
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::error::ReservationErr;
+use crate::fleet::{Fleet, ReservationRules, Vehicle, VehicleId, VehicleKind};
+use crate::routes::auth::require;
+use crate::routes::error::{reservation_detail, ApiError};
+use crate::routes::parse_date_range;
+use crate::routes::sites::visible_site;
+use crate::routes::vehicles::record_served;
+use crate::routes::warnings::{Warned, Warning, WarningCode};
+use crate::sites::{DayPeriod, ReservedDate, SiteId, Sites};
+
+/// A reservation with the names of its site and vehicle.
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleReservation {
+    pub vehicle_id: VehicleId,
+    pub vehicle_name: String,
+    pub vehicle_kind: VehicleKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_id: Option<SiteId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    pub reservation: ReservedDate,
+}
+
+/// Longest range, in days, a single reservations search may cover.
+const MAX_SEARCH_DAYS: i64 = 366;
+
+/// Days listed by `/reservations/ending-soon` when not told otherwise.
+const DEFAULT_ENDING_SOON_DAYS: u32 = 3;
+/// Most days ahead `/reservations/ending-soon` may look.
+const MAX_ENDING_SOON_DAYS: u32 = 366;
+
+/// Columns of a reservations import, in order. A first row equal to it is skipped.
+const IMPORT_HEADER: &str = "vehicle_id,start_date,start_period,end_date,end_period";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RowOutcome {
+    Reserved,
+    Conflict,
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedRow {
+    /// Line of the row in the submitted CSV, starting at `1`.
+    pub line: usize,
+    pub outcome: RowOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vehicle_id: Option<VehicleId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation: Option<ReservedDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Existing reservation the row ran into.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicting: Option<ReservedDate>,
+}
+
+impl ImportedRow {
+    fn invalid(line: usize, detail: impl Into<String>) -> Self {
+        ImportedRow {
+            line,
+            outcome: RowOutcome::Invalid,
+            vehicle_id: None,
+            reservation: None,
+            detail: Some(detail.into()),
+            conflicting: None,
+        }
+    }
+}
+
+fn parse_period(value: &str) -> Option<DayPeriod> {
+    match value {
+        "Morning" => Some(DayPeriod::Morning),
+        "Afternoon" => Some(DayPeriod::Afternoon),
+        _ => None,
+    }
+}
+
+/// Parses a `vehicle_id,start_date,start_period,end_date,end_period` row.
+fn parse_row(row: &str) -> Result<(VehicleId, ReservedDate), String> {
+    let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+    let [vehicle_id, start_date, start_period, end_date, end_period] = fields[..] else {
+        return Err(format!("expected the columns {IMPORT_HEADER}"));
+    };
+    let vehicle_id = vehicle_id
+        .parse()
+        .map(VehicleId)
+        .map_err(|_| format!("invalid vehicle id {vehicle_id}"))?;
+    let start_period =
+        parse_period(start_period).ok_or_else(|| format!("invalid period {start_period}"))?;
+    let end_period =
+        parse_period(end_period).ok_or_else(|| format!("invalid period {end_period}"))?;
+    let date = ReservedDate::new(start_date, start_period, end_date, end_period)
+        .map_err(|err| err.to_string())?;
+    Ok((vehicle_id, date))
+}
+
+/// Reserves the site's vehicles from a CSV schedule, one reservation per row, in order.
+///
+/// Every row gets an outcome; rows that are invalid or conflict with an existing reservation,
+/// including one made by a previous row, are skipped while the others are kept.
+#[post("/sites/<id>/reservations/import", data = "<csv>")]
+#[allow(clippy::too_many_arguments)]
+pub fn import_reservations(
+    id: SiteId,
+    csv: &str,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    clock: &State<SharedClock>,
+) -> Result<Warned<Vec<ImportedRow>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let warning = site
+        .status
+        .reservation_warning()?
+        .map(|message| Warning::new(WarningCode::SiteInterrupted, message));
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let rows = csv
+        .lines()
+        .enumerate()
+        .map(|(index, row)| (index + 1, row))
+        .filter(|(line, row)| {
+            let row = row.trim();
+            let is_header = *line == 1 && row == IMPORT_HEADER;
+            !row.is_empty() && !is_header
+        })
+        .map(|(line, row)| {
+            let (vehicle_id, date) = match parse_row(row) {
+                Ok(parsed) => parsed,
+                Err(detail) => return ImportedRow::invalid(line, detail),
+            };
+            if !site.resources.vehicles.contains(&vehicle_id) {
+                return ImportedRow::invalid(
+                    line,
+                    format!("Vehicle {vehicle_id} is not used by site {id}"),
+                );
+            }
+            let Some(vehicle) = fleet.get_mut(&vehicle_id) else {
+                return ImportedRow::invalid(line, format!("No vehicle with id {vehicle_id}"));
+            };
+
+            let date = date.for_site(id).made_by(Some(user.name.clone()), None);
+            let (outcome, detail, conflicting) = match vehicle.reserve(date.clone(), &rules) {
+                Ok(()) => (RowOutcome::Reserved, None, None),
+                Err(err) => {
+                    let conflicting = match &err {
+                        ReservationErr::AlreadyReserved(conflict) => {
+                            Some(conflict.reserved.clone())
+                        }
+                        _ => None,
+                    };
+                    (
+                        RowOutcome::Conflict,
+                        Some(reservation_detail(&err)),
+                        conflicting,
+                    )
+                }
+            };
+            ImportedRow {
+                line,
+                outcome,
+                vehicle_id: Some(vehicle_id),
+                reservation: Some(date),
+                detail,
+                conflicting,
+            }
+        })
+        .collect();
+
+    Ok(warning.into_iter().fold(Warned::new(rows), Warned::warn))
+}
+
+/// Reservations of every vehicle kept by `keep`, unsorted. Site managers only get those of
+/// the sites they manage, see [`crate::sites::Site::visible_to`].
+fn find_reservations(
+    user: &User,
+    sites: &Sites,
+    fleet: &Fleet,
+    keep: impl Fn(&Vehicle, &ReservedDate) -> bool,
+) -> Vec<VehicleReservation> {
+    let sites = sites.read();
+    let fleet = fleet.lock();
+    let visible = |reserved: &ReservedDate| match reserved.site_id {
+        Some(site_id) => sites
+            .get(&site_id)
+            .is_some_and(|site| site.visible_to(user)),
+        None => user.role == AppRole::SitesGlobalManager,
+    };
+
+    fleet
+        .values()
+        .flat_map(|vehicle| {
+            vehicle
+                .reserved_dates
+                .iter()
+                .filter(|reserved| visible(reserved) && keep(vehicle, reserved))
+                .map(|reserved| VehicleReservation {
+                    vehicle_id: vehicle.id,
+                    vehicle_name: vehicle.name.clone(),
+                    vehicle_kind: vehicle.kind,
+                    site_id: reserved.site_id,
+                    site_name: reserved
+                        .site_id
+                        .and_then(|site_id| sites.get(&site_id))
+                        .map(|site| site.name.clone()),
+                    reservation: reserved.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Reservations made by the logged in user across all vehicles, sorted by start.
+#[get("/reservations/mine")]
+pub fn my_reservations(
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+) -> Json<Vec<VehicleReservation>> {
+    let mut reservations = find_reservations(&user, sites, fleet, |_, reserved| {
+        reserved.reserved_by.as_deref() == Some(user.name.as_str())
+    });
+    reservations.sort_by(|a, b| {
+        a.reservation
+            .cmp(&b.reservation)
+            .then(a.vehicle_id.cmp(&b.vehicle_id))
+    });
+
+    Json(reservations)
+}
+
+/// Reservations ending between today and `days` days from now (3 by default, at most
+/// [`MAX_ENDING_SOON_DAYS`]), the first to end first, so vehicles can be collected.
+#[get("/reservations/ending-soon?<days>")]
+pub fn ending_soon(
+    days: Option<u32>,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Vec<VehicleReservation>>, ApiError> {
+    let days = days.unwrap_or(DEFAULT_ENDING_SOON_DAYS);
+    if days > MAX_ENDING_SOON_DAYS {
+        return Err(ApiError::unprocessable(format!(
+            "`days` must be at most {MAX_ENDING_SOON_DAYS}"
+        )));
+    }
+    let today = clock.today();
+    let until = today + TimeDelta::days(i64::from(days));
+    let mut reservations = find_reservations(&user, sites, fleet, |_, reserved| {
+        (today..=until).contains(&reserved.last_date())
+    });
+    reservations.sort_by_key(|reservation| {
+        (
+            reservation.reservation.last_date(),
+            reservation.reservation.end_period,
+            reservation.vehicle_id,
+        )
+    });
+
+    Ok(Json(reservations))
+}
+
+/// Reservations intersecting the days from `from` to `to`, both included, of vehicles of
+/// `vehicle_kind` if given, sorted by start.
+#[get("/reservations?<from>&<to>&<vehicle_kind>")]
+pub fn search_reservations(
+    from: &str,
+    to: &str,
+    vehicle_kind: Option<VehicleKind>,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+) -> Result<Json<Vec<VehicleReservation>>, ApiError> {
+    let (from, to) = parse_date_range(from, to, MAX_SEARCH_DAYS)?;
+    let range = ReservedDate::from_dates(from, DayPeriod::Morning, to, DayPeriod::Afternoon)?;
+
+    let mut reservations = find_reservations(&user, sites, fleet, |vehicle, reserved| {
+        vehicle_kind.is_none_or(|kind| vehicle.kind == kind) && !reserved.compatible_with(&range)
+    });
+    reservations.sort_by(|a, b| {
+        a.reservation
+            .cmp(&b.reservation)
+            .then(a.vehicle_id.cmp(&b.vehicle_id))
+    });
+
+    Ok(Json(reservations))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClearedReservations {
+    pub site_id: SiteId,
+    /// Number of reservations cancelled, over every vehicle of the site.
+    pub cleared: usize,
+}
+
+/// Cancels every reservation made for a site on its vehicles, e.g. when the site is called
+/// off, along with its holds and waitlisted requests. Waitlisted requests of other sites are
+/// then reserved where they fit.
+#[post("/sites/<id>/reservations/clear")]
+#[allow(clippy::too_many_arguments)]
+pub fn clear_reservations(
+    id: SiteId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<ClearedReservations>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+
+    let now = clock.now();
+    let mut fleet = fleet.lock_pruned(now);
+    let mut cleared = 0;
+    for vehicle_id in &site.resources.vehicles {
+        let Some(vehicle) = fleet.get_mut(vehicle_id) else {
+            continue;
+        };
+        cleared += vehicle.clear_site(id);
+        let served = vehicle.serve_waitlist(&rules);
+        record_served(audit, now, *vehicle_id, &served, ip);
+    }
+
+    audit.record_request(
+        now,
+        user.name,
+        format!("clear_reservations {cleared}"),
+        Some(id),
+        ip,
+    );
+    Ok(Json(ClearedReservations {
+        site_id: id,
+        cleared,
+    }))
+}
@@ -0,0 +1,130 @@
+use chrono::NaiveDate;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::auth::User;
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, LabeledPeriod};
+use crate::fleet::{Fleet, ReservationRules, VehicleId, VehicleKind};
+use crate::routes::error::ApiError;
+use crate::routes::{parse_choice_param, parse_date_range};
+use crate::sites::{DayPeriod, HalfDayIter, ReservedDate};
+
+/// Longest range, in days, a single heatmap request may cover.
+const MAX_HEATMAP_DAYS: i64 = 90;
+/// Longest range, in days, a single matrix request may cover.
+const MAX_MATRIX_DAYS: i64 = 31;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeatmapSlot {
+    pub date: NaiveDate,
+    pub period: DayPeriod,
+    pub label: String,
+    /// Number of fleet vehicles reserved during that half-day.
+    pub reserved: usize,
+}
+
+#[get("/resources/heatmap?<from>&<to>")]
+pub fn heatmap(
+    from: &str,
+    to: &str,
+    _user: User,
+    fleet: &State<Fleet>,
+    config: &State<AppConfig>,
+) -> Result<Json<Vec<HeatmapSlot>>, ApiError> {
+    let (from, to) = parse_date_range(from, to, MAX_HEATMAP_DAYS)?;
+
+    let fleet = fleet.lock();
+    let slots = HalfDayIter::days(from, to)
+        .map(|(date, period)| {
+            let slot = ReservedDate::half_day(date, period);
+            let reserved = fleet
+                .values()
+                .filter(|vehicle| {
+                    vehicle
+                        .reserved_dates
+                        .iter()
+                        .any(|reserved| !reserved.compatible_with(&slot))
+                })
+                .count();
+            HeatmapSlot {
+                date,
+                period,
+                label: config.period_labels.label(period).to_owned(),
+                reserved,
+            }
+        })
+        .collect();
+
+    Ok(Json(slots))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatrixSlot {
+    pub date: NaiveDate,
+    pub period: LabeledPeriod,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatrixRow {
+    pub vehicle_id: VehicleId,
+    pub name: String,
+    pub kind: VehicleKind,
+    /// Whether the vehicle could be reserved for each slot, in the order of the slots.
+    pub free: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AvailabilityMatrix {
+    pub slots: Vec<MatrixSlot>,
+    /// Fleet vehicles, of `kind` if given, by id.
+    pub vehicles: Vec<MatrixRow>,
+}
+
+/// Which half-days from `from` to `to` each vehicle is free in, held or reserved ones
+/// and maintenance counting as busy.
+#[get("/resources/matrix?<from>&<to>&<kind>")]
+#[allow(clippy::too_many_arguments)]
+pub fn matrix(
+    from: &str,
+    to: &str,
+    kind: Option<&str>,
+    _user: User,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<AvailabilityMatrix>, ApiError> {
+    let (from, to) = parse_date_range(from, to, MAX_MATRIX_DAYS)?;
+    let kind: Option<VehicleKind> = kind
+        .map(|kind| parse_choice_param("kind", kind))
+        .transpose()?;
+
+    let half_days: Vec<ReservedDate> = HalfDayIter::days(from, to)
+        .map(|(date, period)| ReservedDate::half_day(date, period))
+        .collect();
+    let fleet = fleet.lock_pruned(clock.now());
+    let mut vehicles: Vec<MatrixRow> = fleet
+        .values()
+        .filter(|vehicle| kind.is_none_or(|kind| vehicle.kind == kind))
+        .map(|vehicle| MatrixRow {
+            vehicle_id: vehicle.id,
+            name: vehicle.name.clone(),
+            kind: vehicle.kind,
+            free: half_days
+                .iter()
+                .map(|slot| vehicle.check_available(slot, &rules).is_ok())
+                .collect(),
+        })
+        .collect();
+    vehicles.sort_by_key(|row| row.vehicle_id);
+
+    let slots = HalfDayIter::days(from, to)
+        .map(|(date, period)| MatrixSlot {
+            date,
+            period: config.period_labels.labeled(period),
+        })
+        .collect();
+    Ok(Json(AvailabilityMatrix { slots, vehicles }))
+}
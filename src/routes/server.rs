@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, WorkHours};
+
+/// Dates and reservation instants are all handled in UTC, there is no timezone setting.
+const TIMEZONE: &str = "UTC";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerInfo {
+    pub now: DateTime<Utc>,
+    pub timezone: &'static str,
+    pub work_hours: WorkHours,
+}
+
+/// What clients need to compute half-day slots the way the server does.
+#[get("/server-info")]
+pub fn server_info(clock: &State<SharedClock>, config: &State<AppConfig>) -> Json<ServerInfo> {
+    Json(ServerInfo {
+        now: clock.now(),
+        timezone: TIMEZONE,
+        work_hours: config.work_hours,
+    })
+}
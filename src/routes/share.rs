@@ -0,0 +1,121 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use rocket::http::Status;
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Serialize;
+
+use crate::audit::AuditLog;
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::config::AppConfig;
+use crate::photos::Photo;
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::{site_not_found, ScheduleWindow};
+use crate::share::ShareToken;
+use crate::sites::{SiteId, SiteStatus, Sites};
+
+/// Hours a share link lasts when not told otherwise.
+const DEFAULT_SHARE_HOURS: u32 = 7 * 24;
+/// Longest a share link may last, 30 days.
+const MAX_SHARE_HOURS: u32 = 30 * 24;
+
+fn share_secret(config: &AppConfig) -> Result<&str, ApiError> {
+    config.share_secret.as_deref().ok_or_else(|| {
+        ApiError::new(
+            Status::ServiceUnavailable,
+            "Share links are disabled, set `share_secret` to enable them",
+        )
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    pub site_id: SiteId,
+    pub token: String,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Hands out a link to view a site without logging in, valid for `hours`.
+#[post("/sites/<id>/share?<hours>")]
+#[allow(clippy::too_many_arguments)]
+pub fn share_site(
+    id: SiteId,
+    hours: Option<u32>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Created<Json<ShareLink>>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let hours = hours.unwrap_or(DEFAULT_SHARE_HOURS);
+    if !(1..=MAX_SHARE_HOURS).contains(&hours) {
+        return Err(ApiError::unprocessable(format!(
+            "`hours` must be between 1 and {MAX_SHARE_HOURS}"
+        )));
+    }
+    let secret = share_secret(config)?;
+    if !sites.read().contains_key(&id) {
+        return Err(site_not_found(id));
+    }
+
+    let now = clock.now();
+    let share = ShareToken {
+        site_id: id,
+        expires_at: now + TimeDelta::hours(i64::from(hours)),
+    };
+    let token = share.sign(secret);
+    audit.record_request(now, user.name, format!("share {hours}h"), Some(id), ip);
+
+    let url = format!("/shared/{token}");
+    Ok(Created::new(url.clone()).body(Json(ShareLink {
+        site_id: id,
+        token,
+        url,
+        expires_at: share.expires_at,
+    })))
+}
+
+/// What a share link shows of a site: its progress, not its crew nor its client.
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedSite {
+    pub site_id: SiteId,
+    pub name: String,
+    pub status: SiteStatus,
+    pub overdue: bool,
+    /// `None` for a site without duration.
+    pub schedule: Option<ScheduleWindow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_photo: Option<Photo>,
+    pub link_expires_at: DateTime<Utc>,
+}
+
+/// The site a share link was handed out for. 401 for a tampered link, 410 once it expired.
+#[get("/shared/<token>")]
+pub fn shared_site(
+    token: &str,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<SharedSite>, ApiError> {
+    let share = ShareToken::verify(token, share_secret(config)?, clock.now())?;
+    let sites = sites.read();
+    let site = sites
+        .get(&share.site_id)
+        .ok_or_else(|| site_not_found(share.site_id))?;
+    Ok(Json(SharedSite {
+        site_id: site.id,
+        name: site.name.clone(),
+        status: site.status,
+        overdue: site.overdue,
+        schedule: ScheduleWindow::of(site, config),
+        cover_photo: site.cover_photo().cloned(),
+        link_expires_at: share.expires_at,
+    }))
+}
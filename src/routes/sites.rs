@@ -0,0 +1,1541 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rocket::http::{ContentType, MediaType, Status};
+use rocket::response::status::Created;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::{Request, Response, State};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditLog;
+use crate::auth::{AppRole, CsrfChecked, Permission, User, Users};
+use crate::calendar::DayIndex;
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, LabeledPeriod};
+use crate::fleet::{alternatives, Fleet, ReservationRules, VehicleId, VehicleKind};
+use crate::photos::Photo;
+use crate::routes::auth::require;
+use crate::routes::error::{reservation_detail, ApiError};
+use crate::routes::warnings::{Warned, Warning, WarningCode};
+use crate::routes::workers::{certification_check, check_accepts_workers};
+use crate::routes::{parse_choice_param, parse_date_param, parse_date_range, parse_number_param};
+use crate::sites::{
+    Anomaly, BoundingBox, Client, Contact, Coordinates, DayPeriod, ReservedDate, Site,
+    SiteDuration, SiteId, SiteResource, SiteStatus, Sites,
+};
+use crate::validation::{
+    bounds_validation, client_validation, contact_validation, dependencies_validation,
+    fields_validation, horizon_validation, reservations_validation, FieldError,
+};
+use crate::versions::{diff, FieldChange, SiteVersions};
+use crate::workers::{Trade, Worker, Workers};
+
+/// User-provided fields of a site, used to create and edit it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSite {
+    pub name: String,
+    pub coordinates: Coordinates,
+    pub client: ClientFields,
+    pub start_day: DayIndex,
+    pub duration: SiteDuration,
+    #[serde(default)]
+    pub depends_on: Vec<SiteId>,
+    #[serde(default)]
+    pub required_certifications: Vec<String>,
+    #[serde(default)]
+    pub required_trades: Vec<Trade>,
+}
+
+impl NewSite {
+    /// Names of the fields, the only ones a JSON patch may touch.
+    const FIELDS: [&'static str; 8] = [
+        "name",
+        "coordinates",
+        "client",
+        "start_day",
+        "duration",
+        "depends_on",
+        "required_certifications",
+        "required_trades",
+    ];
+
+    /// The fields of `site`, as they would be submitted to recreate it.
+    fn of(site: &Site) -> NewSite {
+        NewSite {
+            name: site.name.clone(),
+            coordinates: site.coordinates,
+            client: ClientFields {
+                name: site.client.name.clone(),
+                phone: site.client.phone.as_str().to_owned(),
+                contacts: site.client.contacts.iter().map(ContactFields::of).collect(),
+            },
+            start_day: site.start_day,
+            duration: site.duration.clone(),
+            depends_on: site.depends_on.clone(),
+            required_certifications: site.required_certifications.clone(),
+            required_trades: site.required_trades.clone(),
+        }
+    }
+
+    /// Every problem with the fields, empty when they are valid.
+    fn validate(&self, config: &AppConfig) -> Vec<FieldError> {
+        let mut errors = fields_validation(&self.name, &self.coordinates, &self.duration);
+        errors.extend(horizon_validation(
+            self.start_day,
+            &self.duration,
+            config.planning_horizon_days,
+        ));
+        errors.extend(self.client.validate());
+        errors
+    }
+}
+
+/// A client as submitted, its phone numbers not parsed yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientFields {
+    pub name: String,
+    pub phone: String,
+    #[serde(default)]
+    pub contacts: Vec<ContactFields>,
+}
+
+impl ClientFields {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = client_validation(&self.name, &self.phone);
+        for (index, contact) in self.contacts.iter().enumerate() {
+            errors.extend(contact.validate(&format!("client.contacts.{index}")));
+        }
+        errors
+    }
+
+    pub fn parse(self) -> Result<Client, Vec<FieldError>> {
+        let errors = self.validate();
+        match self.phone.parse() {
+            Ok(phone) if errors.is_empty() => Ok(Client {
+                name: self.name,
+                phone,
+                contacts: self
+                    .contacts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, contact)| contact.parse(&format!("client.contacts.{index}")))
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+/// A client contact as submitted, its phone number not parsed yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactFields {
+    pub name: String,
+    pub phone: String,
+    #[serde(default)]
+    pub role: String,
+}
+
+impl ContactFields {
+    fn of(contact: &Contact) -> ContactFields {
+        ContactFields {
+            name: contact.name.clone(),
+            phone: contact.phone.as_str().to_owned(),
+            role: contact.role.clone(),
+        }
+    }
+
+    /// Problems with the contact, reported under `field`.
+    pub fn validate(&self, field: &str) -> Vec<FieldError> {
+        contact_validation(field, &self.name, &self.phone)
+    }
+
+    pub fn parse(self, field: &str) -> Result<Contact, Vec<FieldError>> {
+        let errors = self.validate(field);
+        match self.phone.parse() {
+            Ok(phone) if errors.is_empty() => Ok(Contact {
+                name: self.name.trim().to_owned(),
+                phone,
+                role: self.role.trim().to_owned(),
+            }),
+            _ => Err(errors),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWorker {
+    pub name: String,
+    #[serde(default)]
+    pub trade: Trade,
+    #[serde(default)]
+    pub certifications: Vec<String>,
+}
+
+impl NewWorker {
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "must not be empty"));
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReservationRequest {
+    pub start_date: String,
+    pub start_period: DayPeriod,
+    pub end_date: String,
+    pub end_period: DayPeriod,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Creates a site, recording its creator and, unless `creator_manages_site` is off, making
+/// them one of its managers.
+#[post("/sites", data = "<site>")]
+pub fn create_site(
+    site: Json<NewSite>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+) -> Result<Created<Json<Site>>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let id = Sites::next_id(&sites);
+
+    let mut errors = site.validate(config);
+    errors.extend(dependencies_validation(&sites, id, &site.depends_on));
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let NewSite {
+        name,
+        coordinates,
+        client,
+        start_day,
+        duration,
+        depends_on,
+        required_certifications,
+        required_trades,
+    } = site.into_inner();
+
+    let site = Site {
+        id,
+        name,
+        coordinates,
+        client: client.parse()?,
+        start_day,
+        duration,
+        status: SiteStatus::NotCarried,
+        overdue: false,
+        resources: SiteResource::default(),
+        workers: Vec::new(),
+        depends_on,
+        status_history: Vec::new(),
+        anomalies: Vec::new(),
+        required_certifications,
+        required_trades,
+        priority: Sites::next_priority(&sites),
+        photos: Vec::new(),
+        managers: if config.creator_manages_site {
+            vec![user.name.clone()]
+        } else {
+            Vec::new()
+        },
+        responsible: Some(user.name.clone()),
+        created_by: user.name,
+    };
+    sites.insert(id, site.clone());
+
+    Ok(Created::new(format!("/sites/{id}")).body(Json(site)))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewManager {
+    pub name: String,
+}
+
+/// Makes a user one of a site's managers, unless they already manage `max_sites_per_manager`
+/// sites that are not completed.
+#[post("/sites/<id>/managers", data = "<manager>")]
+#[allow(clippy::too_many_arguments)]
+pub fn add_manager(
+    id: SiteId,
+    manager: Json<NewManager>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let name = manager.into_inner().name.trim().to_string();
+    if name.is_empty() {
+        return Err(vec![FieldError::new("name", "must not be empty")].into());
+    }
+
+    let mut sites = sites.write();
+    let managed = Sites::managed_count(&sites, &name);
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    if site.managers.contains(&name) {
+        return Ok(Json(site.clone()));
+    }
+    if let Some(max) = config.max_sites_per_manager {
+        if site.status != SiteStatus::Completed && managed >= max {
+            return Err(ApiError::conflict(format!(
+                "{name} already manages {managed} sites, the most allowed is {max}"
+            )));
+        }
+    }
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("add_manager {name}"),
+        Some(id),
+        ip,
+    );
+    site.managers.push(name);
+    Ok(Json(site.clone()))
+}
+
+/// Hands a site over to another global manager, who becomes responsible for it. The target must
+/// have made a request as a global manager since the server started.
+#[post("/sites/<id>/transfer", data = "<to_username>")]
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_site(
+    id: SiteId,
+    to_username: Json<String>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    users: &State<Users>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let to = to_username.into_inner().trim().to_string();
+    if to.is_empty() {
+        return Err(ApiError::unprocessable(
+            "The user to transfer to must not be empty",
+        ));
+    }
+    match users.role_of(&to) {
+        Some(AppRole::SitesGlobalManager) => {}
+        Some(role) => {
+            return Err(ApiError::unprocessable(format!(
+                "`{to}` is a {role:?}, only a SitesGlobalManager can be responsible for a site"
+            )))
+        }
+        None => return Err(ApiError::unprocessable(format!("Unknown user `{to}`"))),
+    }
+
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let from = site.responsible.replace(to.clone());
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("transfer {} to {to}", from.as_deref().unwrap_or("nobody")),
+        Some(id),
+        ip,
+    );
+    Ok(Json(site.clone()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteDiff {
+    pub from_version: u32,
+    /// Latest stored version, the one the site is compared with.
+    pub to_version: u32,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Changes made to a site since one of its stored versions.
+#[get("/sites/<id>/diff?<from_version>")]
+pub fn site_diff(
+    id: SiteId,
+    from_version: u32,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    versions: &State<SiteVersions>,
+) -> Result<Json<SiteDiff>, ApiError> {
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let vehicles = fleet.lock();
+    let current = site.snapshot().with_reservations(id, &vehicles);
+
+    let versions = versions.lock();
+    let history = versions.get(&id).map(Vec::as_slice).unwrap_or_default();
+    let from = history
+        .iter()
+        .find(|stored| stored.version == from_version)
+        .ok_or_else(|| ApiError::not_found(format!("Site {id} has no version {from_version}")))?;
+    Ok(Json(SiteDiff {
+        from_version,
+        to_version: history.last().map_or(from_version, |latest| latest.version),
+        changes: diff(&from.snapshot, &current),
+    }))
+}
+
+/// Replaces the user-provided fields of a site.
+#[put("/sites/<id>", data = "<fields>")]
+pub fn edit_site(
+    id: SiteId,
+    fields: Json<NewSite>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    replace_fields(&mut sites, id, fields.into_inner(), config, clock.today()).map(Json)
+}
+
+/// Applies an RFC 6902 JSON patch to the user-provided fields of a site, e.g.
+/// `[{ "op": "replace", "path": "/name", "value": "Depot" }]`, then checks them like
+/// [`edit_site`]. Nothing is changed when an operation fails or the result is invalid.
+#[patch(
+    "/sites/<id>",
+    format = "application/json-patch+json",
+    data = "<patch>"
+)]
+pub fn patch_site(
+    id: SiteId,
+    patch: Json<json_patch::Patch>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let mut sites = sites.write();
+    let site = visible_site(&sites, id, &user)?;
+
+    let mut fields = serde_json::to_value(NewSite::of(site))
+        .map_err(|err| ApiError::internal(format!("Could not serialize site {id}: {err}")))?;
+    json_patch::patch(&mut fields, &patch).map_err(|err| {
+        let detail = format!(
+            "Operation {} on `{}` failed: {}",
+            err.operation, err.path, err.kind
+        );
+        match err.kind {
+            json_patch::PatchErrorKind::TestFailed => ApiError::conflict(detail),
+            _ => ApiError::unprocessable(detail),
+        }
+    })?;
+    if let Some(field) = fields.as_object().and_then(|fields| {
+        fields
+            .keys()
+            .find(|field| !NewSite::FIELDS.contains(&field.as_str()))
+    }) {
+        return Err(ApiError::unprocessable(format!(
+            "`/{field}` cannot be patched"
+        )));
+    }
+    let fields: NewSite = serde_json::from_value(fields)
+        .map_err(|err| ApiError::unprocessable(format!("The patched site is invalid: {err}")))?;
+
+    replace_fields(&mut sites, id, fields, config, clock.today()).map(Json)
+}
+
+/// Validates `fields` and replaces those of site `id` with them. A site moved past `today` is
+/// not overdue anymore.
+fn replace_fields(
+    sites: &mut HashMap<SiteId, Site>,
+    id: SiteId,
+    fields: NewSite,
+    config: &AppConfig,
+    today: NaiveDate,
+) -> Result<Site, ApiError> {
+    if !sites.contains_key(&id) {
+        return Err(site_not_found(id));
+    }
+
+    let mut errors = fields.validate(config);
+    errors.extend(dependencies_validation(sites, id, &fields.depends_on));
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let NewSite {
+        name,
+        coordinates,
+        client,
+        start_day,
+        duration,
+        depends_on,
+        required_certifications,
+        required_trades,
+    } = fields;
+
+    let site = sites.get_mut(&id).ok_or_else(|| site_not_found(id))?;
+    site.name = name;
+    site.coordinates = coordinates;
+    site.client = client.parse()?;
+    site.start_day = start_day;
+    site.duration = duration;
+    site.depends_on = depends_on;
+    site.required_certifications = required_certifications;
+    site.required_trades = required_trades;
+    site.refresh_overdue(&config.calendar, today);
+
+    Ok(site.clone())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatusChange {
+    pub status: SiteStatus,
+    /// Why the status changes, required when interrupting the site.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Moves a site to another status. Starting a site requires its dependencies to be completed,
+/// completing it drops the holds and waitlisted requests made for it. Setting the status the
+/// site already has changes nothing and records no history.
+#[patch("/sites/<id>/status", data = "<change>")]
+#[allow(clippy::too_many_arguments)]
+pub fn set_status(
+    id: SiteId,
+    change: Json<StatusChange>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Site>, ApiError> {
+    require(&user, Permission::SetStatus)?;
+    let mut sites = sites.write();
+    let site = visible_site(&sites, id, &user)?;
+    let StatusChange {
+        status: next,
+        reason,
+    } = change.into_inner();
+    let reason = reason
+        .map(|reason| reason.trim().to_owned())
+        .filter(|reason| !reason.is_empty());
+    if next == SiteStatus::Interrupted && reason.is_none() {
+        return Err(vec![FieldError::new("reason", "is required to interrupt a site")].into());
+    }
+
+    if site.status == next {
+        return Ok(Json(site.clone()));
+    }
+    if !site.status.can_transition_to(next) {
+        return Err(ApiError::conflict(format!(
+            "A {:?} site cannot become {next:?}",
+            site.status
+        )));
+    }
+    if site.status == SiteStatus::NotCarried && next == SiteStatus::InProgress {
+        let blocking = site.blocking_dependencies(&sites);
+        if !blocking.is_empty() {
+            let blocking: Vec<String> = blocking.iter().map(ToString::to_string).collect();
+            return Err(ApiError::conflict(format!(
+                "Site {id} is waiting for sites {} to be completed",
+                blocking.join(", ")
+            )));
+        }
+    }
+
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    site.set_status(next, clock.now(), reason);
+    if !next.accepts_reservations() {
+        for vehicle in fleet.lock().values_mut() {
+            vehicle.drop_pending(id);
+        }
+    }
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("set_status {next:?}"),
+        Some(id),
+        ip,
+    );
+    Ok(Json(site.clone()))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAnomaly {
+    pub reason: String,
+}
+
+/// Records a problem on a site. A site in progress is interrupted by it.
+#[post("/sites/<id>/anomalies", data = "<anomaly>")]
+#[allow(clippy::too_many_arguments)]
+pub fn report_anomaly(
+    id: SiteId,
+    anomaly: Json<NewAnomaly>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Created<Json<Site>>, ApiError> {
+    require(&user, Permission::ReportAnomaly)?;
+    let reason = anomaly.into_inner().reason.trim().to_owned();
+    if reason.is_empty() {
+        return Err(vec![FieldError::new("reason", "must not be empty")].into());
+    }
+
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    let now = clock.now();
+    if site.status == SiteStatus::InProgress {
+        site.set_status(SiteStatus::Interrupted, now, Some(reason.clone()));
+    }
+    site.anomalies.push(Anomaly { reason, at: now });
+    audit.record_request(now, user.name, "report_anomaly", Some(id), ip);
+
+    Ok(Created::new(format!("/sites/{id}")).body(Json(site.clone())))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InterruptedSite {
+    pub site_id: SiteId,
+    pub name: String,
+    pub interrupted_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+/// Interrupted sites, the longest-interrupted first, with the latest anomaly reported on each.
+#[get("/sites/interrupted")]
+pub fn interrupted_sites(user: User, sites: &State<Sites>) -> Json<Vec<InterruptedSite>> {
+    let sites = sites.read();
+    let mut interrupted: Vec<InterruptedSite> = sites
+        .values()
+        .filter(|site| site.visible_to(&user) && site.status == SiteStatus::Interrupted)
+        .map(|site| InterruptedSite {
+            site_id: site.id,
+            name: site.name.clone(),
+            interrupted_at: site.interrupted_at(),
+            reason: site.latest_anomaly().map(|anomaly| anomaly.reason.clone()),
+        })
+        .collect();
+    interrupted.sort_by_key(|site| (site.interrupted_at, site.site_id));
+    Json(interrupted)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverdueSite {
+    pub site_id: SiteId,
+    pub name: String,
+    pub status: SiteStatus,
+    /// Last working day of the site.
+    pub end_date: NaiveDate,
+    /// Calendar days from the end date to the reference date.
+    pub days_overdue: i64,
+}
+
+/// Sites the user may see that are not completed although their last working day is before
+/// `as_of`, `YYYY-MM-DD` defaulting to today, the most overdue first.
+#[get("/sites/overdue?<as_of>")]
+pub fn overdue_sites(
+    as_of: Option<&str>,
+    user: User,
+    sites: &State<Sites>,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Vec<OverdueSite>>, ApiError> {
+    let as_of = match as_of {
+        Some(as_of) => parse_date_param("as_of", as_of)?,
+        None => clock.today(),
+    };
+    let calendar = &config.calendar;
+    let sites = sites.read();
+    let mut overdue: Vec<OverdueSite> = sites
+        .values()
+        .filter(|site| site.visible_to(&user) && site.status != SiteStatus::Completed)
+        .filter_map(|site| {
+            let end_date = calendar.date_of_day(site.end_day(calendar)).ok()?;
+            (end_date < as_of).then(|| OverdueSite {
+                site_id: site.id,
+                name: site.name.clone(),
+                status: site.status,
+                end_date,
+                days_overdue: (as_of - end_date).num_days(),
+            })
+        })
+        .collect();
+    overdue.sort_by_key(|site| (Reverse(site.days_overdue), site.site_id));
+    Ok(Json(overdue))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteAnomaly {
+    pub site_id: SiteId,
+    pub site_name: String,
+    #[serde(flatten)]
+    pub anomaly: Anomaly,
+}
+
+/// Anomalies reported on the sites the user may see, the latest first.
+#[get("/anomalies")]
+pub fn list_anomalies(user: User, sites: &State<Sites>) -> Json<Vec<SiteAnomaly>> {
+    let sites = sites.read();
+    let mut anomalies: Vec<SiteAnomaly> = sites
+        .values()
+        .filter(|site| site.visible_to(&user))
+        .flat_map(|site| {
+            site.anomalies.iter().map(|anomaly| SiteAnomaly {
+                site_id: site.id,
+                site_name: site.name.clone(),
+                anomaly: anomaly.clone(),
+            })
+        })
+        .collect();
+    anomalies.sort_by(|a, b| {
+        b.anomaly
+            .at
+            .cmp(&a.anomaly.at)
+            .then(a.site_id.cmp(&b.site_id))
+    });
+    Json(anomalies)
+}
+
+/// Sites rendered as CSV when the client prefers `text/csv`, as JSON otherwise:
+/// `{ "items": [...], "next_cursor": 12 }`.
+///
+/// When more sites follow a page, `next_cursor` is the id to pass as `after` for the next one,
+/// `null` otherwise. It is also sent in a `Next-Cursor` header, the only place CSV has for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteList {
+    #[serde(rename = "items")]
+    pub sites: Vec<Site>,
+    pub next_cursor: Option<SiteId>,
+}
+
+impl SiteList {
+    const CSV_HEADER: &'static str = "id,name,start_day,half_day,status,overdue,vehicles";
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from(Self::CSV_HEADER);
+        csv.push('\n');
+        for site in &self.sites {
+            let vehicles: Vec<String> = site
+                .resources
+                .vehicles
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            let row = [
+                site.id.to_string(),
+                csv_field(&site.name),
+                site.start_day.to_string(),
+                site.duration.half_day.to_string(),
+                format!("{:?}", site.status),
+                site.overdue.to_string(),
+                vehicles.join(";"),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl<'r> Responder<'r, 'static> for SiteList {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let wants_csv = request
+            .accept()
+            .is_some_and(|accept| accept.preferred().media_type() == &MediaType::CSV);
+
+        let next_cursor = self.next_cursor;
+        let mut response = if wants_csv {
+            (ContentType::CSV, self.to_csv()).respond_to(request)?
+        } else {
+            Json(self).respond_to(request)?
+        };
+        if let Some(cursor) = next_cursor {
+            response.set_raw_header("Next-Cursor", cursor.to_string());
+        }
+        Ok(response)
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromFormField)]
+pub enum SiteSort {
+    #[default]
+    Id,
+    /// Backlog order, ties broken by id.
+    Priority,
+    /// Planned start, ties broken by duration then id, e.g. for a Gantt chart.
+    #[field(value = "start_day")]
+    StartDay,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromFormField)]
+pub enum SortDir {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Sites the user may see, only those in `status` and those `responsible` answers for if
+/// given, ordered by `sort` in the `dir` direction. Unknown values are refused.
+///
+/// At most `limit` sites are returned when given, from `offset` in that order or, when sorted
+/// by id, from the one following the id `after`. Unlike an offset, a cursor does not skip nor
+/// repeat sites when others are created or deleted between two pages. Sorted by id, a page
+/// followed by others gives the cursor of the next one in `next_cursor`, see [`SiteList`].
+#[get("/sites?<sort>&<dir>&<status>&<responsible>&<after>&<offset>&<limit>")]
+#[allow(clippy::too_many_arguments)]
+pub fn list_sites(
+    sort: Option<&str>,
+    dir: Option<&str>,
+    status: Option<&str>,
+    responsible: Option<&str>,
+    after: Option<&str>,
+    offset: Option<&str>,
+    limit: Option<&str>,
+    user: User,
+    sites: &State<Sites>,
+) -> Result<SiteList, ApiError> {
+    let sort: SiteSort = sort
+        .map(|sort| parse_choice_param("sort", sort))
+        .transpose()?
+        .unwrap_or_default();
+    let dir: SortDir = dir
+        .map(|dir| parse_choice_param("dir", dir))
+        .transpose()?
+        .unwrap_or_default();
+    let status: Option<SiteStatus> = status
+        .map(|status| parse_choice_param("status", status))
+        .transpose()?;
+    let after: Option<SiteId> = after
+        .map(|after| parse_number_param("after", after).map(SiteId))
+        .transpose()?;
+    let offset: Option<usize> = offset
+        .map(|offset| parse_number_param("offset", offset))
+        .transpose()?;
+    let limit: Option<usize> = limit
+        .map(|limit| parse_number_param("limit", limit))
+        .transpose()?;
+    if after.is_some() && offset.is_some() {
+        return Err(ApiError::unprocessable(
+            "`after` and `offset` cannot be combined",
+        ));
+    }
+    if after.is_some() && sort != SiteSort::Id {
+        return Err(ApiError::unprocessable(
+            "`after` needs the sites sorted by id",
+        ));
+    }
+    if limit == Some(0) {
+        return Err(ApiError::unprocessable("`limit` must be at least 1"));
+    }
+
+    let mut sites: Vec<Site> = sites
+        .read()
+        .values()
+        .filter(|site| site.visible_to(&user))
+        .filter(|site| status.is_none_or(|status| site.status == status))
+        .filter(|site| responsible.is_none_or(|name| site.responsible.as_deref() == Some(name)))
+        .cloned()
+        .collect();
+    match sort {
+        SiteSort::Id => sites.sort_by_key(|site| site.id),
+        SiteSort::Priority => sites.sort_by_key(|site| (site.priority, site.id)),
+        SiteSort::StartDay => {
+            sites.sort_by_key(|site| (site.start_day, site.duration.half_day, site.id))
+        }
+    }
+    if dir == SortDir::Desc {
+        sites.reverse();
+    }
+
+    if let Some(after) = after {
+        sites.retain(|site| match dir {
+            SortDir::Asc => site.id > after,
+            SortDir::Desc => site.id < after,
+        });
+    }
+    sites.drain(..offset.unwrap_or(0).min(sites.len()));
+    let mut next_cursor = None;
+    if let Some(limit) = limit.filter(|limit| sites.len() > *limit) {
+        sites.truncate(limit);
+        if sort == SiteSort::Id {
+            next_cursor = sites.last().map(|site| site.id);
+        }
+    }
+    Ok(SiteList { sites, next_cursor })
+}
+
+/// Sets the backlog order of not carried sites: the first id gets priority 0, the next 1, and
+/// so on. Sites left out keep their priority. Returns the reordered sites, in order.
+#[post("/sites/reorder", data = "<ordered_ids>")]
+pub fn reorder_sites(
+    ordered_ids: Json<Vec<SiteId>>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<Json<Vec<Site>>, ApiError> {
+    require(&user, Permission::CreateSite)?;
+    let ordered_ids = ordered_ids.into_inner();
+    let mut sites = sites.write();
+
+    let mut seen = HashSet::new();
+    for id in &ordered_ids {
+        let site = sites.get(id).ok_or_else(|| site_not_found(*id))?;
+        if !seen.insert(*id) {
+            return Err(ApiError::unprocessable(format!(
+                "Site {id} is listed more than once"
+            )));
+        }
+        if site.status != SiteStatus::NotCarried {
+            return Err(ApiError::unprocessable(format!(
+                "Site {id} is {:?}, only not carried sites can be reordered",
+                site.status
+            )));
+        }
+    }
+
+    let mut reordered = Vec::new();
+    for (priority, id) in ordered_ids.iter().enumerate() {
+        if let Some(site) = sites.get_mut(id) {
+            site.priority = priority as i32;
+            reordered.push(site.clone());
+        }
+    }
+    Ok(Json(reordered))
+}
+
+/// Sites the user may see inside a map viewport, the bounds being included.
+#[get("/sites/in-bounds?<min_lat>&<min_lon>&<max_lat>&<max_lon>")]
+pub fn sites_in_bounds(
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+    user: User,
+    sites: &State<Sites>,
+) -> Result<SiteList, ApiError> {
+    let bounds = BoundingBox {
+        min_latitude: min_lat,
+        min_longitude: min_lon,
+        max_latitude: max_lat,
+        max_longitude: max_lon,
+    };
+    let errors = bounds_validation(&bounds);
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut sites: Vec<Site> = sites
+        .read()
+        .values()
+        .filter(|site| site.visible_to(&user) && bounds.contains(&site.coordinates))
+        .cloned()
+        .collect();
+    sites.sort_by_key(|site| site.id);
+    Ok(SiteList {
+        sites,
+        next_cursor: None,
+    })
+}
+
+/// A single site, sent with an `ETag` hashing its JSON so clients can revalidate it.
+#[derive(Debug, Clone)]
+pub struct SiteDocument(pub Site);
+
+impl SiteDocument {
+    /// Whether an `If-None-Match` header lists `etag`, or is `*`. Weak tags match too.
+    fn matches(if_none_match: &str, etag: &str) -> bool {
+        if_none_match.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for SiteDocument {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let body = serde_json::to_string(&self.0).map_err(|_| Status::InternalServerError)?;
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let etag = format!("\"{:016x}\"", hasher.finish());
+
+        let not_modified = request
+            .headers()
+            .get("If-None-Match")
+            .any(|if_none_match| SiteDocument::matches(if_none_match, &etag));
+        let mut response = if not_modified {
+            Response::build().status(Status::NotModified).finalize()
+        } else {
+            (ContentType::JSON, body).respond_to(request)?
+        };
+        response.set_raw_header("ETag", etag);
+        Ok(response)
+    }
+}
+
+/// A site, or `304 Not Modified` when `If-None-Match` holds its current `ETag`. `HEAD` gets
+/// the same headers without the body.
+#[get("/sites/<id>")]
+pub fn get_site(id: SiteId, user: User, sites: &State<Sites>) -> Result<SiteDocument, ApiError> {
+    let sites = sites.read();
+    visible_site(&sites, id, &user).cloned().map(SiteDocument)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteValidation {
+    pub site_id: SiteId,
+    pub valid: bool,
+    pub problems: Vec<FieldError>,
+}
+
+/// Every broken invariant of the site, including the reservations of its vehicles.
+#[get("/sites/<id>/validate")]
+pub fn validate_site(
+    id: SiteId,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    config: &State<AppConfig>,
+) -> Result<Json<SiteValidation>, ApiError> {
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let mut problems = site.validate().err().unwrap_or_default();
+    problems.extend(horizon_validation(
+        site.start_day,
+        &site.duration,
+        config.planning_horizon_days,
+    ));
+    problems.extend(reservations_validation(site, &fleet.lock()));
+
+    Ok(Json(SiteValidation {
+        site_id: id,
+        valid: problems.is_empty(),
+        problems,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReservationConflict {
+    pub vehicle_id: VehicleId,
+    pub first: ReservedDate,
+    pub second: ReservedDate,
+}
+
+/// Overlapping reservations on the site's vehicles, which should never have been accepted.
+#[get("/sites/<id>/conflicts")]
+pub fn conflicts(
+    id: SiteId,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+) -> Result<Json<Vec<ReservationConflict>>, ApiError> {
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let conflicts = site
+        .resources
+        .all_conflicts(&fleet.lock())
+        .into_iter()
+        .map(|(vehicle_id, first, second)| ReservationConflict {
+            vehicle_id,
+            first,
+            second,
+        })
+        .collect();
+
+    Ok(Json(conflicts))
+}
+
+/// Longest range, in days, a single utilization request may cover.
+const MAX_UTILIZATION_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleUtilization {
+    pub vehicle_id: VehicleId,
+    pub utilization: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Utilization {
+    pub site_id: SiteId,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Reserved half-days over available half-days, averaged across the site's vehicles.
+    pub utilization: f64,
+    pub vehicles: Vec<VehicleUtilization>,
+}
+
+#[get("/sites/<id>/utilization?<from>&<to>")]
+pub fn utilization(
+    id: SiteId,
+    from: &str,
+    to: &str,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+) -> Result<Json<Utilization>, ApiError> {
+    let (from, to) = parse_date_range(from, to, MAX_UTILIZATION_DAYS)?;
+    let (start, end) = (
+        from.and_time(NaiveTime::MIN).and_utc(),
+        to.and_time(NaiveTime::MIN).and_utc(),
+    );
+
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let fleet = fleet.lock();
+    let vehicles = site
+        .resources
+        .vehicles
+        .iter()
+        .filter_map(|vehicle_id| fleet.get(vehicle_id))
+        .map(|vehicle| VehicleUtilization {
+            vehicle_id: vehicle.id,
+            utilization: vehicle.utilization(start, end),
+        })
+        .collect();
+
+    Ok(Json(Utilization {
+        site_id: id,
+        from,
+        to,
+        utilization: site.resources.utilization(&fleet, start, end),
+        vehicles,
+    }))
+}
+
+/// Makes a fleet vehicle available to the site, warning when the site is interrupted.
+#[post("/sites/<id>/vehicles/<vehicle_id>")]
+pub fn attach_vehicle(
+    id: SiteId,
+    vehicle_id: VehicleId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+) -> Result<Warned<Site>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    if !fleet.lock().contains_key(&vehicle_id) {
+        return Err(vehicle_not_found(vehicle_id));
+    }
+    let warnings = site
+        .status
+        .reservation_warning()?
+        .map(|message| Warning::new(WarningCode::SiteInterrupted, message))
+        .into_iter()
+        .collect();
+
+    if !site.resources.vehicles.contains(&vehicle_id) {
+        site.resources.vehicles.push(vehicle_id);
+    }
+    Ok(Warned {
+        data: site.clone(),
+        warnings,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReservationReceipt {
+    pub vehicle_id: VehicleId,
+    pub reserved: ReservedDate,
+    pub half_days: usize,
+    /// Whether the reservation was only checked, leaving the vehicle untouched.
+    pub dry_run: bool,
+    pub available: bool,
+    /// Why the vehicle is not available, on dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<String>,
+}
+
+/// Reserves one of the site's vehicles, checked against its reservations for every site.
+/// With `dry_run`, only reports whether the reservation would succeed. Reserving outside the
+/// site's window is allowed but warned about.
+#[post(
+    "/sites/<id>/vehicles/<vehicle_id>/reserve?<dry_run>",
+    data = "<reservation>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn reserve(
+    id: SiteId,
+    vehicle_id: VehicleId,
+    dry_run: bool,
+    reservation: Json<ReservationRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Warned<ReservationReceipt>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let mut warnings: Vec<Warning> = site
+        .status
+        .reservation_warning()?
+        .map(|message| Warning::new(WarningCode::SiteInterrupted, message))
+        .into_iter()
+        .collect();
+    if !site.resources.vehicles.contains(&vehicle_id) {
+        return Err(ApiError::unprocessable(format!(
+            "Vehicle {vehicle_id} is not used by site {id}"
+        )));
+    }
+
+    let date = ReservedDate::new(
+        &reservation.start_date,
+        reservation.start_period,
+        &reservation.end_date,
+        reservation.end_period,
+    )?
+    .for_site(id)
+    .made_by(Some(user.name), reservation.into_inner().note);
+    if let Some(window) = site.window(&config.calendar) {
+        let (start, end) = date.half_day_span();
+        let (window_start, window_end) = window.half_day_span();
+        if start < window_start || end > window_end {
+            warnings.push(Warning::new(
+                WarningCode::OutsideSiteWindow,
+                format!(
+                    "The reservation is outside the window of site {id}, {} to {}",
+                    window.first_date(),
+                    window.last_date()
+                ),
+            ));
+        }
+    }
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet
+        .get_mut(&vehicle_id)
+        .ok_or_else(|| vehicle_not_found(vehicle_id))?;
+    let conflict = if dry_run {
+        vehicle.check_available(&date, &rules).err()
+    } else {
+        if let Err(err) = vehicle.reserve(date.clone(), &rules) {
+            let alternatives = alternatives(&fleet, vehicle_id, &date, &rules);
+            return Err(
+                ApiError::from(err).with_meta("alternatives", serde_json::json!(alternatives))
+            );
+        }
+        None
+    };
+    let receipt = ReservationReceipt {
+        vehicle_id,
+        half_days: date.half_day_count(),
+        reserved: date,
+        dry_run,
+        available: conflict.is_none(),
+        conflict: conflict.as_ref().map(reservation_detail),
+    };
+    Ok(warnings
+        .into_iter()
+        .fold(Warned::new(receipt), Warned::warn))
+}
+
+#[post("/sites/<id>/workers", data = "<worker>")]
+pub fn assign_worker(
+    id: SiteId,
+    worker: Json<NewWorker>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+) -> Result<Created<Warned<Worker>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = worker.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut sites = sites.write();
+    let site = visible_site_mut(&mut sites, id, &user)?;
+    check_accepts_workers(site)?;
+    let mut workers = workers.lock();
+    let NewWorker {
+        name,
+        trade,
+        certifications,
+    } = worker.into_inner();
+    let worker = Worker {
+        id: Workers::next_id(&workers),
+        name,
+        trade,
+        certifications,
+    };
+    let warning = certification_check(site, &worker, config.certification_check)?;
+    workers.insert(worker.id, worker.clone());
+    site.workers.push(worker.id);
+
+    let location = format!("/workers/{}", worker.id);
+    let body = warning.into_iter().fold(Warned::new(worker), Warned::warn);
+    Ok(Created::new(location).body(body))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrewVehicle {
+    pub vehicle_id: VehicleId,
+    pub name: String,
+    pub kind: VehicleKind,
+    /// Periods of the day the vehicle is reserved for the site.
+    pub periods: Vec<LabeledPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Crew {
+    pub site_id: SiteId,
+    pub date: NaiveDate,
+    pub workers: Vec<Worker>,
+    pub vehicles: Vec<CrewVehicle>,
+}
+
+/// Who is on site on `date` (today by default): every assigned worker, and the vehicles
+/// reserved for the site on that day.
+#[get("/sites/<id>/crew?<date>")]
+#[allow(clippy::too_many_arguments)]
+pub fn crew(
+    id: SiteId,
+    date: Option<&str>,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    workers: &State<Workers>,
+    clock: &State<SharedClock>,
+    config: &State<AppConfig>,
+) -> Result<Json<Crew>, ApiError> {
+    let date = match date {
+        Some(date) => parse_date_param("date", date)?,
+        None => clock.today(),
+    };
+
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let fleet = fleet.lock();
+    let vehicles = site
+        .resources
+        .vehicles
+        .iter()
+        .filter_map(|vehicle_id| fleet.get(vehicle_id))
+        .filter_map(|vehicle| {
+            let periods: Vec<LabeledPeriod> = [DayPeriod::Morning, DayPeriod::Afternoon]
+                .into_iter()
+                .filter(|period| {
+                    let slot = ReservedDate::half_day(date, *period);
+                    vehicle.reserved_dates.iter().any(|reserved| {
+                        reserved.site_id == Some(id) && !reserved.compatible_with(&slot)
+                    })
+                })
+                .map(|period| config.period_labels.labeled(period))
+                .collect();
+            (!periods.is_empty()).then(|| CrewVehicle {
+                vehicle_id: vehicle.id,
+                name: vehicle.name.clone(),
+                kind: vehicle.kind,
+                periods,
+            })
+        })
+        .collect();
+
+    Ok(Json(Crew {
+        site_id: id,
+        date,
+        workers: Workers::resolve(&workers.lock(), &site.workers),
+        vehicles,
+    }))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleWindow {
+    pub first_date: NaiveDate,
+    pub first_period: LabeledPeriod,
+    pub last_date: NaiveDate,
+    pub last_period: LabeledPeriod,
+}
+
+impl ScheduleWindow {
+    /// First and last working half-days of `site`, `None` for a site without duration.
+    pub fn of(site: &Site, config: &AppConfig) -> Option<ScheduleWindow> {
+        let periods = site.working_periods(&config.calendar);
+        periods
+            .first()
+            .zip(periods.last())
+            .map(
+                |((first, first_period), (last, last_period))| ScheduleWindow {
+                    first_date: *first,
+                    first_period: config.period_labels.labeled(*first_period),
+                    last_date: *last,
+                    last_period: config.period_labels.labeled(*last_period),
+                },
+            )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryVehicle {
+    pub vehicle_id: VehicleId,
+    pub name: String,
+    pub kind: VehicleKind,
+    /// Reservations of the vehicle for the site, by start.
+    pub reservations: Vec<ReservedDate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteLabor {
+    pub site_id: SiteId,
+    pub workers: usize,
+    pub duration_half_days: usize,
+    /// See [`Site::total_labor_half_days`].
+    pub labor_half_days: usize,
+}
+
+/// Work a site takes, for costing.
+#[get("/sites/<id>/labor")]
+pub fn labor(id: SiteId, user: User, sites: &State<Sites>) -> Result<Json<SiteLabor>, ApiError> {
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    Ok(Json(SiteLabor {
+        site_id: id,
+        workers: site.workers.len(),
+        duration_half_days: site.duration.half_day,
+        labor_half_days: site.total_labor_half_days(),
+    }))
+}
+
+/// Maintenance of one of the site's vehicles planned while the site is worked.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceConflict {
+    pub vehicle_id: VehicleId,
+    pub maintenance: ReservedDate,
+}
+
+/// Everything a field manager needs about a site on a single page.
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteSummary {
+    pub site_id: SiteId,
+    pub name: String,
+    pub status: SiteStatus,
+    pub overdue: bool,
+    pub client: Client,
+    pub coordinates: Coordinates,
+    /// `None` for a site without duration.
+    pub schedule: Option<ScheduleWindow>,
+    pub workers: Vec<Worker>,
+    pub vehicles: Vec<SummaryVehicle>,
+    pub open_anomalies: Vec<Anomaly>,
+    /// See [`Site::maintenance_conflicts`].
+    pub maintenance_conflicts: Vec<MaintenanceConflict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_photo: Option<Photo>,
+    /// See [`Site::total_labor_half_days`].
+    pub labor_half_days: usize,
+}
+
+#[get("/sites/<id>/summary")]
+pub fn summary(
+    id: SiteId,
+    user: User,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+) -> Result<Json<SiteSummary>, ApiError> {
+    let sites = sites.read();
+    let site = visible_site(&sites, id, &user)?;
+    let fleet = fleet.lock();
+
+    let schedule = ScheduleWindow::of(site, config);
+    let vehicles = site
+        .resources
+        .vehicles
+        .iter()
+        .filter_map(|vehicle_id| fleet.get(vehicle_id))
+        .map(|vehicle| SummaryVehicle {
+            vehicle_id: vehicle.id,
+            name: vehicle.name.clone(),
+            kind: vehicle.kind,
+            reservations: vehicle
+                .reserved_dates
+                .iter()
+                .filter(|reserved| reserved.site_id == Some(id))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(SiteSummary {
+        site_id: id,
+        name: site.name.clone(),
+        status: site.status,
+        overdue: site.overdue,
+        client: site.client.clone(),
+        coordinates: site.coordinates,
+        schedule,
+        workers: Workers::resolve(&workers.lock(), &site.workers),
+        vehicles,
+        open_anomalies: site.open_anomalies().into_iter().cloned().collect(),
+        maintenance_conflicts: site
+            .maintenance_conflicts(&fleet, &config.calendar)
+            .into_iter()
+            .map(|(vehicle_id, maintenance)| MaintenanceConflict {
+                vehicle_id,
+                maintenance,
+            })
+            .collect(),
+        cover_photo: site.cover_photo().cloned(),
+        labor_half_days: site.total_labor_half_days(),
+    }))
+}
+
+pub fn site_not_found(id: SiteId) -> ApiError {
+    ApiError::not_found(format!("No site with id {id}"))
+}
+
+/// Site `id`, not found unless `user` may see it, see [`Site::visible_to`]. Site managers
+/// thus cannot tell the sites they do not manage from those that do not exist.
+pub fn visible_site<'a>(
+    sites: &'a HashMap<SiteId, Site>,
+    id: SiteId,
+    user: &User,
+) -> Result<&'a Site, ApiError> {
+    sites
+        .get(&id)
+        .filter(|site| site.visible_to(user))
+        .ok_or_else(|| site_not_found(id))
+}
+
+/// Like [`visible_site`], for changing the site.
+pub fn visible_site_mut<'a>(
+    sites: &'a mut HashMap<SiteId, Site>,
+    id: SiteId,
+    user: &User,
+) -> Result<&'a mut Site, ApiError> {
+    sites
+        .get_mut(&id)
+        .filter(|site| site.visible_to(user))
+        .ok_or_else(|| site_not_found(id))
+}
+
+pub fn vehicle_not_found(id: VehicleId) -> ApiError {
+    ApiError::not_found(format!("No vehicle with id {id}"))
+}
@@ -0,0 +1,142 @@
+use rocket::response::status::{Created, NoContent};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Deserialize;
+
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::site_not_found;
+use crate::sites::{SiteId, Sites};
+use crate::supplies::{Material, MaterialId, Tool, ToolId};
+use crate::validation::FieldError;
+
+/// Tool or material to add to a site.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSupply {
+    pub name: String,
+    pub quantity: u32,
+}
+
+impl NewSupply {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "must not be empty"));
+        }
+        if self.quantity == 0 {
+            errors.push(FieldError::new("quantity", "must be at least 1"));
+        }
+        errors
+    }
+}
+
+#[post("/sites/<id>/tools", data = "<tool>")]
+pub fn add_tool(
+    id: SiteId,
+    tool: Json<NewSupply>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<Created<Json<Tool>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = tool.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut sites = sites.write();
+    let resources = &mut sites
+        .get_mut(&id)
+        .ok_or_else(|| site_not_found(id))?
+        .resources;
+    let NewSupply { name, quantity } = tool.into_inner();
+    let tool = Tool {
+        id: resources.next_tool_id(),
+        name,
+        quantity,
+    };
+    resources.tools.push(tool.clone());
+
+    Ok(Created::new(format!("/sites/{id}/tools/{}", tool.id)).body(Json(tool)))
+}
+
+#[delete("/sites/<id>/tools/<tool_id>")]
+pub fn remove_tool(
+    id: SiteId,
+    tool_id: ToolId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<NoContent, ApiError> {
+    require(&user, Permission::ManageResources)?;
+
+    let mut sites = sites.write();
+    let tools = &mut sites
+        .get_mut(&id)
+        .ok_or_else(|| site_not_found(id))?
+        .resources
+        .tools;
+    let index = tools
+        .iter()
+        .position(|tool| tool.id == tool_id)
+        .ok_or_else(|| ApiError::not_found(format!("No tool {tool_id} on site {id}")))?;
+    tools.remove(index);
+
+    Ok(NoContent)
+}
+
+#[post("/sites/<id>/materials", data = "<material>")]
+pub fn add_material(
+    id: SiteId,
+    material: Json<NewSupply>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<Created<Json<Material>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = material.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut sites = sites.write();
+    let resources = &mut sites
+        .get_mut(&id)
+        .ok_or_else(|| site_not_found(id))?
+        .resources;
+    let NewSupply { name, quantity } = material.into_inner();
+    let material = Material {
+        id: resources.next_material_id(),
+        name,
+        quantity,
+    };
+    resources.materials.push(material.clone());
+
+    Ok(Created::new(format!("/sites/{id}/materials/{}", material.id)).body(Json(material)))
+}
+
+#[delete("/sites/<id>/materials/<material_id>")]
+pub fn remove_material(
+    id: SiteId,
+    material_id: MaterialId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+) -> Result<NoContent, ApiError> {
+    require(&user, Permission::ManageResources)?;
+
+    let mut sites = sites.write();
+    let materials = &mut sites
+        .get_mut(&id)
+        .ok_or_else(|| site_not_found(id))?
+        .resources
+        .materials;
+    let index = materials
+        .iter()
+        .position(|material| material.id == material_id)
+        .ok_or_else(|| ApiError::not_found(format!("No material {material_id} on site {id}")))?;
+    materials.remove(index);
+
+    Ok(NoContent)
+}
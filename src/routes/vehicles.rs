@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rocket::response::status::{Created, NoContent};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{AuditLog, SYSTEM_ACTOR};
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::client_ip::ClientIp;
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, LabeledPeriod};
+use crate::error::{HoldErr, ReservationErr};
+use crate::fleet::{
+    self, Fleet, Hold, HoldId, MaintenanceReport, Release, ReservationRules, Vehicle, VehicleId,
+    VehicleKind, WaitlistEntry,
+};
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::parse_date_param;
+use crate::routes::sites::{site_not_found, vehicle_not_found, ReservationRequest};
+use crate::routes::warnings::{Warned, Warning, WarningCode};
+use crate::sites::{DayPeriod, ReservedDate, Site, SiteId, Sites};
+use crate::validation::FieldError;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewVehicle {
+    pub name: String,
+    pub kind: VehicleKind,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceOptions {
+    /// Move upcoming reservations to other free vehicles instead of cancelling them.
+    pub transfer: bool,
+}
+
+/// A reservation, by its dates, and where it should now end.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShortenRequest {
+    pub start_date: String,
+    pub start_period: DayPeriod,
+    pub end_date: String,
+    pub end_period: DayPeriod,
+    pub new_end_date: String,
+    pub new_end_period: DayPeriod,
+}
+
+/// A reservation, by its dates.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseRequest {
+    pub start_date: String,
+    pub start_period: DayPeriod,
+    pub end_date: String,
+    pub end_period: DayPeriod,
+}
+
+/// A slot to keep for `site_id` while its booking is completed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HoldRequest {
+    pub site_id: SiteId,
+    #[serde(flatten)]
+    pub reservation: ReservationRequest,
+    /// How long the slot is kept, [`DEFAULT_HOLD_MINUTES`] when not given.
+    #[serde(default)]
+    pub ttl_minutes: Option<i64>,
+}
+
+pub const DEFAULT_HOLD_MINUTES: i64 = 15;
+pub const MAX_HOLD_MINUTES: i64 = 60;
+
+/// A reservation to make for `site_id` once the vehicle is free.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaitlistRequest {
+    pub site_id: SiteId,
+    #[serde(flatten)]
+    pub reservation: ReservationRequest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleAvailability {
+    pub vehicle: Vehicle,
+    /// Periods of the `free_on` date in which the vehicle is free, when one was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_periods: Option<Vec<LabeledPeriod>>,
+}
+
+#[post("/vehicles", data = "<vehicle>")]
+pub fn create_vehicle(
+    vehicle: Json<NewVehicle>,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+) -> Result<Created<Json<Vehicle>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let NewVehicle { name, kind } = vehicle.into_inner();
+
+    let mut fleet = fleet.lock();
+    let id = Fleet::next_id(&fleet);
+    let vehicle = Vehicle::new(id, name, kind);
+    fleet.insert(id, vehicle.clone());
+
+    Ok(Created::new(format!("/vehicles/{id}")).body(Json(vehicle)))
+}
+
+/// Adds a vehicle of the same kind as vehicle `id`, e.g. when buying an identical one. The
+/// copy is named after it and starts without reservations nor maintenance.
+#[post("/vehicles/<id>/clone")]
+pub fn clone_vehicle(
+    id: VehicleId,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+) -> Result<Created<Json<Vehicle>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut fleet = fleet.lock();
+    let template = fleet.get(&id).ok_or_else(|| vehicle_not_found(id))?;
+    let name = format!("{} (copy)", template.name);
+    let kind = template.kind;
+
+    let id = Fleet::next_id(&fleet);
+    let vehicle = Vehicle::new(id, name, kind);
+    fleet.insert(id, vehicle.clone());
+
+    Ok(Created::new(format!("/vehicles/{id}")).body(Json(vehicle)))
+}
+
+#[get("/vehicles/<id>")]
+pub fn get_vehicle(
+    id: VehicleId,
+    _user: User,
+    fleet: &State<Fleet>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Vehicle>, ApiError> {
+    fleet
+        .lock_pruned(clock.now())
+        .get(&id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| vehicle_not_found(id))
+}
+
+/// Fleet vehicles, optionally of a given kind and free at least part of `free_on`.
+#[get("/vehicles?<kind>&<free_on>")]
+pub fn list_vehicles(
+    kind: Option<VehicleKind>,
+    free_on: Option<&str>,
+    _user: User,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    config: &State<AppConfig>,
+    clock: &State<SharedClock>,
+) -> Result<Json<Vec<VehicleAvailability>>, ApiError> {
+    let free_on = free_on
+        .map(|date| parse_date_param("free_on", date))
+        .transpose()?;
+
+    let mut vehicles: Vec<VehicleAvailability> = fleet
+        .lock_pruned(clock.now())
+        .values()
+        .filter(|vehicle| kind.is_none_or(|kind| vehicle.kind == kind))
+        .map(|vehicle| VehicleAvailability {
+            vehicle: vehicle.clone(),
+            free_periods: free_on.map(|date| {
+                vehicle
+                    .free_periods_on(date, &rules)
+                    .into_iter()
+                    .map(|period| config.period_labels.labeled(period))
+                    .collect()
+            }),
+        })
+        .filter(|availability| {
+            availability
+                .free_periods
+                .as_ref()
+                .is_none_or(|periods| !periods.is_empty())
+        })
+        .collect();
+    vehicles.sort_by_key(|availability| availability.vehicle.id);
+
+    Ok(Json(vehicles))
+}
+
+#[post("/vehicles/<id>/maintenance", data = "<opts>")]
+#[allow(clippy::too_many_arguments)]
+pub fn start_maintenance(
+    id: VehicleId,
+    opts: Json<MaintenanceOptions>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<MaintenanceReport>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut sites = sites.write();
+    let report = fleet::start_maintenance(
+        &mut fleet.lock_pruned(clock.now()),
+        id,
+        opts.transfer,
+        clock.today(),
+        &rules,
+    )
+    .ok_or_else(|| vehicle_not_found(id))?;
+
+    // Sites whose reservations moved now use the vehicle taking them over.
+    for moved in &report.moved {
+        let site = moved
+            .reservation
+            .site_id
+            .and_then(|site_id| sites.get_mut(&site_id));
+        if let Some(site) = site {
+            if !site.resources.vehicles.contains(&moved.to) {
+                site.resources.vehicles.push(moved.to);
+            }
+        }
+    }
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("start_maintenance vehicle {id}"),
+        None,
+        ip,
+    );
+    Ok(Json(report))
+}
+
+/// Takes a vehicle put into maintenance by [`start_maintenance`] back into service, serving
+/// its waitlist.
+#[delete("/vehicles/<id>/maintenance")]
+#[allow(clippy::too_many_arguments)]
+pub fn end_maintenance(
+    id: VehicleId,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Vehicle>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    let served = vehicle.end_maintenance(&rules);
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("end_maintenance vehicle {id}"),
+        None,
+        ip,
+    );
+    record_served(audit, clock.now(), id, &served, ip);
+    Ok(Json(vehicle.clone()))
+}
+
+/// Plans maintenance of a vehicle over a period, during which it cannot be reserved.
+/// Refused when the period intersects one of its reservations or leaves less than the
+/// turnaround buffer with it.
+#[post("/vehicles/<id>/maintenance/windows", data = "<window>")]
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_maintenance(
+    id: VehicleId,
+    window: Json<ReservationRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Created<Json<Vehicle>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let ReservationRequest {
+        start_date,
+        start_period,
+        end_date,
+        end_period,
+        note,
+    } = window.into_inner();
+    let window = ReservedDate::new(&start_date, start_period, &end_date, end_period)?
+        .made_by(Some(user.name.clone()), note);
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    vehicle.schedule_maintenance(window, &rules)?;
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("schedule_maintenance vehicle {id}"),
+        None,
+        ip,
+    );
+    Ok(Created::new(format!("/vehicles/{id}")).body(Json(vehicle.clone())))
+}
+
+/// Ends one of a vehicle's reservations earlier, freeing the half-days after its new end.
+#[post("/vehicles/<id>/reservations/shorten", data = "<shorten>")]
+#[allow(clippy::too_many_arguments)]
+pub fn shorten_reservation(
+    id: VehicleId,
+    shorten: Json<ShortenRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Vehicle>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let ShortenRequest {
+        start_date,
+        start_period,
+        end_date,
+        end_period,
+        new_end_date,
+        new_end_period,
+    } = shorten.into_inner();
+    let original = ReservedDate::new(&start_date, start_period, &end_date, end_period)?;
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    vehicle.shorten_reservation(&original, &new_end_date, new_end_period)?;
+    let served = vehicle.serve_waitlist(&rules);
+
+    audit.record_request(
+        clock.now(),
+        user.name,
+        format!("shorten_reservation vehicle {id}"),
+        None,
+        ip,
+    );
+    record_served(audit, clock.now(), id, &served, ip);
+    Ok(Json(vehicle.clone()))
+}
+
+/// Audits the waitlisted requests reserved after some of a vehicle's half-days were freed.
+pub fn record_served(
+    audit: &AuditLog,
+    at: DateTime<Utc>,
+    id: VehicleId,
+    served: &[WaitlistEntry],
+    ip: Option<ClientIp>,
+) {
+    for entry in served {
+        let reservation = &entry.reservation;
+        audit.record_request(
+            at,
+            reservation.reserved_by.as_deref().unwrap_or(SYSTEM_ACTOR),
+            format!("serve_waitlist vehicle {id}"),
+            reservation.site_id,
+            ip,
+        );
+    }
+}
+
+/// The reservation of vehicle `id` for the site `site_id` described by `request`, refused
+/// when the site cannot reserve the vehicle and warned about when it is interrupted.
+fn site_reservation(
+    sites: &HashMap<SiteId, Site>,
+    site_id: SiteId,
+    id: VehicleId,
+    request: ReservationRequest,
+    reserved_by: String,
+) -> Result<Warned<ReservedDate>, ApiError> {
+    let site = sites.get(&site_id).ok_or_else(|| site_not_found(site_id))?;
+    let warnings = site
+        .status
+        .reservation_warning()?
+        .map(|message| Warning::new(WarningCode::SiteInterrupted, message))
+        .into_iter()
+        .collect();
+    if !site.resources.vehicles.contains(&id) {
+        return Err(ApiError::unprocessable(format!(
+            "Vehicle {id} is not used by site {site_id}"
+        )));
+    }
+    let reservation = ReservedDate::new(
+        &request.start_date,
+        request.start_period,
+        &request.end_date,
+        request.end_period,
+    )?
+    .for_site(site_id)
+    .made_by(Some(reserved_by), request.note);
+    Ok(Warned {
+        data: reservation,
+        warnings,
+    })
+}
+
+/// Queues a reservation of one of a site's vehicles, made as soon as the vehicle is free over
+/// its period. Refused when it is already free, the reservation can be made right away, and
+/// when the period intersects a blackout or spans too many days.
+#[post("/vehicles/<id>/waitlist", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub fn join_waitlist(
+    id: VehicleId,
+    request: Json<WaitlistRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    clock: &State<SharedClock>,
+) -> Result<Created<Warned<WaitlistEntry>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let WaitlistRequest {
+        site_id,
+        reservation,
+    } = request.into_inner();
+    let sites = sites.read();
+    let Warned {
+        data: reservation,
+        warnings,
+    } = site_reservation(&sites, site_id, id, reservation, user.name)?;
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    match vehicle.check_available(&reservation, &rules) {
+        Ok(()) => {
+            return Err(ApiError::unprocessable(format!(
+                "Vehicle {id} is free in that period, reserve it instead"
+            )))
+        }
+        Err(err @ (ReservationErr::Blackout(_) | ReservationErr::TooLong { .. })) => {
+            return Err(err.into())
+        }
+        Err(_) => {}
+    }
+    let entry = WaitlistEntry {
+        reservation,
+        queued_at: clock.now(),
+    };
+    vehicle.waitlist.push(entry.clone());
+
+    Ok(Created::new(format!("/vehicles/{id}")).body(Warned {
+        data: entry,
+        warnings,
+    }))
+}
+
+/// Cancels one of a vehicle's reservations, then reserves the waitlisted requests that fit.
+#[post("/vehicles/<id>/reservations/release", data = "<release>")]
+#[allow(clippy::too_many_arguments)]
+pub fn release_reservation(
+    id: VehicleId,
+    release: Json<ReleaseRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<Release>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let ReleaseRequest {
+        start_date,
+        start_period,
+        end_date,
+        end_period,
+    } = release.into_inner();
+    let reservation = ReservedDate::new(&start_date, start_period, &end_date, end_period)?;
+
+    let mut fleet = fleet.lock_pruned(clock.now());
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    let release = vehicle.release(&reservation, &rules).ok_or_else(|| {
+        ApiError::not_found(format!("Vehicle {id} is not reserved in that period"))
+    })?;
+
+    let now = clock.now();
+    audit.record_request(
+        now,
+        user.name,
+        format!("release_reservation vehicle {id}"),
+        release.released.site_id,
+        ip,
+    );
+    record_served(audit, now, id, &release.served, ip);
+    Ok(Json(release))
+}
+
+/// Keeps a slot of one of a site's vehicles for a few minutes, e.g. while a booking is
+/// completed over several steps. The slot is refused to everyone else until the hold is
+/// confirmed, released or expires.
+#[post("/vehicles/<id>/holds", data = "<request>")]
+#[allow(clippy::too_many_arguments)]
+pub fn place_hold(
+    id: VehicleId,
+    request: Json<HoldRequest>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    clock: &State<SharedClock>,
+) -> Result<Created<Warned<Hold>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let HoldRequest {
+        site_id,
+        reservation,
+        ttl_minutes,
+    } = request.into_inner();
+    let ttl_minutes = ttl_minutes.unwrap_or(DEFAULT_HOLD_MINUTES);
+    if !(1..=MAX_HOLD_MINUTES).contains(&ttl_minutes) {
+        return Err(vec![FieldError::new(
+            "ttl_minutes",
+            format!("must be between 1 and {MAX_HOLD_MINUTES}"),
+        )]
+        .into());
+    }
+    let sites = sites.read();
+    let Warned {
+        data: reservation,
+        warnings,
+    } = site_reservation(&sites, site_id, id, reservation, user.name)?;
+
+    let now = clock.now();
+    let mut fleet = fleet.lock_pruned(now);
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    let hold = vehicle.hold(reservation, Duration::minutes(ttl_minutes), now, &rules)?;
+
+    Ok(Created::new(format!("/vehicles/{id}")).body(Warned {
+        data: hold,
+        warnings,
+    }))
+}
+
+/// Turns a hold into a reservation.
+#[post("/vehicles/<id>/holds/<hold_id>/confirm")]
+#[allow(clippy::too_many_arguments)]
+pub fn confirm_hold(
+    id: VehicleId,
+    hold_id: HoldId,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<Json<ReservedDate>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let now = clock.now();
+    let mut fleet = fleet.lock_pruned(now);
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    let reservation = vehicle.confirm_hold(hold_id, now, &rules)?;
+
+    audit.record_request(
+        now,
+        user.name,
+        format!("confirm_hold {hold_id} vehicle {id}"),
+        reservation.site_id,
+        ip,
+    );
+    Ok(Json(reservation))
+}
+
+/// Gives up a hold before it expires, then reserves the waitlisted requests that fit.
+#[delete("/vehicles/<id>/holds/<hold_id>")]
+#[allow(clippy::too_many_arguments)]
+pub fn release_hold(
+    id: VehicleId,
+    hold_id: HoldId,
+    user: User,
+    _csrf: CsrfChecked,
+    fleet: &State<Fleet>,
+    rules: ReservationRules,
+    audit: &State<AuditLog>,
+    clock: &State<SharedClock>,
+    ip: Option<ClientIp>,
+) -> Result<NoContent, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let now = clock.now();
+    let mut fleet = fleet.lock_pruned(now);
+    let vehicle = fleet.get_mut(&id).ok_or_else(|| vehicle_not_found(id))?;
+    vehicle.release_hold(hold_id, now).ok_or(HoldErr::NotHeld)?;
+    let served = vehicle.serve_waitlist(&rules);
+
+    record_served(audit, now, id, &served, ip);
+    Ok(NoContent)
+}
@@ -0,0 +1,74 @@
+use rocket::http::Header;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::Request;
+use serde::Serialize;
+
+/// Stable identifier of a warning, for clients that should not parse its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningCode {
+    /// The site is interrupted, the reservation may not be used.
+    SiteInterrupted,
+    /// The worker lacks a certification the site requires.
+    MissingCertifications,
+    /// The reservation is not within the site's working window.
+    OutsideSiteWindow,
+}
+
+/// A non-fatal advisory about a request that otherwise succeeded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+        Warning {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Response of a mutating endpoint, `{ "data": ..., "warnings": [...] }`.
+///
+/// The warnings are also sent as `Warning` headers for clients that only look at those.
+#[derive(Debug, Clone, Serialize)]
+pub struct Warned<T> {
+    pub data: T,
+    pub warnings: Vec<Warning>,
+}
+
+impl<T> Warned<T> {
+    pub fn new(data: T) -> Self {
+        Warned {
+            data,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn warn(mut self, warning: Warning) -> Self {
+        self.warnings.push(warning);
+        self
+    }
+}
+
+impl<'r, 'o: 'r, T: Serialize> Responder<'r, 'o> for Warned<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let headers: Vec<Header<'static>> = self
+            .warnings
+            .iter()
+            .map(|warning| {
+                let message = warning.message.replace('"', "'");
+                Header::new("Warning", format!("199 - \"{message}\""))
+            })
+            .collect();
+        let mut response = Json(self).respond_to(request)?;
+        for header in headers {
+            response.adjoin_header(header);
+        }
+        Ok(response)
+    }
+}
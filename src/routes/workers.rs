@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rocket::response::status::{Created, NoContent};
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{CsrfChecked, Permission, User};
+use crate::config::{AppConfig, CertificationCheck};
+use crate::i18n::ErrorCode;
+use crate::routes::auth::require;
+use crate::routes::error::ApiError;
+use crate::routes::sites::{visible_site, NewWorker};
+use crate::routes::warnings::{Warned, Warning, WarningCode};
+use crate::sites::{Site, SiteId, SiteStatus, Sites};
+use crate::workers::{Trade, Worker, WorkerId, Workers};
+
+pub fn worker_not_found(id: WorkerId) -> ApiError {
+    ApiError::not_found(format!("No worker with id {id}"))
+}
+
+/// Refuses with a 409 to assign workers to a completed site, whose crew is only history.
+pub fn check_accepts_workers(site: &Site) -> Result<(), ApiError> {
+    if site.status == SiteStatus::Completed {
+        return Err(ApiError::conflict(format!("Site {} is completed", site.id))
+            .with_code(ErrorCode::SiteCompleted));
+    }
+    Ok(())
+}
+
+/// Checks that `worker` holds the certifications `site` requires.
+///
+/// With [`CertificationCheck::Block`] a missing one is a 422 listing them in its `meta`,
+/// otherwise it is returned as a warning.
+pub fn certification_check(
+    site: &Site,
+    worker: &Worker,
+    check: CertificationCheck,
+) -> Result<Option<Warning>, ApiError> {
+    let missing = site.missing_certifications(worker);
+    if missing.is_empty() {
+        return Ok(None);
+    }
+
+    let detail = format!(
+        "Worker {} lacks the certifications {} required by site {}",
+        worker.name,
+        missing.join(", "),
+        site.id
+    );
+    match check {
+        CertificationCheck::Warn => Ok(Some(Warning::new(
+            WarningCode::MissingCertifications,
+            detail,
+        ))),
+        CertificationCheck::Block => {
+            let mut error = ApiError::unprocessable(detail);
+            error.errors[0].meta = Some(serde_json::json!({ "missing_certifications": missing }));
+            Err(error)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MoveWorker {
+    pub to: SiteId,
+}
+
+/// Moves a worker from the sites they currently work on, every non-completed one, to `to`.
+///
+/// Completed sites keep the worker in their history; the move is refused when one of
+/// them overlaps the target's window, or when the target is completed itself, leaving every
+/// assignment unchanged.
+#[post("/workers/<id>/move", data = "<to_site>")]
+pub fn move_worker(
+    id: WorkerId,
+    to_site: Json<MoveWorker>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+) -> Result<Warned<Vec<Site>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let to = to_site.to;
+    let mut sites = sites.write();
+    let worker = workers
+        .lock()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| worker_not_found(id))?;
+    let target = visible_site(&sites, to, &user)?;
+    check_accepts_workers(target)?;
+    let warning = certification_check(target, &worker, config.certification_check)?;
+
+    // Non-completed sites lose the worker, so only completed ones can still conflict.
+    let conflicts: Vec<String> = Sites::worker_conflicts(&sites, id, target, &config.calendar)
+        .into_iter()
+        .filter(|site_id| sites[site_id].status == SiteStatus::Completed)
+        .map(|site_id| site_id.to_string())
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(ApiError::conflict(format!(
+            "Worker {id} would overlap with sites {}",
+            conflicts.join(", ")
+        )));
+    }
+
+    let mut moved_from = Vec::new();
+    for site in sites.values_mut() {
+        if site.id != to && site.status != SiteStatus::Completed && site.has_worker(id) {
+            site.workers.retain(|assigned| *assigned != id);
+            moved_from.push(site.id);
+        }
+    }
+    if let Some(target) = sites.get_mut(&to) {
+        if !target.has_worker(id) {
+            target.workers.push(id);
+        }
+    }
+
+    moved_from.push(to);
+    moved_from.sort();
+    let moved = moved_from
+        .iter()
+        .filter_map(|site_id| sites.get(site_id).cloned())
+        .collect();
+    Ok(warning.into_iter().fold(Warned::new(moved), Warned::warn))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AssignmentOutcome {
+    Assigned,
+    AlreadyAssigned,
+    NotFound,
+    /// The worker lacks a certification the site requires, see [`CertificationCheck`].
+    MissingCertifications,
+    /// The worker is on another site whose window overlaps this one.
+    Overlapping,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerAssignment {
+    pub worker_id: WorkerId,
+    pub outcome: AssignmentOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Adds existing workers to a site, keeping them on their other sites, and reports what
+/// happened to each one, in order. Refused workers are skipped while the others are added.
+/// The whole batch is refused when the site is completed.
+#[post("/sites/<id>/workers/batch", data = "<worker_ids>")]
+pub fn assign_workers(
+    id: SiteId,
+    worker_ids: Json<Vec<WorkerId>>,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+) -> Result<Json<Vec<WorkerAssignment>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let mut sites = sites.write();
+    check_accepts_workers(visible_site(&sites, id, &user)?)?;
+    let workers = workers.lock();
+
+    let mut assignments = Vec::new();
+    for worker_id in worker_ids.into_inner() {
+        let assignment = |outcome, detail| WorkerAssignment {
+            worker_id,
+            outcome,
+            detail,
+        };
+        let target = &sites[&id];
+        if target.has_worker(worker_id) {
+            assignments.push(assignment(AssignmentOutcome::AlreadyAssigned, None));
+            continue;
+        }
+        let Some(worker) = workers.get(&worker_id) else {
+            assignments.push(assignment(AssignmentOutcome::NotFound, None));
+            continue;
+        };
+        let warning = match certification_check(target, worker, config.certification_check) {
+            Ok(warning) => warning,
+            Err(err) => {
+                let detail = err.errors.into_iter().next().map(|error| error.detail);
+                assignments.push(assignment(AssignmentOutcome::MissingCertifications, detail));
+                continue;
+            }
+        };
+        let overlapping = Sites::worker_conflicts(&sites, worker_id, target, &config.calendar);
+        if !overlapping.is_empty() {
+            let overlapping: Vec<String> = overlapping.iter().map(ToString::to_string).collect();
+            assignments.push(assignment(
+                AssignmentOutcome::Overlapping,
+                Some(format!(
+                    "Worker {worker_id} already works on sites {} at that time",
+                    overlapping.join(", ")
+                )),
+            ));
+            continue;
+        }
+
+        if let Some(target) = sites.get_mut(&id) {
+            target.workers.push(worker_id);
+        }
+        assignments.push(assignment(
+            AssignmentOutcome::Assigned,
+            warning.map(|warning| warning.message),
+        ));
+    }
+
+    Ok(Json(assignments))
+}
+
+#[post("/workers", data = "<worker>")]
+pub fn create_worker(
+    worker: Json<NewWorker>,
+    user: User,
+    _csrf: CsrfChecked,
+    workers: &State<Workers>,
+) -> Result<Created<Json<Worker>>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = worker.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut workers = workers.lock();
+    let NewWorker {
+        name,
+        trade,
+        certifications,
+    } = worker.into_inner();
+    let worker = Worker {
+        id: Workers::next_id(&workers),
+        name,
+        trade,
+        certifications,
+    };
+    workers.insert(worker.id, worker.clone());
+
+    Ok(Created::new(format!("/workers/{}", worker.id)).body(Json(worker)))
+}
+
+#[get("/workers")]
+pub fn list_workers(_user: User, workers: &State<Workers>) -> Json<Vec<Worker>> {
+    let mut workers: Vec<Worker> = workers.lock().values().cloned().collect();
+    workers.sort_by_key(|worker| worker.id);
+    Json(workers)
+}
+
+/// Number of workers of each trade assigned to at least one site the user may see. Trades
+/// without any are left out.
+#[get("/workers/trades")]
+pub fn trades(
+    user: User,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+) -> Json<BTreeMap<Trade, usize>> {
+    let sites = sites.read();
+    let workers = workers.lock();
+    let assigned: BTreeSet<WorkerId> = sites
+        .values()
+        .filter(|site| site.visible_to(&user))
+        .flat_map(|site| site.workers.iter().copied())
+        .collect();
+
+    let mut counts = BTreeMap::new();
+    for worker in assigned.iter().filter_map(|id| workers.get(id)) {
+        *counts.entry(worker.trade).or_default() += 1;
+    }
+    Json(counts)
+}
+
+/// Sites the user may see needing the worker's trade they are not assigned to yet, and could
+/// be assigned to without overlapping their other sites. Completed sites are left out.
+#[get("/workers/<id>/matches")]
+pub fn matches(
+    id: WorkerId,
+    user: User,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+    config: &State<AppConfig>,
+) -> Result<Json<Vec<Site>>, ApiError> {
+    let sites = sites.read();
+    let worker = workers
+        .lock()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| worker_not_found(id))?;
+
+    let mut matches: Vec<Site> = sites
+        .values()
+        .filter(|site| site.visible_to(&user) && site.status != SiteStatus::Completed)
+        .filter(|site| site.required_trades.contains(&worker.trade) && !site.has_worker(id))
+        .filter(|site| Sites::worker_conflicts(&sites, id, site, &config.calendar).is_empty())
+        .cloned()
+        .collect();
+    matches.sort_by_key(|site| site.id);
+    Ok(Json(matches))
+}
+
+#[get("/workers/<id>")]
+pub fn get_worker(
+    id: WorkerId,
+    _user: User,
+    workers: &State<Workers>,
+) -> Result<Json<Worker>, ApiError> {
+    workers
+        .lock()
+        .get(&id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| worker_not_found(id))
+}
+
+/// Replaces the name, trade and certifications of a worker. Sites they are already assigned to are
+/// not checked again against the new certifications.
+#[put("/workers/<id>", data = "<fields>")]
+pub fn edit_worker(
+    id: WorkerId,
+    fields: Json<NewWorker>,
+    user: User,
+    _csrf: CsrfChecked,
+    workers: &State<Workers>,
+) -> Result<Json<Worker>, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let errors = fields.validate();
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut workers = workers.lock();
+    let worker = workers.get_mut(&id).ok_or_else(|| worker_not_found(id))?;
+    let NewWorker {
+        name,
+        trade,
+        certifications,
+    } = fields.into_inner();
+    worker.name = name;
+    worker.trade = trade;
+    worker.certifications = certifications;
+
+    Ok(Json(worker.clone()))
+}
+
+/// Removes a worker assigned to no site.
+#[delete("/workers/<id>")]
+pub fn delete_worker(
+    id: WorkerId,
+    user: User,
+    _csrf: CsrfChecked,
+    sites: &State<Sites>,
+    workers: &State<Workers>,
+) -> Result<NoContent, ApiError> {
+    require(&user, Permission::ManageResources)?;
+    let sites = sites.read();
+    let mut workers = workers.lock();
+    if !workers.contains_key(&id) {
+        return Err(worker_not_found(id));
+    }
+
+    let mut assigned_to: Vec<SiteId> = sites
+        .values()
+        .filter(|site| site.has_worker(id))
+        .map(|site| site.id)
+        .collect();
+    if !assigned_to.is_empty() {
+        assigned_to.sort();
+        let assigned_to: Vec<String> = assigned_to.iter().map(ToString::to_string).collect();
+        return Err(ApiError::conflict(format!(
+            "Worker {id} is still assigned to sites {}",
+            assigned_to.join(", ")
+        )));
+    }
+    workers.remove(&id);
+
+    Ok(NoContent)
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::ShareErr;
+use crate::sites::SiteId;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Grant to view a site without logging in until `expires_at`, handed out as a token of the
+/// form `<site id>.<expiry as a unix timestamp>.<hex HMAC-SHA256 of the first two parts>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareToken {
+    pub site_id: SiteId,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ShareToken {
+    fn payload(site_id: SiteId, expires_at: i64) -> String {
+        format!("{site_id}.{expires_at}")
+    }
+
+    fn mac(secret: &str, payload: &str) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// The token, signed with `secret`.
+    pub fn sign(&self, secret: &str) -> String {
+        let payload = ShareToken::payload(self.site_id, self.expires_at.timestamp());
+        let signature: String = ShareToken::mac(secret, &payload)
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        format!("{payload}.{signature}")
+    }
+
+    /// Reads a token signed with `secret`, refusing it once expired at `now`.
+    pub fn verify(token: &str, secret: &str, now: DateTime<Utc>) -> Result<ShareToken, ShareErr> {
+        let mut parts = token.split('.');
+        let (Some(site_id), Some(expires_at), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ShareErr::Invalid);
+        };
+        let site_id: u32 = site_id.parse().map_err(|_| ShareErr::Invalid)?;
+        let expires_at: i64 = expires_at.parse().map_err(|_| ShareErr::Invalid)?;
+        let signature = decode_hex(signature).ok_or(ShareErr::Invalid)?;
+
+        let payload = ShareToken::payload(SiteId(site_id), expires_at);
+        ShareToken::mac(secret, &payload)
+            .verify_slice(&signature)
+            .map_err(|_| ShareErr::Invalid)?;
+
+        let expires_at = DateTime::from_timestamp(expires_at, 0).ok_or(ShareErr::Invalid)?;
+        if expires_at <= now {
+            return Err(ShareErr::Expired(expires_at));
+        }
+        Ok(ShareToken {
+            site_id: SiteId(site_id),
+            expires_at,
+        })
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,1424 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::num::ParseIntError;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, TimeZone, Utc};
+use rocket::request::FromParam;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{AppRole, User};
+use crate::calendar::{Calendar, DayIndex};
+use crate::config::WorkHours;
+use crate::error::{DateParsedErr, ReservationErr, Result};
+use crate::fleet::{Vehicle, VehicleId};
+use crate::phone::PhoneNumber;
+use crate::photos::{Photo, PhotoId};
+use crate::storage::{StorageErr, StorageFormat};
+use crate::supplies::{Material, MaterialId, Tool, ToolId};
+use crate::validation::{integrity_validation, FieldError};
+use crate::versions::SiteSnapshot;
+use crate::workers::{deserialize_worker_ids, Trade, Worker, WorkerId};
+
+/// Half of a working day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DayPeriod {
+    Morning,
+    Afternoon,
+}
+
+impl DayPeriod {
+    /// Canonical time of day at which the period starts.
+    pub fn to_hms(&self) -> (u32, u32, u32) {
+        match self {
+            DayPeriod::Morning => (8, 0, 0),
+            DayPeriod::Afternoon => (13, 0, 0),
+        }
+    }
+
+    pub fn opposite(&self) -> DayPeriod {
+        match self {
+            DayPeriod::Morning => DayPeriod::Afternoon,
+            DayPeriod::Afternoon => DayPeriod::Morning,
+        }
+    }
+
+    /// How the day index and period change when moving one half-day forward.
+    pub fn advance(&self) -> (i64, DayPeriod) {
+        match self {
+            DayPeriod::Morning => (0, DayPeriod::Afternoon),
+            DayPeriod::Afternoon => (1, DayPeriod::Morning),
+        }
+    }
+}
+
+/// Half-days from a first to a last one, both included, in order. With
+/// [`HalfDayIter::working`], half-days of days the calendar does not work are skipped.
+#[derive(Debug, Clone)]
+pub struct HalfDayIter<'a> {
+    next: Option<(NaiveDate, DayPeriod)>,
+    last: (NaiveDate, DayPeriod),
+    calendar: Option<&'a Calendar>,
+}
+
+impl<'a> HalfDayIter<'a> {
+    pub fn new(first: (NaiveDate, DayPeriod), last: (NaiveDate, DayPeriod)) -> Self {
+        HalfDayIter {
+            next: Some(first),
+            last,
+            calendar: None,
+        }
+    }
+
+    /// Both half-days of every date from `from` to `to`.
+    pub fn days(from: NaiveDate, to: NaiveDate) -> Self {
+        HalfDayIter::new((from, DayPeriod::Morning), (to, DayPeriod::Afternoon))
+    }
+
+    /// Only yields the half-days of days `calendar` works.
+    pub fn working(self, calendar: &'a Calendar) -> Self {
+        HalfDayIter {
+            calendar: Some(calendar),
+            ..self
+        }
+    }
+}
+
+impl Iterator for HalfDayIter<'_> {
+    type Item = (NaiveDate, DayPeriod);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let current = self.next.filter(|current| *current <= self.last)?;
+            let (days, period) = current.1.advance();
+            self.next = current
+                .0
+                .checked_add_signed(TimeDelta::days(days))
+                .map(|date| (date, period));
+            if self
+                .calendar
+                .is_none_or(|calendar| calendar.is_working_day(current.0))
+            {
+                return Some(current);
+            }
+        }
+    }
+}
+
+/// A reservation spanning from a start half-day to an end half-day, both inclusive.
+///
+/// Reservations are ordered by start, then end; the remaining fields only break ties. Responses
+/// show them with the display label of each period, see [`period_labels`](crate::config::period_labels).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReservedDate {
+    pub start_date: SystemTime,
+    pub start_period: DayPeriod,
+    pub end_date: SystemTime,
+    pub end_period: DayPeriod,
+    /// Site the reservation was made for, ignored by `compatible_with`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_id: Option<SiteId>,
+    /// Name of the user who made the reservation, ignored by `compatible_with`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserved_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl ReservedDate {
+    /// Builds a reservation from `YYYY-MM-DD` dates, anchoring each instant
+    /// at the canonical time of its period.
+    pub fn new(
+        start_date: &str,
+        start_period: DayPeriod,
+        end_date: &str,
+        end_period: DayPeriod,
+    ) -> Result<Self> {
+        let start = parse_date(start_date).ok_or(DateParsedErr::InvalidStartDate)?;
+        let end = parse_date(end_date).ok_or(DateParsedErr::InvalidEndDate)?;
+        ReservedDate::from_dates(start, start_period, end, end_period)
+    }
+
+    /// Builds a reservation from instants, covering every half-day worked between them, see
+    /// [`snap_to_half_day`].
+    pub fn from_instants(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        work_hours: &WorkHours,
+        calendar: &Calendar,
+    ) -> Result<Self> {
+        let ((start, start_period), (end, end_period)) =
+            snap_to_half_day(start, end, work_hours, calendar);
+        ReservedDate::from_dates(start, start_period, end, end_period)
+    }
+
+    pub fn from_dates(
+        start: NaiveDate,
+        start_period: DayPeriod,
+        end: NaiveDate,
+        end_period: DayPeriod,
+    ) -> Result<Self> {
+        if (start, start_period) > (end, end_period) {
+            return Err(DateParsedErr::StartAfterEnd.into());
+        }
+
+        Ok(ReservedDate {
+            start_date: to_instant(start, start_period),
+            start_period,
+            end_date: to_instant(end, end_period),
+            end_period,
+            site_id: None,
+            reserved_by: None,
+            note: None,
+        })
+    }
+
+    /// Reservation of a single half-day.
+    pub fn half_day(date: NaiveDate, period: DayPeriod) -> Self {
+        ReservedDate {
+            start_date: to_instant(date, period),
+            start_period: period,
+            end_date: to_instant(date, period),
+            end_period: period,
+            site_id: None,
+            reserved_by: None,
+            note: None,
+        }
+    }
+
+    pub fn for_site(self, site_id: SiteId) -> Self {
+        ReservedDate {
+            site_id: Some(site_id),
+            ..self
+        }
+    }
+
+    /// Records who made the reservation and why.
+    pub fn made_by(self, reserved_by: Option<String>, note: Option<String>) -> Self {
+        ReservedDate {
+            reserved_by,
+            note,
+            ..self
+        }
+    }
+
+    /// Calendar date of the first reserved half-day.
+    pub fn first_date(&self) -> NaiveDate {
+        DateTime::<Utc>::from(self.start_date).date_naive()
+    }
+
+    /// Calendar date of the last reserved half-day.
+    pub fn last_date(&self) -> NaiveDate {
+        DateTime::<Utc>::from(self.end_date).date_naive()
+    }
+
+    /// Calendar days from the first to the last reserved half-day, both included.
+    pub fn span_days(&self) -> i64 {
+        (self.last_date() - self.first_date()).num_days() + 1
+    }
+
+    /// Indices of the first and last half-days covered, counting two per calendar day.
+    pub fn half_day_span(&self) -> (i64, i64) {
+        let index = |date: NaiveDate, period: DayPeriod| {
+            i64::from(date.num_days_from_ce()) * 2
+                + match period {
+                    DayPeriod::Morning => 0,
+                    DayPeriod::Afternoon => 1,
+                }
+        };
+        (
+            index(self.first_date(), self.start_period),
+            index(self.last_date(), self.end_period),
+        )
+    }
+
+    /// Number of half-days covered, working or not.
+    pub fn half_day_count(&self) -> usize {
+        let (start, end) = self.half_day_span();
+        (end - start + 1).max(0) as usize
+    }
+
+    /// Every half-day covered, working or not, in order.
+    pub fn half_days(&self) -> Vec<(NaiveDate, DayPeriod)> {
+        HalfDayIter::new(
+            (self.first_date(), self.start_period),
+            (self.last_date(), self.end_period),
+        )
+        .collect()
+    }
+
+    /// Half-days covered by both reservations, in order.
+    pub fn overlapping_half_days(&self, another: &ReservedDate) -> Vec<(NaiveDate, DayPeriod)> {
+        let theirs = another.half_days();
+        self.half_days()
+            .into_iter()
+            .filter(|half_day| theirs.contains(half_day))
+            .collect()
+    }
+
+    /// Two reservations are compatible when they do not share any half-day.
+    ///
+    /// Reservations touching on the same day are compatible as long as the
+    /// earlier one ends in the morning and the later one starts in the
+    /// afternoon, whichever order they are given in. Instants are compared by
+    /// calendar date only, the periods settle same-day boundaries.
+    pub fn compatible_with(&self, another: &ReservedDate) -> bool {
+        let (self_start, self_end) = (self.first_date(), self.last_date());
+        let (another_start, another_end) = (another.first_date(), another.last_date());
+
+        self_end < another_start
+            || another_end < self_start
+            || (self_end == another_start && self.end_period < another.start_period)
+            || (another_end == self_start && another.end_period < self.start_period)
+    }
+
+    /// Like [`ReservedDate::compatible_with`], also requiring at least `buffer` between the
+    /// end of the earlier reservation's work and the start of the later's, as given by `hours`.
+    pub fn compatible_with_buffer(
+        &self,
+        another: &ReservedDate,
+        buffer: TimeDelta,
+        hours: &WorkHours,
+    ) -> bool {
+        if !self.compatible_with(another) {
+            return false;
+        }
+        if buffer <= TimeDelta::zero() {
+            return true;
+        }
+        let (earlier, later) = if self.half_day_span().1 < another.half_day_span().0 {
+            (self, another)
+        } else {
+            (another, self)
+        };
+        let earlier_end = earlier
+            .last_date()
+            .and_time(hours.bounds(earlier.end_period).1);
+        let later_start = later
+            .first_date()
+            .and_time(hours.bounds(later.start_period).0);
+        later_start - earlier_end >= buffer
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Half-days from the one `start` falls in, rounded down, to the one `end` falls in, rounded
+/// up, so that every bit of work between them is covered: 10:30 starts in the morning,
+/// 14:00 ends in the afternoon. Instants out of work hours go to the next half-day when
+/// starting and to the previous one when ending, e.g. a start after the afternoon ends moves
+/// to the next morning. Likewise, a start on a day `calendar` does not work moves to the
+/// morning of the next working day, and an end to the afternoon of the previous one.
+pub fn snap_to_half_day(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    work_hours: &WorkHours,
+    calendar: &Calendar,
+) -> ((NaiveDate, DayPeriod), (NaiveDate, DayPeriod)) {
+    let (start_date, start_time) = (start.date_naive(), start.time());
+    let first = if start_time < work_hours.morning_end {
+        (start_date, DayPeriod::Morning)
+    } else if start_time < work_hours.afternoon_end {
+        (start_date, DayPeriod::Afternoon)
+    } else {
+        (start_date + TimeDelta::days(1), DayPeriod::Morning)
+    };
+    let first = calendar
+        .working_periods(first, 1)
+        .first()
+        .copied()
+        .unwrap_or(first);
+
+    let (end_date, end_time) = (end.date_naive(), end.time());
+    let last = if end_time <= work_hours.morning_start {
+        (end_date - TimeDelta::days(1), DayPeriod::Afternoon)
+    } else if end_time <= work_hours.afternoon_start {
+        (end_date, DayPeriod::Morning)
+    } else {
+        (end_date, DayPeriod::Afternoon)
+    };
+    let last = if calendar.working_weekdays.is_empty() || calendar.is_working_day(last.0) {
+        last
+    } else {
+        std::iter::successors(last.0.pred_opt(), NaiveDate::pred_opt)
+            .find(|date| calendar.is_working_day(*date))
+            .map_or(last, |date| (date, DayPeriod::Afternoon))
+    };
+    (first, last)
+}
+
+fn to_instant(date: NaiveDate, period: DayPeriod) -> SystemTime {
+    let (h, m, s) = period.to_hms();
+    let naive = date
+        .and_hms_opt(h, m, s)
+        .expect("canonical period times are valid");
+    Utc.from_utc_datetime(&naive).into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SiteId(pub u32);
+
+impl fmt::Display for SiteId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for SiteId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(SiteId)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteResource {
+    /// Fleet vehicles used by the site.
+    pub vehicles: Vec<VehicleId>,
+    #[serde(default)]
+    pub tools: Vec<Tool>,
+    #[serde(default)]
+    pub materials: Vec<Material>,
+}
+
+impl SiteResource {
+    pub fn next_tool_id(&self) -> ToolId {
+        ToolId(self.tools.iter().map(|tool| tool.id.0).max().unwrap_or(0) + 1)
+    }
+
+    pub fn next_material_id(&self) -> MaterialId {
+        MaterialId(
+            self.materials
+                .iter()
+                .map(|material| material.id.0)
+                .max()
+                .unwrap_or(0)
+                + 1,
+        )
+    }
+
+    /// Every pair of intersecting reservations on the site's vehicles. Reservations are checked
+    /// on the way in, so any pair found here means the data got into an inconsistent state.
+    pub fn all_conflicts(
+        &self,
+        fleet: &HashMap<VehicleId, Vehicle>,
+    ) -> Vec<(VehicleId, ReservedDate, ReservedDate)> {
+        let mut conflicts = Vec::new();
+        for vehicle in self.vehicles.iter().filter_map(|id| fleet.get(id)) {
+            let reserved = &vehicle.reserved_dates;
+            for (i, first) in reserved.iter().enumerate() {
+                for second in &reserved[i + 1..] {
+                    if !first.compatible_with(second) {
+                        conflicts.push((vehicle.id, first.clone(), second.clone()));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Average utilization of the site's vehicles between `from` and `to`, `0.0` without any.
+    pub fn utilization(
+        &self,
+        fleet: &HashMap<VehicleId, Vehicle>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> f64 {
+        let utilizations: Vec<f64> = self
+            .vehicles
+            .iter()
+            .filter_map(|id| fleet.get(id))
+            .map(|vehicle| vehicle.utilization(from, to))
+            .collect();
+        if utilizations.is_empty() {
+            return 0.0;
+        }
+        utilizations.iter().sum::<f64>() / utilizations.len() as f64
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, FromFormField,
+)]
+pub enum SiteStatus {
+    NotCarried,
+    InProgress,
+    Completed,
+    Interrupted,
+}
+
+impl SiteStatus {
+    /// Whether vehicles can be attached to, reserved, held or waitlisted for a site in this
+    /// status, i.e. unless it is completed.
+    pub fn accepts_reservations(&self) -> bool {
+        *self != SiteStatus::Completed
+    }
+
+    /// Checks [`SiteStatus::accepts_reservations`], with a warning for interrupted sites as
+    /// reserving for them is usually a mistake. Every reservation entry point goes through it.
+    pub fn reservation_warning(&self) -> Result<Option<&'static str>, ReservationErr> {
+        if !self.accepts_reservations() {
+            return Err(ReservationErr::SiteCompleted);
+        }
+        Ok((*self == SiteStatus::Interrupted).then_some("The site is interrupted"))
+    }
+
+    /// Percentage of the work done a site in this status shows, `None` for interrupted sites
+    /// which keep the progress they had, see [`Site::progress`].
+    pub fn progress(&self) -> Option<u8> {
+        match self {
+            SiteStatus::NotCarried => Some(0),
+            SiteStatus::InProgress => Some(50),
+            SiteStatus::Completed => Some(100),
+            SiteStatus::Interrupted => None,
+        }
+    }
+
+    /// Whether a site can go from this status to `next`. Completed sites stay completed.
+    pub fn can_transition_to(&self, next: SiteStatus) -> bool {
+        use SiteStatus::*;
+        matches!(
+            (self, next),
+            (NotCarried, InProgress)
+                | (InProgress, Completed)
+                | (InProgress, Interrupted)
+                | (Interrupted, InProgress)
+                | (Interrupted, Completed)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteDuration {
+    pub half_day: usize,
+}
+
+impl SiteDuration {
+    pub fn as_working_days(&self) -> f64 {
+        self.half_day as f64 / 2.0
+    }
+
+    /// Working time spanned by the site, which always starts on a morning.
+    pub fn to_chrono_duration(&self, work_hours: &WorkHours) -> TimeDelta {
+        [DayPeriod::Morning, DayPeriod::Afternoon]
+            .into_iter()
+            .cycle()
+            .take(self.half_day)
+            .map(|period| work_hours.length_of(period))
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Area between two latitudes and two longitudes, not crossing the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub min_longitude: f64,
+    pub max_latitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    /// Whether `coordinates` are in the box, edges included.
+    pub fn contains(&self, coordinates: &Coordinates) -> bool {
+        (self.min_latitude..=self.max_latitude).contains(&coordinates.latitude)
+            && (self.min_longitude..=self.max_longitude).contains(&coordinates.longitude)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Client {
+    pub name: String,
+    /// Primary phone number.
+    pub phone: PhoneNumber,
+    /// People to reach besides the primary number, e.g. the client's foreman.
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub phone: PhoneNumber,
+    /// What the contact is reached for, e.g. `"accounting"`, possibly empty.
+    #[serde(default)]
+    pub role: String,
+}
+
+/// A status a site entered, when, and why if it was said.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusRecord {
+    pub status: SiteStatus,
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A problem reported on a site.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Site {
+    pub id: SiteId,
+    pub name: String,
+    pub coordinates: Coordinates,
+    pub client: Client,
+    /// Day index, counted from the calendar's project start, on whose morning the site starts.
+    pub start_day: DayIndex,
+    pub duration: SiteDuration,
+    pub status: SiteStatus,
+    /// Set once the site is still in progress after its end day.
+    #[serde(default)]
+    pub overdue: bool,
+    #[serde(default)]
+    pub resources: SiteResource,
+    /// Ids of the assigned workers, whose details are in the [`Workers`](crate::workers::Workers)
+    /// registry.
+    #[serde(default, deserialize_with = "deserialize_worker_ids")]
+    pub workers: Vec<WorkerId>,
+    /// Sites that must be completed before this one starts.
+    #[serde(default)]
+    pub depends_on: Vec<SiteId>,
+    #[serde(default)]
+    pub status_history: Vec<StatusRecord>,
+    #[serde(default)]
+    pub anomalies: Vec<Anomaly>,
+    /// Certifications every worker assigned to the site must hold.
+    #[serde(default)]
+    pub required_certifications: Vec<String>,
+    /// Trades the site needs workers of, see `GET /workers/<id>/matches`.
+    #[serde(default)]
+    pub required_trades: Vec<Trade>,
+    /// Rank in the global managers' backlog, lower first.
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub photos: Vec<Photo>,
+    /// Name of the user who created the site, empty for sites stored before it was recorded.
+    #[serde(default)]
+    pub created_by: String,
+    /// Names of the users managing the site.
+    #[serde(default)]
+    pub managers: Vec<String>,
+    /// Global manager answering for the site, its creator until it is transferred.
+    #[serde(default)]
+    pub responsible: Option<String>,
+}
+
+impl Site {
+    pub fn to_bytes(&self, format: StorageFormat) -> Result<Vec<u8>, StorageErr> {
+        format.encode(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8], format: StorageFormat) -> Result<Self, StorageErr> {
+        format.decode(bytes)
+    }
+
+    /// State of the site to compare with later versions, without its reservations, see
+    /// [`SiteSnapshot::with_reservations`].
+    pub fn snapshot(&self) -> SiteSnapshot {
+        SiteSnapshot {
+            name: self.name.clone(),
+            status: self.status,
+            workers: self.workers.clone(),
+            vehicles: self.resources.vehicles.clone(),
+            reservations: Vec::new(),
+        }
+    }
+
+    /// Checks the invariants the API enforces on the way in, see [`integrity_validation`].
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let errors = integrity_validation(self);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Moves the site to `status`, keeping track of when and why it happened. A site no longer
+    /// in progress is not overdue anymore.
+    pub fn set_status(&mut self, status: SiteStatus, at: DateTime<Utc>, reason: Option<String>) {
+        self.status = status;
+        if status != SiteStatus::InProgress {
+            self.overdue = false;
+        }
+        self.status_history
+            .push(StatusRecord { status, at, reason });
+    }
+
+    /// Whether the site is still in progress after its end day, as of `today`.
+    pub fn is_past_due(&self, calendar: &Calendar, today: NaiveDate) -> bool {
+        self.status == SiteStatus::InProgress
+            && calendar
+                .date_of_day(self.end_day(calendar))
+                .is_ok_and(|end| end < today)
+    }
+
+    /// Clears the overdue flag of a site no longer past due, e.g. after moving its schedule.
+    /// Only the overdue scan sets it, recording it in the audit log.
+    pub fn refresh_overdue(&mut self, calendar: &Calendar, today: NaiveDate) {
+        self.overdue = self.overdue && self.is_past_due(calendar, today);
+    }
+
+    /// When the site was last interrupted, if it still is.
+    pub fn interrupted_at(&self) -> Option<DateTime<Utc>> {
+        if self.status != SiteStatus::Interrupted {
+            return None;
+        }
+        self.status_history
+            .iter()
+            .rev()
+            .find(|record| record.status == SiteStatus::Interrupted)
+            .map(|record| record.at)
+    }
+
+    /// Percentage of the work done, from [`SiteStatus::progress`]. An interrupted site keeps
+    /// the progress of the status it was interrupted in.
+    pub fn progress(&self) -> u8 {
+        self.status.progress().unwrap_or_else(|| {
+            self.status_history
+                .iter()
+                .rev()
+                .find_map(|record| record.status.progress())
+                .unwrap_or(0)
+        })
+    }
+
+    pub fn latest_anomaly(&self) -> Option<&Anomaly> {
+        self.anomalies.last()
+    }
+
+    /// Whether `user` may see the site in listings: global managers see every site, site
+    /// managers only those they manage.
+    pub fn visible_to(&self, user: &User) -> bool {
+        match user.role {
+            AppRole::SitesGlobalManager => true,
+            AppRole::SiteManager => self.managers.contains(&user.name),
+        }
+    }
+
+    /// Anomalies reported since the site last left the interrupted status, if ever.
+    pub fn open_anomalies(&self) -> Vec<&Anomaly> {
+        let resolved_at = self
+            .status_history
+            .iter()
+            .rev()
+            .find(|record| record.status != SiteStatus::Interrupted)
+            .map(|record| record.at);
+        self.anomalies
+            .iter()
+            .filter(|anomaly| resolved_at.is_none_or(|at| anomaly.at > at))
+            .collect()
+    }
+
+    /// Half-days the site is worked, skipping the calendar's non-working days. Empty when its
+    /// start day is out of the calendar's range.
+    pub fn working_periods(&self, calendar: &Calendar) -> Vec<(NaiveDate, DayPeriod)> {
+        let Ok(start) = calendar.date_of_day(self.start_day) else {
+            return Vec::new();
+        };
+        calendar.working_periods((start, DayPeriod::Morning), self.duration.half_day)
+    }
+
+    /// Span from the first to the last half-day the site is worked, `None` when it has no duration.
+    pub fn window(&self, calendar: &Calendar) -> Option<ReservedDate> {
+        let periods = self.working_periods(calendar);
+        let (first, first_period) = periods.first()?;
+        let (last, last_period) = periods.last()?;
+        ReservedDate::from_dates(*first, *first_period, *last, *last_period).ok()
+    }
+
+    /// Maintenance windows of the site's vehicles that intersect its working window, with the
+    /// vehicle they belong to. Empty for a site without duration.
+    pub fn maintenance_conflicts(
+        &self,
+        fleet: &HashMap<VehicleId, Vehicle>,
+        calendar: &Calendar,
+    ) -> Vec<(VehicleId, ReservedDate)> {
+        let Some(window) = self.window(calendar) else {
+            return Vec::new();
+        };
+        self.resources
+            .vehicles
+            .iter()
+            .filter_map(|id| fleet.get(id))
+            .flat_map(|vehicle| {
+                vehicle
+                    .maintenance_windows
+                    .iter()
+                    .filter(|maintenance| !maintenance.compatible_with(&window))
+                    .map(|maintenance| (vehicle.id, maintenance.clone()))
+            })
+            .collect()
+    }
+
+    /// Dependencies not completed yet, unknown ones included.
+    pub fn blocking_dependencies(&self, all: &HashMap<SiteId, Site>) -> Vec<SiteId> {
+        self.depends_on
+            .iter()
+            .filter(|id| {
+                all.get(id)
+                    .is_none_or(|site| site.status != SiteStatus::Completed)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Whether every dependency of the site is completed.
+    pub fn can_start(&self, all: &HashMap<SiteId, Site>) -> bool {
+        self.blocking_dependencies(all).is_empty()
+    }
+
+    pub fn next_photo_id(&self) -> PhotoId {
+        PhotoId(
+            self.photos
+                .iter()
+                .map(|photo| photo.id.0)
+                .max()
+                .unwrap_or(0)
+                + 1,
+        )
+    }
+
+    pub fn cover_photo(&self) -> Option<&Photo> {
+        self.photos.iter().find(|photo| photo.is_cover)
+    }
+
+    pub fn has_worker(&self, worker: WorkerId) -> bool {
+        self.workers.contains(&worker)
+    }
+
+    /// Required certifications `worker` does not hold.
+    pub fn missing_certifications(&self, worker: &Worker) -> Vec<String> {
+        self.required_certifications
+            .iter()
+            .filter(|required| !worker.certifications.contains(required))
+            .cloned()
+            .collect()
+    }
+
+    /// Half-days of work the site takes, every assigned worker working its whole duration.
+    pub fn total_labor_half_days(&self) -> usize {
+        self.workers.len() * self.duration.half_day
+    }
+
+    /// Day index of the last working day of the site.
+    pub fn end_day(&self, calendar: &Calendar) -> DayIndex {
+        self.working_periods(calendar)
+            .last()
+            .map_or(self.start_day, |(date, _)| calendar.day_of_date(*date))
+    }
+}
+
+/// In-memory registry of every site, shared between handlers and background tasks.
+///
+/// Handlers only reading sites take [`Sites::read`] so they don't block each other. A guard is
+/// never upgraded: a handler that may write takes [`Sites::write`] from the start.
+#[derive(Debug, Clone, Default)]
+pub struct Sites(Arc<RwLock<HashMap<SiteId, Site>>>);
+
+impl Sites {
+    /// Locks the registry for reading. When the fleet is needed too, lock the sites first.
+    pub fn read(&self) -> RwLockReadGuard<'_, HashMap<SiteId, Site>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the registry for writing. When the fleet is needed too, lock the sites first.
+    pub fn write(&self) -> RwLockWriteGuard<'_, HashMap<SiteId, Site>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn next_id(sites: &HashMap<SiteId, Site>) -> SiteId {
+        SiteId(sites.keys().map(|id| id.0 + 1).max().unwrap_or(1))
+    }
+
+    /// Priority putting a new site at the end of the backlog.
+    pub fn next_priority(sites: &HashMap<SiteId, Site>) -> i32 {
+        sites
+            .values()
+            .map(|site| site.priority + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of sites not completed `manager` manages.
+    pub fn managed_count(sites: &HashMap<SiteId, Site>, manager: &str) -> usize {
+        sites
+            .values()
+            .filter(|site| site.status != SiteStatus::Completed)
+            .filter(|site| site.managers.iter().any(|name| name == manager))
+            .count()
+    }
+
+    /// Sites other than `target` the worker is assigned to whose window overlaps the target's.
+    pub fn worker_conflicts(
+        sites: &HashMap<SiteId, Site>,
+        worker: WorkerId,
+        target: &Site,
+        calendar: &Calendar,
+    ) -> Vec<SiteId> {
+        let Some(window) = target.window(calendar) else {
+            return Vec::new();
+        };
+        let mut conflicts: Vec<SiteId> = sites
+            .values()
+            .filter(|site| site.id != target.id && site.has_worker(worker))
+            .filter(|site| {
+                site.window(calendar)
+                    .is_some_and(|other| !other.compatible_with(&window))
+            })
+            .map(|site| site.id)
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    /// Whether giving `id` the dependencies `depends_on` would make it depend on itself.
+    pub fn creates_dependency_cycle(
+        sites: &HashMap<SiteId, Site>,
+        id: SiteId,
+        depends_on: &[SiteId],
+    ) -> bool {
+        let mut stack = depends_on.to_vec();
+        let mut seen = HashSet::new();
+        while let Some(current) = stack.pop() {
+            if current == id {
+                return true;
+            }
+            if seen.insert(current) {
+                if let Some(site) = sites.get(&current) {
+                    stack.extend(&site.depends_on);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use DayPeriod::{Afternoon, Morning};
+
+    fn reservation(
+        start: &str,
+        start_period: DayPeriod,
+        end: &str,
+        end_period: DayPeriod,
+    ) -> ReservedDate {
+        ReservedDate::new(start, start_period, end, end_period).unwrap()
+    }
+
+    #[test]
+    fn touching_half_days_are_compatible_in_both_orders() {
+        let morning = reservation("2024-03-01", Morning, "2024-03-04", Morning);
+        let afternoon = reservation("2024-03-04", Afternoon, "2024-03-06", Afternoon);
+
+        assert!(morning.compatible_with(&afternoon));
+        assert!(afternoon.compatible_with(&morning));
+    }
+
+    #[test]
+    fn sharing_a_half_day_is_incompatible_in_both_orders() {
+        let earlier = reservation("2024-03-01", Morning, "2024-03-04", Afternoon);
+        let later = reservation("2024-03-04", Afternoon, "2024-03-06", Afternoon);
+
+        assert!(!earlier.compatible_with(&later));
+        assert!(!later.compatible_with(&earlier));
+    }
+
+    #[test]
+    fn inverted_periods_on_the_touching_day_are_incompatible() {
+        // The earlier one ends in the afternoon of the day the later one starts in the morning.
+        let earlier = reservation("2024-03-01", Morning, "2024-03-04", Afternoon);
+        let later = reservation("2024-03-04", Morning, "2024-03-06", Afternoon);
+
+        assert!(!earlier.compatible_with(&later));
+        assert!(!later.compatible_with(&earlier));
+    }
+
+    #[test]
+    fn identical_reservations_are_incompatible() {
+        let half_day = reservation("2024-03-04", Morning, "2024-03-04", Morning);
+        let days = reservation("2024-03-01", Morning, "2024-03-04", Afternoon);
+
+        assert!(!half_day.compatible_with(&half_day.clone()));
+        assert!(!days.compatible_with(&days.clone()));
+    }
+
+    #[test]
+    fn reservations_days_apart_are_compatible() {
+        let first = reservation("2024-03-01", Morning, "2024-03-01", Afternoon);
+        let second = reservation("2024-03-03", Morning, "2024-03-03", Morning);
+
+        assert!(first.compatible_with(&second));
+        assert!(second.compatible_with(&first));
+    }
+
+    #[test]
+    fn overlapping_half_days_are_the_shared_ones_in_both_orders() {
+        let earlier = reservation("2024-03-01", Afternoon, "2024-03-04", Morning);
+        let later = reservation("2024-03-03", Afternoon, "2024-03-05", Afternoon);
+        let shared = [
+            (parse_date("2024-03-03").unwrap(), Afternoon),
+            (parse_date("2024-03-04").unwrap(), Morning),
+        ];
+
+        assert_eq!(earlier.half_days().len(), 6);
+        assert_eq!(earlier.overlapping_half_days(&later), shared);
+        assert_eq!(later.overlapping_half_days(&earlier), shared);
+        assert_eq!(
+            earlier.overlapping_half_days(&reservation(
+                "2024-03-06",
+                Morning,
+                "2024-03-06",
+                Morning
+            )),
+            []
+        );
+    }
+
+    /// A reservation whose instants are exactly the given ones, e.g. coinciding with another's.
+    fn between(
+        start: DateTime<Utc>,
+        start_period: DayPeriod,
+        end: DateTime<Utc>,
+        end_period: DayPeriod,
+    ) -> ReservedDate {
+        ReservedDate {
+            start_date: start.into(),
+            start_period,
+            end_date: end.into(),
+            end_period,
+            site_id: None,
+            reserved_by: None,
+            note: None,
+        }
+    }
+
+    /// The canonical instant of `period` on `date`, as [`ReservedDate::new`] anchors it.
+    fn canonical(date: &str, period: DayPeriod) -> DateTime<Utc> {
+        let (h, m, s) = period.to_hms();
+        let date = parse_date(date).unwrap();
+        Utc.from_utc_datetime(&date.and_hms_opt(h, m, s).unwrap())
+    }
+
+    #[test]
+    fn canonical_instants_are_those_reservations_are_built_with() {
+        let built = reservation("2024-03-04", Morning, "2024-03-05", Afternoon);
+        let exact = between(
+            canonical("2024-03-04", Morning),
+            Morning,
+            canonical("2024-03-05", Afternoon),
+            Afternoon,
+        );
+
+        assert_eq!(built, exact);
+    }
+
+    #[test]
+    fn coinciding_boundary_instants_conflict_on_the_same_period() {
+        let boundary = canonical("2024-03-04", Afternoon);
+        let earlier = between(
+            canonical("2024-03-01", Morning),
+            Morning,
+            boundary,
+            Afternoon,
+        );
+        let later = between(
+            boundary,
+            Afternoon,
+            canonical("2024-03-06", Morning),
+            Morning,
+        );
+
+        assert!(!earlier.compatible_with(&later));
+        assert!(!later.compatible_with(&earlier));
+    }
+
+    #[test]
+    fn canonical_morning_end_and_afternoon_start_on_the_same_day_are_compatible() {
+        let earlier = between(
+            canonical("2024-03-01", Morning),
+            Morning,
+            canonical("2024-03-04", Morning),
+            Morning,
+        );
+        let later = between(
+            canonical("2024-03-04", Afternoon),
+            Afternoon,
+            canonical("2024-03-06", Morning),
+            Morning,
+        );
+
+        assert!(earlier.compatible_with(&later));
+        assert!(later.compatible_with(&earlier));
+    }
+
+    #[test]
+    fn a_full_day_conflicts_with_an_afternoon_starting_that_day() {
+        let full_day = reservation("2024-03-04", Morning, "2024-03-04", Afternoon);
+        let afternoon = reservation("2024-03-04", Afternoon, "2024-03-05", Morning);
+
+        assert!(!full_day.compatible_with(&afternoon));
+        assert!(!afternoon.compatible_with(&full_day));
+    }
+
+    #[test]
+    fn instants_are_compared_by_calendar_date() {
+        // Late and early instants of the same day still only meet on their periods.
+        let day = parse_date("2024-03-04").unwrap();
+        let late = Utc.from_utc_datetime(&day.and_hms_opt(23, 30, 0).unwrap());
+        let early = Utc.from_utc_datetime(&day.and_hms_opt(0, 30, 0).unwrap());
+        let earlier = between(canonical("2024-03-01", Morning), Morning, late, Morning);
+        let later = between(early, Afternoon, canonical("2024-03-06", Morning), Morning);
+
+        assert_eq!(earlier.last_date(), later.first_date());
+        assert!(earlier.compatible_with(&later));
+        assert!(!between(early, Morning, late, Afternoon).compatible_with(&later));
+    }
+
+    fn site(id: u32) -> Site {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": format!("Site {id}"),
+            "coordinates": { "latitude": 48.1, "longitude": -1.6 },
+            "client": { "name": "Acme", "phone": "+33 2 99 00 00 00" },
+            "start_day": 0,
+            "duration": { "half_day": 2 },
+            "status": SiteStatus::NotCarried,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn readers_do_not_block_each_other() {
+        let sites = Sites::default();
+        sites.write().insert(SiteId(1), site(1));
+        let held = sites.read();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let other = sites.clone();
+        std::thread::spawn(move || sender.send(other.read().len()).unwrap());
+        let read = receiver.recv_timeout(std::time::Duration::from_secs(5));
+
+        assert_eq!(read, Ok(1));
+        drop(held);
+    }
+
+    #[test]
+    fn concurrent_readers_and_a_writer_all_complete() {
+        let sites = Sites::default();
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let sites = sites.clone();
+                std::thread::spawn(move || (0..500).map(|_| sites.read().len()).max().unwrap_or(0))
+            })
+            .collect();
+        let writer = {
+            let sites = sites.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    let mut sites = sites.write();
+                    let id = Sites::next_id(&sites);
+                    sites.insert(id, site(id.0));
+                }
+            })
+        };
+
+        writer.join().unwrap();
+        for reader in readers {
+            assert!(reader.join().unwrap() <= 100);
+        }
+        assert_eq!(sites.read().len(), 100);
+    }
+
+    #[test]
+    fn reservations_are_ordered_by_start_then_end() {
+        let mut reservations = [
+            reservation("2024-03-05", Morning, "2024-03-05", Morning),
+            reservation("2024-03-04", Afternoon, "2024-03-06", Morning),
+            reservation("2024-03-04", Afternoon, "2024-03-04", Afternoon),
+            reservation("2024-03-04", Morning, "2024-03-08", Morning),
+        ];
+        reservations.sort();
+
+        let starts: Vec<_> = reservations
+            .iter()
+            .map(|reserved| {
+                (
+                    reserved.first_date(),
+                    reserved.start_period,
+                    reserved.last_date(),
+                )
+            })
+            .collect();
+        let day = |date| parse_date(date).unwrap();
+        assert_eq!(
+            starts,
+            [
+                (day("2024-03-04"), Morning, day("2024-03-08")),
+                (day("2024-03-04"), Afternoon, day("2024-03-04")),
+                (day("2024-03-04"), Afternoon, day("2024-03-06")),
+                (day("2024-03-05"), Morning, day("2024-03-05")),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_days_yield_four_half_days() {
+        let monday = parse_date("2024-03-04").unwrap();
+        let tuesday = parse_date("2024-03-05").unwrap();
+
+        let slots: Vec<_> = HalfDayIter::days(monday, tuesday).collect();
+
+        assert_eq!(
+            slots,
+            [
+                (monday, Morning),
+                (monday, Afternoon),
+                (tuesday, Morning),
+                (tuesday, Afternoon),
+            ]
+        );
+    }
+
+    #[test]
+    fn working_half_days_skip_the_weekend() {
+        let friday = parse_date("2024-03-01").unwrap();
+        let monday = parse_date("2024-03-04").unwrap();
+        let calendar = Calendar::default();
+
+        let slots: Vec<_> = HalfDayIter::days(friday, monday)
+            .working(&calendar)
+            .collect();
+
+        assert_eq!(
+            slots,
+            [
+                (friday, Morning),
+                (friday, Afternoon),
+                (monday, Morning),
+                (monday, Afternoon),
+            ]
+        );
+    }
+
+    fn at(date: &str, hour: u32, minute: u32) -> DateTime<Utc> {
+        let date = parse_date(date).unwrap();
+        Utc.from_utc_datetime(&date.and_hms_opt(hour, minute, 0).unwrap())
+    }
+
+    /// The half-days [`ReservedDate::from_instants`] snaps `start` and `end` to.
+    fn snapped(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> ((NaiveDate, DayPeriod), (NaiveDate, DayPeriod)) {
+        let reserved =
+            ReservedDate::from_instants(start, end, &WorkHours::default(), &Calendar::default())
+                .unwrap();
+        (
+            (reserved.first_date(), reserved.start_period),
+            (reserved.last_date(), reserved.end_period),
+        )
+    }
+
+    #[test]
+    fn starts_round_down_to_the_half_day_they_fall_in() {
+        let end = at("2024-03-06", 17, 0);
+        let day = |date| parse_date(date).unwrap();
+
+        assert_eq!(
+            snapped(at("2024-03-04", 10, 30), end).0,
+            (day("2024-03-04"), Morning)
+        );
+        assert_eq!(
+            snapped(at("2024-03-04", 11, 59), end).0,
+            (day("2024-03-04"), Morning)
+        );
+        // The morning ends at 12:00 with the default work hours.
+        assert_eq!(
+            snapped(at("2024-03-04", 12, 0), end).0,
+            (day("2024-03-04"), Afternoon)
+        );
+        assert_eq!(
+            snapped(at("2024-03-04", 16, 59), end).0,
+            (day("2024-03-04"), Afternoon)
+        );
+        assert_eq!(
+            snapped(at("2024-03-04", 17, 0), end).0,
+            (day("2024-03-05"), Morning)
+        );
+    }
+
+    #[test]
+    fn ends_round_up_to_the_half_day_they_fall_in() {
+        let start = at("2024-03-04", 8, 0);
+        let day = |date| parse_date(date).unwrap();
+
+        assert_eq!(
+            snapped(start, at("2024-03-06", 8, 0)).1,
+            (day("2024-03-05"), Afternoon)
+        );
+        assert_eq!(
+            snapped(start, at("2024-03-06", 8, 1)).1,
+            (day("2024-03-06"), Morning)
+        );
+        // The afternoon starts at 13:00 with the default work hours.
+        assert_eq!(
+            snapped(start, at("2024-03-06", 13, 0)).1,
+            (day("2024-03-06"), Morning)
+        );
+        assert_eq!(
+            snapped(start, at("2024-03-06", 14, 0)).1,
+            (day("2024-03-06"), Afternoon)
+        );
+    }
+
+    #[test]
+    fn instants_within_the_same_off_hours_are_refused() {
+        let evening = at("2024-03-04", 18, 0);
+        let night = at("2024-03-04", 23, 0);
+
+        assert_eq!(
+            ReservedDate::from_instants(
+                evening,
+                night,
+                &WorkHours::default(),
+                &Calendar::default()
+            ),
+            Err(DateParsedErr::StartAfterEnd.into())
+        );
+    }
+
+    #[test]
+    fn durations_add_up_the_work_hours_of_their_half_days() {
+        let hours = |hour| chrono::NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let short_afternoons = WorkHours {
+            afternoon_end: hours(16),
+            ..WorkHours::default()
+        };
+        let duration = |half_day| SiteDuration { half_day };
+
+        assert_eq!(
+            duration(0).to_chrono_duration(&short_afternoons),
+            TimeDelta::zero()
+        );
+        assert_eq!(
+            duration(1).to_chrono_duration(&short_afternoons),
+            TimeDelta::hours(4)
+        );
+        // Morning, afternoon, then morning again.
+        assert_eq!(
+            duration(3).to_chrono_duration(&short_afternoons),
+            TimeDelta::hours(11)
+        );
+        assert_eq!(
+            duration(4).to_chrono_duration(&WorkHours::default()),
+            TimeDelta::hours(16)
+        );
+        assert_eq!(duration(3).as_working_days(), 1.5);
+    }
+
+    #[test]
+    fn working_periods_skip_weekends_and_holidays() {
+        // Day 4 of the default calendar is Friday 2024-01-05.
+        let mut site = site(1);
+        site.start_day = DayIndex(4);
+        site.duration = SiteDuration { half_day: 3 };
+        let mut calendar = Calendar::default();
+        let day = |date| parse_date(date).unwrap();
+
+        assert_eq!(
+            site.working_periods(&calendar),
+            [
+                (day("2024-01-05"), Morning),
+                (day("2024-01-05"), Afternoon),
+                (day("2024-01-08"), Morning),
+            ]
+        );
+
+        calendar.holidays.push(day("2024-01-08"));
+        assert_eq!(
+            site.working_periods(&calendar),
+            [
+                (day("2024-01-05"), Morning),
+                (day("2024-01-05"), Afternoon),
+                (day("2024-01-09"), Morning),
+            ]
+        );
+    }
+
+    #[test]
+    fn periods_advance_to_the_opposite_one() {
+        assert_eq!(Morning.opposite(), Afternoon);
+        assert_eq!(Afternoon.opposite(), Morning);
+
+        assert_eq!(Morning.advance(), (0, Afternoon));
+        assert_eq!(Afternoon.advance(), (1, Morning));
+        for period in [Morning, Afternoon] {
+            assert_eq!(period.advance().1, period.opposite());
+        }
+    }
+
+    #[test]
+    fn sites_sharing_a_vehicle_report_their_overlapping_reservations() {
+        let mut truck = Vehicle::new(
+            VehicleId(1),
+            "Truck 1".to_owned(),
+            crate::fleet::VehicleKind::Truck,
+        );
+        // Pushed as is, as reservations are only checked on the way in.
+        let first = reservation("2024-03-04", Morning, "2024-03-05", Afternoon).for_site(SiteId(1));
+        let second = reservation("2024-03-05", Morning, "2024-03-06", Morning).for_site(SiteId(2));
+        let later = reservation("2024-03-07", Morning, "2024-03-07", Afternoon).for_site(SiteId(2));
+        truck.reserved_dates = vec![first.clone(), second.clone(), later];
+        let fleet = HashMap::from([(truck.id, truck)]);
+
+        for id in [1, 2] {
+            let mut site = site(id);
+            site.resources.vehicles.push(VehicleId(1));
+            assert_eq!(
+                site.resources.all_conflicts(&fleet),
+                [(VehicleId(1), first.clone(), second.clone())]
+            );
+        }
+        assert_eq!(site(3).resources.all_conflicts(&fleet), []);
+    }
+
+    #[test]
+    fn instants_on_days_off_snap_to_the_nearest_working_days() {
+        let mut calendar = Calendar::default();
+        // Friday 2024-03-08, then the weekend and a holiday on Monday.
+        calendar.holidays.push(parse_date("2024-03-11").unwrap());
+        let snap = |start, end| snap_to_half_day(start, end, &WorkHours::default(), &calendar);
+        let day = |date| parse_date(date).unwrap();
+
+        let (first, last) = snap(at("2024-03-09", 10, 0), at("2024-03-13", 17, 0));
+        assert_eq!(first, (day("2024-03-12"), Morning));
+        assert_eq!(last, (day("2024-03-13"), Afternoon));
+
+        // A Friday evening start goes past the weekend and the holiday.
+        let (first, _) = snap(at("2024-03-08", 18, 0), at("2024-03-13", 17, 0));
+        assert_eq!(first, (day("2024-03-12"), Morning));
+
+        let (_, last) = snap(at("2024-03-06", 8, 0), at("2024-03-11", 10, 0));
+        assert_eq!(last, (day("2024-03-08"), Afternoon));
+    }
+}
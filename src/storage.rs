@@ -0,0 +1,49 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Encoding of data at rest. The API always speaks JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageFormat {
+    #[default]
+    Json,
+    /// Compact binary encoding, keeping field names so optional fields can be left out.
+    MessagePack,
+}
+
+#[derive(Debug)]
+pub enum StorageErr {
+    Json(serde_json::Error),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+}
+
+impl fmt::Display for StorageErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageErr::Json(err) => write!(f, "Invalid JSON data: {err}"),
+            StorageErr::Encode(err) => write!(f, "Could not encode to MessagePack: {err}"),
+            StorageErr::Decode(err) => write!(f, "Invalid MessagePack data: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageErr {}
+
+impl StorageFormat {
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, StorageErr> {
+        match self {
+            StorageFormat::Json => serde_json::to_vec(value).map_err(StorageErr::Json),
+            StorageFormat::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(StorageErr::Encode)
+            }
+        }
+    }
+
+    pub fn decode<T: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<T, StorageErr> {
+        match self {
+            StorageFormat::Json => serde_json::from_slice(bytes).map_err(StorageErr::Json),
+            StorageFormat::MessagePack => rmp_serde::from_slice(bytes).map_err(StorageErr::Decode),
+        }
+    }
+}
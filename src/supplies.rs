@@ -0,0 +1,55 @@
+use std::fmt;
+use std::num::ParseIntError;
+
+use rocket::request::FromParam;
+use serde::{Deserialize, Serialize};
+
+/// Identifier of a tool within its site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ToolId(pub u32);
+
+impl fmt::Display for ToolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for ToolId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(ToolId)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tool {
+    pub id: ToolId,
+    pub name: String,
+    pub quantity: u32,
+}
+
+/// Identifier of a material within its site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MaterialId(pub u32);
+
+impl fmt::Display for MaterialId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for MaterialId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(MaterialId)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Material {
+    pub id: MaterialId,
+    pub name: String,
+    pub quantity: u32,
+}
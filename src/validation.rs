@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::calendar::DayIndex;
+use crate::fleet::{Vehicle, VehicleId};
+use crate::phone::PhoneNumber;
+use crate::sites::{BoundingBox, Coordinates, Site, SiteDuration, SiteId, Sites};
+
+/// A problem with one field of a submitted resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Every problem with a site's user-provided fields, empty when it is valid.
+pub fn site_validation(site: &Site) -> Vec<FieldError> {
+    let mut errors = fields_validation(&site.name, &site.coordinates, &site.duration);
+    errors.extend(client_validation(
+        &site.client.name,
+        site.client.phone.as_str(),
+    ));
+    for (index, contact) in site.client.contacts.iter().enumerate() {
+        errors.extend(contact_validation(
+            &format!("client.contacts.{index}"),
+            &contact.name,
+            contact.phone.as_str(),
+        ));
+    }
+    errors
+}
+
+/// Every broken invariant of a stored site. They are enforced on the way in, but sites stored
+/// by older versions may still break them.
+pub fn integrity_validation(site: &Site) -> Vec<FieldError> {
+    let mut errors = site_validation(site);
+
+    let mut workers = HashSet::new();
+    for (index, worker) in site.workers.iter().enumerate() {
+        if !workers.insert(worker) {
+            errors.push(FieldError::new(
+                format!("workers.{index}"),
+                format!("worker {worker} is assigned twice"),
+            ));
+        }
+    }
+
+    let mut vehicles = HashSet::new();
+    for (index, vehicle) in site.resources.vehicles.iter().enumerate() {
+        if !vehicles.insert(vehicle) {
+            errors.push(FieldError::new(
+                format!("resources.vehicles.{index}"),
+                format!("vehicle {vehicle} is listed twice"),
+            ));
+        }
+    }
+    if site.depends_on.contains(&site.id) {
+        errors.push(FieldError::new(
+            "depends_on",
+            "must not contain the site itself",
+        ));
+    }
+
+    for (index, pair) in site.status_history.windows(2).enumerate() {
+        let (previous, record) = (&pair[0], &pair[1]);
+        if record.at < previous.at {
+            errors.push(FieldError::new(
+                format!("status_history.{}.at", index + 1),
+                "is before the previous record",
+            ));
+        }
+        if !previous.status.can_transition_to(record.status) {
+            errors.push(FieldError::new(
+                format!("status_history.{}.status", index + 1),
+                format!("{:?} cannot follow {:?}", record.status, previous.status),
+            ));
+        }
+    }
+    if let Some(last) = site.status_history.last() {
+        if last.status != site.status {
+            errors.push(FieldError::new(
+                "status",
+                format!(
+                    "is {:?} but the last recorded status is {:?}",
+                    site.status, last.status
+                ),
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Reservations of the site's vehicles ending before they start, and pairs of reservations
+/// of a same vehicle that intersect.
+pub fn reservations_validation(
+    site: &Site,
+    fleet: &HashMap<VehicleId, Vehicle>,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for vehicle in site
+        .resources
+        .vehicles
+        .iter()
+        .filter_map(|id| fleet.get(id))
+    {
+        for (index, reserved) in vehicle.reserved_dates.iter().enumerate() {
+            let (start, end) = reserved.half_day_span();
+            if reserved.site_id == Some(site.id) && end < start {
+                errors.push(FieldError::new(
+                    format!("vehicles.{}.reserved_dates.{index}", vehicle.id),
+                    "ends before it starts",
+                ));
+            }
+        }
+    }
+    for (vehicle_id, first, second) in site.resources.all_conflicts(fleet) {
+        errors.push(FieldError::new(
+            format!("vehicles.{vehicle_id}.reserved_dates"),
+            format!(
+                "reservation from {} to {} intersects the one from {} to {}",
+                first.first_date(),
+                first.last_date(),
+                second.first_date(),
+                second.last_date()
+            ),
+        ));
+    }
+    errors
+}
+
+/// Problems with the plain fields of a site.
+pub fn fields_validation(
+    name: &str,
+    coordinates: &Coordinates,
+    duration: &SiteDuration,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    }
+    if duration.half_day == 0 {
+        errors.push(FieldError::new(
+            "duration.half_day",
+            "must last at least one half-day",
+        ));
+    }
+    errors.extend(coordinates_validation(coordinates));
+
+    errors
+}
+
+/// Problems with when a site is planned: it must start by day `horizon_days` and, working
+/// every day, end by it too.
+pub fn horizon_validation(
+    start_day: DayIndex,
+    duration: &SiteDuration,
+    horizon_days: usize,
+) -> Vec<FieldError> {
+    let message = format!("must keep the site within {horizon_days} days of the project start");
+    if start_day.0 > horizon_days {
+        return vec![FieldError::new("start_day", message)];
+    }
+    let working_days = duration.half_day.div_ceil(2);
+    if start_day.0.saturating_add(working_days) > horizon_days {
+        return vec![FieldError::new("duration.half_day", message)];
+    }
+    Vec::new()
+}
+
+pub fn coordinates_validation(coordinates: &Coordinates) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if !(-90.0..=90.0).contains(&coordinates.latitude) {
+        errors.push(FieldError::new(
+            "coordinates.latitude",
+            "must be between -90 and 90",
+        ));
+    }
+    if !(-180.0..=180.0).contains(&coordinates.longitude) {
+        errors.push(FieldError::new(
+            "coordinates.longitude",
+            "must be between -180 and 180",
+        ));
+    }
+    errors
+}
+
+/// Bounds of a map viewport. Boxes crossing the antimeridian, whose minimum longitude would be
+/// greater than their maximum, are not supported.
+pub fn bounds_validation(bounds: &BoundingBox) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (field, latitude) in [
+        ("min_lat", bounds.min_latitude),
+        ("max_lat", bounds.max_latitude),
+    ] {
+        if !(-90.0..=90.0).contains(&latitude) {
+            errors.push(FieldError::new(field, "must be between -90 and 90"));
+        }
+    }
+    for (field, longitude) in [
+        ("min_lon", bounds.min_longitude),
+        ("max_lon", bounds.max_longitude),
+    ] {
+        if !(-180.0..=180.0).contains(&longitude) {
+            errors.push(FieldError::new(field, "must be between -180 and 180"));
+        }
+    }
+    if bounds.min_latitude >= bounds.max_latitude {
+        errors.push(FieldError::new("min_lat", "must be lower than max_lat"));
+    }
+    if bounds.min_longitude >= bounds.max_longitude {
+        errors.push(FieldError::new(
+            "min_lon",
+            "must be lower than max_lon, boxes crossing the antimeridian are not supported",
+        ));
+    }
+    errors
+}
+
+pub fn client_validation(name: &str, phone: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("client.name", "must not be empty"));
+    }
+    if let Err(err) = phone.parse::<PhoneNumber>() {
+        errors.push(FieldError::new("client.phone", err.to_string()));
+    }
+    errors
+}
+
+/// Problems with a client contact, reported under `field`, e.g. `client.contacts.0`.
+pub fn contact_validation(field: &str, name: &str, phone: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if name.trim().is_empty() {
+        errors.push(FieldError::new(
+            format!("{field}.name"),
+            "must not be empty",
+        ));
+    }
+    if let Err(err) = phone.parse::<PhoneNumber>() {
+        errors.push(FieldError::new(format!("{field}.phone"), err.to_string()));
+    }
+    errors
+}
+
+/// Problems with the dependencies of site `id`: unknown sites, or a dependency cycle.
+pub fn dependencies_validation(
+    sites: &HashMap<SiteId, Site>,
+    id: SiteId,
+    depends_on: &[SiteId],
+) -> Vec<FieldError> {
+    let mut errors: Vec<FieldError> = depends_on
+        .iter()
+        .filter(|dependency| **dependency != id && !sites.contains_key(dependency))
+        .map(|dependency| {
+            FieldError::new("depends_on", format!("site {dependency} does not exist"))
+        })
+        .collect();
+    if Sites::creates_dependency_cycle(sites, id, depends_on) {
+        errors.push(FieldError::new(
+            "depends_on",
+            "would create a dependency cycle",
+        ));
+    }
+    errors
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Method;
+use rocket::{Orbit, Request, Response, Rocket};
+use serde::Serialize;
+
+use crate::clock::SharedClock;
+use crate::fleet::{Fleet, Vehicle, VehicleId};
+use crate::sites::{ReservedDate, Site, SiteId, SiteStatus, Sites};
+use crate::workers::WorkerId;
+
+/// The parts of a site worth comparing over time, see [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SiteSnapshot {
+    pub name: String,
+    pub status: SiteStatus,
+    pub workers: Vec<WorkerId>,
+    pub vehicles: Vec<VehicleId>,
+    /// Reservations made for the site, which the fleet keeps rather than the site.
+    pub reservations: Vec<(VehicleId, ReservedDate)>,
+}
+
+impl SiteSnapshot {
+    /// Adds the reservations `vehicles` hold for the site `id`.
+    pub fn with_reservations(mut self, id: SiteId, vehicles: &HashMap<VehicleId, Vehicle>) -> Self {
+        self.reservations = vehicles
+            .values()
+            .flat_map(|vehicle| {
+                vehicle
+                    .reserved_dates
+                    .iter()
+                    .filter(|reserved| reserved.site_id == Some(id))
+                    .map(|reserved| (vehicle.id, reserved.clone()))
+            })
+            .collect();
+        self.reservations
+            .sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum FieldChange {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Status {
+        from: SiteStatus,
+        to: SiteStatus,
+    },
+    WorkerAdded {
+        worker_id: WorkerId,
+    },
+    WorkerRemoved {
+        worker_id: WorkerId,
+    },
+    VehicleAttached {
+        vehicle_id: VehicleId,
+    },
+    VehicleDetached {
+        vehicle_id: VehicleId,
+    },
+    ReservationAdded {
+        vehicle_id: VehicleId,
+        reservation: ReservedDate,
+    },
+    ReservationRemoved {
+        vehicle_id: VehicleId,
+        reservation: ReservedDate,
+    },
+}
+
+/// Items of `after` missing from `before`, then items of `before` missing from `after`.
+fn added_removed<'a, T: PartialEq>(before: &'a [T], after: &'a [T]) -> (Vec<&'a T>, Vec<&'a T>) {
+    let added = after.iter().filter(|item| !before.contains(item)).collect();
+    let removed = before.iter().filter(|item| !after.contains(item)).collect();
+    (added, removed)
+}
+
+/// What changed from `a` to `b`. Additions come before removals for each field.
+pub fn diff(a: &SiteSnapshot, b: &SiteSnapshot) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    if a.name != b.name {
+        changes.push(FieldChange::Renamed {
+            from: a.name.clone(),
+            to: b.name.clone(),
+        });
+    }
+    if a.status != b.status {
+        changes.push(FieldChange::Status {
+            from: a.status,
+            to: b.status,
+        });
+    }
+
+    let (added, removed) = added_removed(&a.workers, &b.workers);
+    changes.extend(added.into_iter().map(|worker_id| FieldChange::WorkerAdded {
+        worker_id: *worker_id,
+    }));
+    changes.extend(
+        removed
+            .into_iter()
+            .map(|worker_id| FieldChange::WorkerRemoved {
+                worker_id: *worker_id,
+            }),
+    );
+
+    let (attached, detached) = added_removed(&a.vehicles, &b.vehicles);
+    changes.extend(
+        attached
+            .into_iter()
+            .map(|vehicle_id| FieldChange::VehicleAttached {
+                vehicle_id: *vehicle_id,
+            }),
+    );
+    changes.extend(
+        detached
+            .into_iter()
+            .map(|vehicle_id| FieldChange::VehicleDetached {
+                vehicle_id: *vehicle_id,
+            }),
+    );
+
+    let (added, removed) = added_removed(&a.reservations, &b.reservations);
+    changes.extend(added.into_iter().map(|(vehicle_id, reservation)| {
+        FieldChange::ReservationAdded {
+            vehicle_id: *vehicle_id,
+            reservation: reservation.clone(),
+        }
+    }));
+    changes.extend(removed.into_iter().map(|(vehicle_id, reservation)| {
+        FieldChange::ReservationRemoved {
+            vehicle_id: *vehicle_id,
+            reservation: reservation.clone(),
+        }
+    }));
+    changes
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SiteVersion {
+    /// Counted from 1 for each site.
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    pub snapshot: SiteSnapshot,
+}
+
+/// Snapshots of every site, a new version being stored each time a site changes.
+#[derive(Debug, Clone, Default)]
+pub struct SiteVersions(Arc<Mutex<HashMap<SiteId, Vec<SiteVersion>>>>);
+
+impl SiteVersions {
+    /// Locks the versions. When the sites or the fleet are needed too, lock them first.
+    pub fn lock(&self) -> MutexGuard<'_, HashMap<SiteId, Vec<SiteVersion>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Stores a version of each site that differs from its latest stored one.
+    pub fn record_changes(
+        &self,
+        sites: &HashMap<SiteId, Site>,
+        vehicles: &HashMap<VehicleId, Vehicle>,
+        at: DateTime<Utc>,
+    ) {
+        let mut versions = self.lock();
+        for site in sites.values() {
+            let snapshot = site.snapshot().with_reservations(site.id, vehicles);
+            let history = versions.entry(site.id).or_default();
+            if history.last().is_some_and(|last| last.snapshot == snapshot) {
+                continue;
+            }
+            history.push(SiteVersion {
+                version: history.len() as u32 + 1,
+                taken_at: at,
+                snapshot,
+            });
+        }
+    }
+}
+
+/// Records the sites' versions at liftoff, then after every successful request that may have
+/// changed them, i.e. any but `GET` and `HEAD`.
+pub struct SiteVersioning;
+
+impl SiteVersioning {
+    fn record(rocket: &Rocket<Orbit>) {
+        if let (Some(sites), Some(fleet), Some(versions), Some(clock)) = (
+            rocket.state::<Sites>(),
+            rocket.state::<Fleet>(),
+            rocket.state::<SiteVersions>(),
+            rocket.state::<SharedClock>(),
+        ) {
+            let sites = sites.read();
+            let vehicles = fleet.lock();
+            versions.record_changes(&sites, &vehicles, clock.now());
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for SiteVersioning {
+    fn info(&self) -> Info {
+        Info {
+            name: "Site versioning",
+            kind: Kind::Liftoff | Kind::Response,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        SiteVersioning::record(rocket);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let read_only = matches!(request.method(), Method::Get | Method::Head);
+        if !read_only && response.status().class().is_success() {
+            SiteVersioning::record(request.rocket());
+        }
+    }
+}
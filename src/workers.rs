@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use rocket::request::FromParam;
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct WorkerId(pub u32);
+
+impl fmt::Display for WorkerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for WorkerId {
+    type Error = ParseIntError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        param.parse().map(WorkerId)
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Trade {
+    /// Workers stored before trades existed.
+    #[default]
+    Laborer,
+    Mason,
+    Carpenter,
+    Electrician,
+    Plumber,
+    Roofer,
+    Painter,
+    Operator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worker {
+    pub id: WorkerId,
+    pub name: String,
+    #[serde(default)]
+    pub trade: Trade,
+    #[serde(default)]
+    pub certifications: Vec<String>,
+}
+
+/// Registry of every worker, sites only keeping the ids of the workers assigned to them.
+#[derive(Debug, Clone, Default)]
+pub struct Workers(Arc<Mutex<HashMap<WorkerId, Worker>>>);
+
+impl Workers {
+    /// Locks the registry. When the sites or the fleet are needed too, lock them first.
+    pub fn lock(&self) -> MutexGuard<'_, HashMap<WorkerId, Worker>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn next_id(workers: &HashMap<WorkerId, Worker>) -> WorkerId {
+        WorkerId(workers.keys().map(|id| id.0 + 1).max().unwrap_or(1))
+    }
+
+    /// Details of the workers with `ids`, in order, unknown ones left out.
+    pub fn resolve(workers: &HashMap<WorkerId, Worker>, ids: &[WorkerId]) -> Vec<Worker> {
+        ids.iter()
+            .filter_map(|id| workers.get(id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A worker as sites stored it: its id, or the whole worker before the [`Workers`] registry
+/// existed.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredWorker {
+    Id(WorkerId),
+    Embedded(Worker),
+}
+
+/// Worker ids of a site, also accepting the whole workers sites were stored with before the
+/// [`Workers`] registry existed. Those are read apart with [`EmbeddedWorkers`], to be moved into
+/// the registry.
+pub fn deserialize_worker_ids<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<WorkerId>, D::Error> {
+    let stored = Vec::<StoredWorker>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|worker| match worker {
+            StoredWorker::Id(id) => id,
+            StoredWorker::Embedded(worker) => worker.id,
+        })
+        .collect())
+}
+
+/// The whole workers a stored site embeds, its other fields ignored.
+#[derive(Debug, Default, Deserialize)]
+pub struct EmbeddedWorkers {
+    #[serde(default, deserialize_with = "deserialize_embedded_workers")]
+    pub workers: Vec<Worker>,
+}
+
+fn deserialize_embedded_workers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Worker>, D::Error> {
+    let stored = Vec::<StoredWorker>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .filter_map(|worker| match worker {
+            StoredWorker::Id(_) => None,
+            StoredWorker::Embedded(worker) => Some(worker),
+        })
+        .collect())
+}
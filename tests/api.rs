@@ -0,0 +1,352 @@
+//! Behaviour shared by every route: error envelopes, CSRF checks, content negotiation and
+//! the informational endpoints.
+
+mod common;
+
+use rocket::http::{Accept, ContentType, Cookie, Header, Method, Status};
+use serde_json::{json, Value};
+
+use mmm_construction_site_manager_back::auth::USER_COOKIE;
+
+use common::*;
+
+fn json_body(body: Option<String>) -> Value {
+    serde_json::from_str(&body.expect("a body")).expect("JSON answer")
+}
+
+#[test]
+fn errors_are_answered_with_the_json_envelope() {
+    let client = client();
+
+    let response = client.get("/sites").dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let error = json_body(response.into_string());
+    assert_eq!(error["errors"][0]["status"], "401");
+    assert_eq!(error["errors"][0]["code"], "unauthorized");
+    assert_eq!(error["errors"][0]["detail"], "Log in to GET /sites");
+
+    let (status, error) = get(&client, "/nowhere", &global_manager());
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(error["errors"][0]["detail"], "Nothing at GET /nowhere");
+
+    let (status, error) = get(&client, "/sites/abc", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(error["errors"][0]["code"], "unprocessable");
+
+    let (status, error) = post(
+        &client,
+        "/sites",
+        &site_manager("sam"),
+        new_site("Depot", 0, 4),
+    );
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(error["errors"][0]["code"], "forbidden");
+}
+
+#[test]
+fn unmatched_and_unparsable_requests_are_caught_in_the_envelope() {
+    let client = client();
+    let response = request(&client, Method::Get, "/sites/abc", &global_manager()).dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let error = json_body(response.into_string());
+    assert_eq!(
+        error["errors"][0]["detail"],
+        "The path or body of GET /sites/abc could not be parsed"
+    );
+
+    let response = request(&client, Method::Post, "/sites", &global_manager())
+        .header(ContentType::JSON)
+        .body(r#"{ "name": "Depot" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let error = json_body(response.into_string());
+    assert_eq!(
+        error["errors"][0]["detail"],
+        "The path or body of POST /sites could not be parsed"
+    );
+
+    let response = request(&client, Method::Get, "/nowhere", &global_manager())
+        .header(Header::new("Accept-Language", "fr"))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let error = json_body(response.into_string());
+    assert_eq!(error["errors"][0]["title"], "Introuvable");
+}
+
+#[test]
+fn changes_without_the_csrf_header_are_refused() {
+    let client = client();
+    let user = serde_json::to_string(&global_manager()).unwrap();
+
+    let response = client
+        .post("/sites")
+        .private_cookie(Cookie::new(USER_COOKIE, user))
+        .header(ContentType::JSON)
+        .body(new_site("Depot", 0, 4).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    let error = json_body(response.into_string());
+    assert_eq!(error["errors"][0]["detail"], "Not allowed to POST /sites");
+
+    let (_, sites) = get(&client, "/sites", &global_manager());
+    assert_eq!(sites["items"], json!([]));
+}
+
+#[test]
+fn roles_are_changed_by_global_managers_only() {
+    let client = client();
+    let uri = "/role?role=SitesGlobalManager";
+    let (status, _) = send(&client, Method::Post, uri, &site_manager("sam"), None);
+    assert_eq!(status, Status::Forbidden);
+
+    let response = client
+        .post("/role?role=SiteManager")
+        .private_cookie(Cookie::new(
+            USER_COOKIE,
+            serde_json::to_string(&global_manager()).unwrap(),
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let (status, user) = send(
+        &client,
+        Method::Post,
+        "/role?role=SiteManager",
+        &global_manager(),
+        None,
+    );
+    assert_eq!(status, Status::Ok, "{user}");
+    assert_eq!(user["role"], "SiteManager");
+}
+
+#[test]
+fn a_granted_role_overrides_the_cookie_one() {
+    let client = client();
+    let uri = "/role?role=SitesGlobalManager&name=sam";
+    let (status, user) = send(&client, Method::Post, uri, &global_manager(), None);
+    assert_eq!(status, Status::Ok, "{user}");
+    assert_eq!(user, json!({ "name": "sam", "role": "SitesGlobalManager" }));
+
+    let (_, me) = get(&client, "/me", &site_manager("sam"));
+    assert_eq!(me["role"], "SitesGlobalManager");
+    let (status, _) = post(
+        &client,
+        "/sites",
+        &site_manager("sam"),
+        new_site("Depot", 0, 4),
+    );
+    assert_eq!(status, Status::Created);
+
+    let (_, me) = get(&client, "/me", &global_manager());
+    assert_eq!(me["role"], "SitesGlobalManager");
+}
+
+#[test]
+fn errors_are_translated_to_the_accepted_language() {
+    let client = client();
+    let response = request(&client, Method::Get, "/sites/42", &global_manager())
+        .header(Header::new("Accept-Language", "fr-FR, en;q=0.5"))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    let error = json_body(response.into_string());
+    assert_eq!(error["errors"][0]["title"], "Introuvable");
+
+    let (_, error) = get(&client, "/sites/42", &global_manager());
+    assert_eq!(error["errors"][0]["title"], "Not Found");
+}
+
+#[test]
+fn json_is_indented_only_when_asked() {
+    let (client, _clock) = client_with_clock();
+    let compact = request(&client, Method::Get, "/server-info", &global_manager())
+        .dispatch()
+        .into_string()
+        .unwrap();
+    assert!(!compact.contains('\n'));
+
+    let pretty = request(
+        &client,
+        Method::Get,
+        "/server-info?pretty=true",
+        &global_manager(),
+    )
+    .dispatch()
+    .into_string()
+    .unwrap();
+    assert!(pretty.contains("\n  "));
+    assert_eq!(json_body(Some(pretty)), json_body(Some(compact)));
+}
+
+#[test]
+fn server_info_gives_the_clock_time_in_utc() {
+    let (client, _clock) = client_with_clock();
+    let (status, info) = get(&client, "/server-info", &global_manager());
+    assert_eq!(status, Status::Ok);
+    assert_eq!(info["now"], "2024-03-04T09:00:00Z");
+    assert_eq!(info["timezone"], "UTC");
+}
+
+#[test]
+fn the_calendar_marks_working_days() {
+    let client = client();
+    let uri = "/calendar?from=2024-01-05&to=2024-01-08";
+    let (status, days) = get(&client, uri, &global_manager());
+    assert_eq!(status, Status::Ok, "{days}");
+    let working: Vec<(&Value, &Value)> = days
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|day| (&day["date"], &day["working"]))
+        .collect();
+    assert_eq!(
+        working,
+        [
+            (&json!("2024-01-05"), &json!(true)),
+            (&json!("2024-01-06"), &json!(false)),
+            (&json!("2024-01-07"), &json!(false)),
+            (&json!("2024-01-08"), &json!(true)),
+        ]
+    );
+
+    let (status, _) = get(
+        &client,
+        "/calendar?from=2024-01-08&to=2024-01-05",
+        &global_manager(),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn roles_list_their_permissions() {
+    let client = client();
+    let response = client.get("/roles").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let roles = json_body(response.into_string());
+    let manager = roles
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|role| role["role"] == "SiteManager")
+        .expect("the site manager role");
+    assert!(manager["permissions"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("SetStatus")));
+}
+
+#[test]
+fn the_index_greets_in_the_accepted_format() {
+    let client = client();
+    let greeting = request(&client, Method::Get, "/", &global_manager())
+        .header(Accept::Text)
+        .dispatch()
+        .into_string()
+        .unwrap();
+    assert_eq!(
+        greeting,
+        "Hello, gina! You are logged in as SitesGlobalManager."
+    );
+
+    let response = request(&client, Method::Get, "/", &global_manager())
+        .header(Accept::JSON)
+        .dispatch();
+    assert_eq!(json_body(response.into_string())["name"], "gina");
+
+    let response = request(&client, Method::Get, "/", &global_manager())
+        .header(Accept::HTML)
+        .dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+}
+
+#[test]
+fn anonymous_visitors_land_as_configured() {
+    let client = client();
+    let response = client.get("/").header(Accept::Text).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().unwrap().contains("/login/github"));
+
+    let (client, _) = client_with_settings(json!({ "anonymous_landing": "Redirect" }));
+    let response = client.get("/").header(Accept::HTML).dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert_eq!(
+        response.headers().get_one("Location"),
+        Some("/login/github")
+    );
+
+    let (client, _) = client_with_settings(json!({ "anonymous_landing": "Unauthorized" }));
+    let response = client.get("/").header(Accept::Text).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn anonymous_json_clients_are_always_unauthorized() {
+    for landing in ["Prompt", "Redirect"] {
+        let (client, _) = client_with_settings(json!({ "anonymous_landing": landing }));
+        let response = client.get("/").header(Accept::JSON).dispatch();
+        assert_eq!(response.status(), Status::Unauthorized, "{landing}");
+    }
+}
+
+#[test]
+fn the_github_login_url_is_answered_instead_of_redirected_to() {
+    let client = client();
+    let response = client.get("/login/github/url").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let state = response
+        .cookies()
+        .get_private("rocket_oauth2_state")
+        .map(|cookie| cookie.value().to_owned());
+    let body = json_body(response.into_string());
+
+    let url = body["url"].as_str().expect("a url");
+    assert!(
+        url.starts_with("https://github.com/login/oauth/authorize?"),
+        "{url}"
+    );
+    assert!(url.contains("scope=read%3Auser"), "{url}");
+    let state = state.expect("the OAuth state cookie");
+    assert!(url.contains(&format!("state={state}")), "{url}");
+}
+
+#[test]
+fn mutations_need_a_role_permitting_them() {
+    let client = client();
+    let gina = global_manager();
+    let sam = site_manager("sam");
+    let site = create_site(&client, "Depot", 0, 4);
+    add_manager(&client, site, &sam);
+
+    let refused = [
+        (&sam, "/sites".to_owned(), new_site("Other", 0, 2)),
+        (
+            &sam,
+            "/vehicles".to_owned(),
+            json!({ "name": "Truck 1", "kind": "Truck" }),
+        ),
+        (&sam, "/workers".to_owned(), json!({ "name": "Ada" })),
+        (
+            &gina,
+            format!("/sites/{site}/anomalies"),
+            json!({ "reason": "Flooded" }),
+        ),
+    ];
+    for (user, uri, body) in refused {
+        let (status, error) = post(&client, &uri, user, body);
+        assert_eq!(status, Status::Forbidden, "{} on {uri}: {error}", user.name);
+    }
+    let uri = format!("/sites/{site}/status");
+    let (status, _) = patch(&client, &uri, &gina, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Ok);
+    let (status, _) = post(
+        &client,
+        "/vehicles",
+        &gina,
+        json!({ "name": "Truck 1", "kind": "Truck" }),
+    );
+    assert_eq!(status, Status::Created);
+}
@@ -0,0 +1,133 @@
+//! Clients of the sites and their contacts, through the routes.
+
+mod common;
+
+use rocket::http::{Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use common::*;
+
+/// Creates a site of `client`, with the `phone` number, and returns its id.
+fn create_site_for(client: &Client, name: &str, phone: &str) -> u64 {
+    let mut site = new_site("Depot", 0, 2);
+    site["client"] = json!({ "name": name, "phone": phone });
+    let (status, site) = post(client, "/sites", &global_manager(), site);
+    assert_eq!(status, Status::Created, "{site}");
+    site["id"].as_u64().expect("site id")
+}
+
+#[test]
+fn clients_are_listed_with_their_sites_and_status_counts() {
+    let client = client();
+    let first = create_site_for(&client, "Acme", "+33 2 99 00 00 00");
+    let second = create_site_for(&client, "Acme", "+33 2 99 00 00 00");
+    let other = create_site_for(&client, "Zenith", "+33 2 99 11 11 11");
+    let sam = site_manager("sam");
+    add_manager(&client, second, &sam);
+    add_manager(&client, other, &sam);
+    let uri = format!("/sites/{second}/status");
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Ok);
+
+    let (status, clients) = get(&client, "/clients", &global_manager());
+    assert_eq!(status, Status::Ok, "{clients}");
+    let summary: Vec<_> = clients
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|listed| {
+            (
+                listed["client"]["name"].clone(),
+                listed["sites"].clone(),
+                listed["status_counts"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                json!("Acme"),
+                json!([first, second]),
+                json!({ "NotCarried": 1, "InProgress": 1 })
+            ),
+            (json!("Zenith"), json!([other]), json!({ "NotCarried": 1 })),
+        ]
+    );
+
+    let (_, clients) = get(&client, "/clients", &sam);
+    let sites: Vec<&Value> = clients
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|listed| &listed["sites"])
+        .collect();
+    assert_eq!(sites, [&json!([second]), &json!([other])]);
+}
+
+#[test]
+fn a_site_is_handed_over_to_another_client() {
+    let client = client();
+    let id = create_site_for(&client, "Acme", "+33 2 99 00 00 00");
+    let uri = format!("/sites/{id}/client");
+    let zenith = json!({ "name": "Zenith", "phone": "+33 2 99 11 11 11" });
+
+    let (status, site) = patch(&client, &uri, &global_manager(), zenith.clone());
+    assert_eq!(status, Status::Ok, "{site}");
+    assert_eq!(site["client"]["name"], "Zenith");
+    let (_, clients) = get(&client, "/clients", &global_manager());
+    assert_eq!(clients.as_array().unwrap().len(), 1);
+    assert_eq!(clients[0]["client"]["name"], "Zenith");
+
+    let invalid = json!({ "name": " ", "phone": "not a phone" });
+    let (status, _) = patch(&client, &uri, &global_manager(), invalid);
+    assert_eq!(status, Status::UnprocessableEntity);
+    let sam = site_manager("sam");
+    add_manager(&client, id, &sam);
+    let (status, _) = patch(&client, &uri, &sam, zenith);
+    assert_eq!(status, Status::Forbidden);
+}
+
+#[test]
+fn client_contacts_are_added_listed_and_removed() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_site_for(&client, "Acme", "+33 2 99 00 00 00");
+    let contacts = format!("/sites/{id}/client/contacts");
+
+    let foreman = json!({ "name": "Fred", "phone": "+33 6 12 34 56 78", "role": "foreman" });
+    let (status, listed) = post(&client, &contacts, &gina, foreman);
+    assert_eq!(status, Status::Created, "{listed}");
+    let accounting = json!({ "name": "Anna", "phone": "+33 6 98 76 54 32" });
+    let (_, listed) = post(&client, &contacts, &gina, accounting);
+    let names = |listed: &Value| -> Vec<String> {
+        listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|contact| contact["name"].as_str().unwrap().to_owned())
+            .collect()
+    };
+    assert_eq!(names(&listed), ["Fred", "Anna"]);
+    assert_eq!(listed[0]["role"], "foreman");
+
+    let invalid = json!({ "name": "Bob", "phone": "not a phone" });
+    let (status, error) = post(&client, &contacts, &gina, invalid);
+    assert_eq!(status, Status::UnprocessableEntity, "{error}");
+
+    let remove = format!("{contacts}/0");
+    let (status, listed) = send(&client, Method::Delete, &remove, &gina, None);
+    assert_eq!(status, Status::Ok, "{listed}");
+    assert_eq!(names(&listed), ["Anna"]);
+    let (_, listed) = get(&client, &contacts, &gina);
+    assert_eq!(names(&listed), ["Anna"]);
+    let (status, _) = send(
+        &client,
+        Method::Delete,
+        &format!("{contacts}/1"),
+        &gina,
+        None,
+    );
+    assert_eq!(status, Status::NotFound);
+}
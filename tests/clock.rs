@@ -0,0 +1,66 @@
+//! Time-dependent behaviour, driven by a [`MockClock`] the server reads the time from.
+
+mod common;
+
+use chrono::{Duration, SecondsFormat, Utc};
+use mmm_construction_site_manager_back::clock::Clock;
+use rocket::http::{Method, Status};
+use serde_json::json;
+
+use common::*;
+
+fn timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[test]
+fn anomalies_are_stamped_with_the_clock_time() {
+    let (client, clock) = client_with_clock();
+    let id = create_site(&client, "Depot", 0, 4);
+    let sam = site_manager("sam");
+    add_manager(&client, id, &sam);
+
+    let uri = format!("/sites/{id}/anomalies");
+    let (status, site) = post(&client, &uri, &sam, json!({ "reason": "Flooded trench" }));
+    assert_eq!(status, Status::Created, "{site}");
+    assert_eq!(site["anomalies"][0]["at"], timestamp(clock.now()));
+
+    clock.advance(Duration::hours(2));
+    let (_, site) = post(&client, &uri, &sam, json!({ "reason": "Broken fence" }));
+    assert_eq!(site["anomalies"][1]["at"], timestamp(clock.now()));
+}
+
+#[test]
+fn holds_stop_blocking_once_expired() {
+    let (client, clock) = client_with_clock();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 4);
+    let vehicle = create_vehicle(&client, "Truck 1", "Truck");
+    let uri = format!("/sites/{site}/vehicles/{vehicle}");
+    let (status, _) = send(&client, Method::Post, &uri, &gina, None);
+    assert_eq!(status, Status::Ok);
+
+    let hold = json!({
+        "site_id": site,
+        "start_date": "2024-03-11",
+        "start_period": "Morning",
+        "end_date": "2024-03-11",
+        "end_period": "Afternoon",
+        "ttl_minutes": 15,
+    });
+    let holds = format!("/vehicles/{vehicle}/holds");
+    let (status, first) = post(&client, &holds, &gina, hold.clone());
+    assert_eq!(status, Status::Created, "{first}");
+
+    clock.advance(Duration::minutes(10));
+    let (status, _) = post(&client, &holds, &gina, hold.clone());
+    assert_eq!(status, Status::Conflict);
+
+    clock.advance(Duration::minutes(5));
+    let confirm = format!("/vehicles/{vehicle}/holds/{}/confirm", first["data"]["id"]);
+    let (status, _) = send(&client, Method::Post, &confirm, &gina, None);
+    assert_eq!(status, Status::NotFound);
+    let (status, second) = post(&client, &holds, &gina, hold);
+    assert_eq!(status, Status::Created, "{second}");
+    assert_ne!(first["data"]["id"], second["data"]["id"]);
+}
@@ -0,0 +1,161 @@
+//! Helpers shared by the request tests: a local client and requests made as a logged-in user.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use chrono::{TimeZone, Utc};
+use rocket::http::{ContentType, Cookie, Header, Method, Status};
+use rocket::local::blocking::{Client, LocalRequest};
+use rocket::{Build, Rocket};
+use serde_json::{json, Value};
+
+use mmm_construction_site_manager_back::auth::{
+    AppRole, User, CSRF_COOKIE, CSRF_HEADER, USER_COOKIE,
+};
+use mmm_construction_site_manager_back::clock::MockClock;
+
+const CSRF_TOKEN: &str = "test-csrf-token";
+
+pub fn client() -> Client {
+    configured(mmm_construction_site_manager_back::rocket(), json!({}))
+}
+
+/// A client whose server reads the time from the returned clock, set to Monday 2024-03-04 9:00.
+pub fn client_with_clock() -> (Client, Arc<MockClock>) {
+    client_with_settings(json!({}))
+}
+
+/// Like [`client_with_clock`], with `settings`, e.g. `{ "turnaround_buffer_mins": 30 }`, on top
+/// of the configuration.
+pub fn client_with_settings(settings: Value) -> (Client, Arc<MockClock>) {
+    let clock = Arc::new(MockClock::new(
+        Utc.with_ymd_and_hms(2024, 3, 4, 9, 0, 0).unwrap(),
+    ));
+    let rocket = mmm_construction_site_manager_back::rocket_with_clock(clock.clone());
+    (configured(rocket, settings), clock)
+}
+
+/// A client of `rocket` with `settings` on top of its configuration. The overdue sites scan is
+/// off: it would flag sites from its own task while the test reads them.
+fn configured(rocket: Rocket<Build>, settings: Value) -> Client {
+    let mut figment = rocket
+        .figment()
+        .clone()
+        .merge(("overdue_check_interval_secs", 0));
+    for (key, value) in settings.as_object().expect("settings are an object") {
+        figment = figment.merge((key.as_str(), value));
+    }
+    Client::tracked(rocket.configure(figment)).expect("valid rocket")
+}
+
+pub fn global_manager() -> User {
+    User {
+        name: "gina".to_owned(),
+        role: AppRole::SitesGlobalManager,
+    }
+}
+
+pub fn global_manager_named(name: &str) -> User {
+    User {
+        name: name.to_owned(),
+        role: AppRole::SitesGlobalManager,
+    }
+}
+
+pub fn site_manager(name: &str) -> User {
+    User {
+        name: name.to_owned(),
+        role: AppRole::SiteManager,
+    }
+}
+
+/// A request logged in as `user`, passing the CSRF check.
+pub fn request<'c>(client: &'c Client, method: Method, uri: &str, user: &User) -> LocalRequest<'c> {
+    let user = serde_json::to_string(user).expect("users serialize");
+    client
+        .req(method, uri.to_owned())
+        .private_cookie(Cookie::new(USER_COOKIE, user))
+        .cookie(Cookie::new(CSRF_COOKIE, CSRF_TOKEN))
+        .header(Header::new(CSRF_HEADER, CSRF_TOKEN))
+}
+
+/// Sends `body` as `user` and returns the status and the JSON answer, `null` when empty.
+pub fn send(
+    client: &Client,
+    method: Method,
+    uri: &str,
+    user: &User,
+    body: Option<Value>,
+) -> (Status, Value) {
+    let mut request = request(client, method, uri, user);
+    if let Some(body) = body {
+        request = request.header(ContentType::JSON).body(body.to_string());
+    }
+    let response = request.dispatch();
+    let status = response.status();
+    let body = response
+        .into_string()
+        .filter(|body| !body.is_empty())
+        .map(|body| serde_json::from_str(&body).expect("JSON answer"))
+        .unwrap_or(Value::Null);
+    (status, body)
+}
+
+pub fn get(client: &Client, uri: &str, user: &User) -> (Status, Value) {
+    send(client, Method::Get, uri, user, None)
+}
+
+pub fn post(client: &Client, uri: &str, user: &User, body: Value) -> (Status, Value) {
+    send(client, Method::Post, uri, user, Some(body))
+}
+
+pub fn patch(client: &Client, uri: &str, user: &User, body: Value) -> (Status, Value) {
+    send(client, Method::Patch, uri, user, Some(body))
+}
+
+/// A new site body starting on `start_day` for `half_days`.
+pub fn new_site(name: &str, start_day: usize, half_days: usize) -> Value {
+    json!({
+        "name": name,
+        "coordinates": { "latitude": 48.1, "longitude": -1.6 },
+        "client": { "name": "Acme", "phone": "+33 2 99 00 00 00" },
+        "start_day": start_day,
+        "duration": { "half_day": half_days },
+    })
+}
+
+/// Creates a site as the global manager and returns its id.
+pub fn create_site(client: &Client, name: &str, start_day: usize, half_days: usize) -> u64 {
+    let (status, site) = post(
+        client,
+        "/sites",
+        &global_manager(),
+        new_site(name, start_day, half_days),
+    );
+    assert_eq!(status, Status::Created, "{site}");
+    site["id"].as_u64().expect("site id")
+}
+
+/// Creates a vehicle as the global manager and returns its id.
+pub fn create_vehicle(client: &Client, name: &str, kind: &str) -> u64 {
+    let (status, vehicle) = post(
+        client,
+        "/vehicles",
+        &global_manager(),
+        json!({ "name": name, "kind": kind }),
+    );
+    assert_eq!(status, Status::Created, "{vehicle}");
+    vehicle["id"].as_u64().expect("vehicle id")
+}
+
+/// Makes `user` a manager of site `id`.
+pub fn add_manager(client: &Client, id: u64, user: &User) {
+    let (status, body) = post(
+        client,
+        &format!("/sites/{id}/managers"),
+        &global_manager(),
+        json!({ "name": user.name }),
+    );
+    assert_eq!(status, Status::Ok, "{body}");
+}
@@ -0,0 +1,53 @@
+//! `POST /dev/seed` only exists under the `dev` feature, e.g. `cargo test --features dev`.
+
+mod common;
+
+use rocket::http::{Method, Status};
+
+use common::*;
+
+#[cfg(not(feature = "dev"))]
+#[test]
+fn the_seed_route_is_missing_without_the_dev_feature() {
+    let client = client();
+
+    let (status, _) = send(&client, Method::Post, "/dev/seed", &global_manager(), None);
+    assert_eq!(status, Status::NotFound);
+}
+
+#[cfg(feature = "dev")]
+mod enabled {
+    use super::*;
+
+    #[test]
+    fn seeding_fills_the_empty_registries_once() {
+        let client = client();
+        let gina = global_manager();
+
+        let (status, report) = send(&client, Method::Post, "/dev/seed", &gina, None);
+        assert_eq!(status, Status::Ok, "{report}");
+        let (_, sites) = get(&client, "/sites", &gina);
+        assert_eq!(
+            sites["items"].as_array().unwrap().len() as u64,
+            report["sites"]
+        );
+        let (_, vehicles) = get(&client, "/vehicles", &gina);
+        assert_eq!(
+            vehicles.as_array().unwrap().len() as u64,
+            report["vehicles"]
+        );
+        let (_, workers) = get(&client, "/workers", &gina);
+        assert_eq!(workers.as_array().unwrap().len() as u64, report["workers"]);
+
+        let (status, _) = send(&client, Method::Post, "/dev/seed", &gina, None);
+        assert_eq!(status, Status::Conflict);
+        let (status, _) = send(
+            &client,
+            Method::Post,
+            "/dev/seed",
+            &site_manager("sam"),
+            None,
+        );
+        assert_eq!(status, Status::Forbidden);
+    }
+}
@@ -0,0 +1,231 @@
+//! The state saved to `sites_path` on shutdown and loaded back on the next start.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use rocket::http::{Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use common::*;
+
+/// A path for the state of `test`, with no file left from an earlier run.
+fn state_path(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mmm-{test}-{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(path.with_extension("corrupt"));
+    path
+}
+
+fn client_saving_to(path: &PathBuf) -> Client {
+    client_with_settings(json!({ "sites_path": path })).0
+}
+
+fn items(client: &Client, uri: &str) -> Vec<Value> {
+    let (status, listed) = get(client, uri, &global_manager());
+    assert_eq!(status, Status::Ok, "{listed}");
+    match listed {
+        Value::Array(items) => items,
+        listed => listed["items"].as_array().expect("a page of items").clone(),
+    }
+}
+
+#[test]
+fn the_state_is_reloaded_after_a_restart() {
+    let path = state_path("reload");
+    let client = client_saving_to(&path);
+    let gina = global_manager();
+    create_site(&client, "Depot", 0, 4);
+    create_vehicle(&client, "Truck 1", "Truck");
+    let (status, _) = post(&client, "/workers", &gina, json!({ "name": "Ada" }));
+    assert_eq!(status, Status::Created);
+    let holiday = json!({
+        "start_date": "2024-01-02",
+        "start_period": "Morning",
+        "end_date": "2024-01-02",
+        "end_period": "Afternoon",
+    });
+    let (status, blackout) = post(&client, "/blackouts", &gina, holiday);
+    assert_eq!(status, Status::Created);
+    client.terminate();
+
+    let client = client_saving_to(&path);
+    assert_eq!(items(&client, "/sites")[0]["name"], "Depot");
+    assert_eq!(items(&client, "/vehicles")[0]["vehicle"]["name"], "Truck 1");
+    assert_eq!(items(&client, "/workers")[0]["name"], "Ada");
+    assert_eq!(items(&client, "/blackouts"), [blackout]);
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn a_file_of_sites_only_is_still_loaded() {
+    let path = state_path("legacy");
+    let client = client_saving_to(&path);
+    create_site(&client, "Depot", 0, 4);
+    client.terminate();
+    let state: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    fs::write(&path, state["sites"].to_string()).unwrap();
+
+    let client = client_saving_to(&path);
+    assert_eq!(items(&client, "/sites")[0]["name"], "Depot");
+    assert_eq!(items(&client, "/vehicles"), Vec::<Value>::new());
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn an_unreadable_file_is_moved_aside() {
+    let path = state_path("corrupt");
+    fs::write(&path, "not a state").unwrap();
+
+    let client = client_saving_to(&path);
+    assert_eq!(items(&client, "/sites"), Vec::<Value>::new());
+    let aside = path.with_extension("corrupt");
+    assert_eq!(fs::read_to_string(&aside).unwrap(), "not a state");
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&aside).unwrap();
+}
+
+#[test]
+fn the_state_round_trips_in_each_storage_format() {
+    for format in ["Json", "MessagePack"] {
+        let path = state_path(&format!("format-{format}"));
+        let settings = json!({ "sites_path": path, "storage_format": format });
+        let client = client_with_settings(settings.clone()).0;
+        let id = create_site(&client, "Depot", 0, 4);
+        let (_, site) = get(&client, &format!("/sites/{id}"), &global_manager());
+        client.terminate();
+
+        let stored = fs::read(&path).unwrap();
+        let is_json = serde_json::from_slice::<Value>(&stored).is_ok();
+        assert_eq!(is_json, format == "Json", "{format}");
+
+        let client = client_with_settings(settings).0;
+        let (_, reloaded) = get(&client, &format!("/sites/{id}"), &global_manager());
+        assert_eq!(reloaded, site, "{format}");
+        client.terminate();
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn stored_sites_breaking_invariants_are_kept_and_reported() {
+    let path = state_path("invalid");
+    let client = client_saving_to(&path);
+    let id = create_site(&client, "Depot", 0, 4);
+    let (status, report) = get(&client, &format!("/sites/{id}/validate"), &global_manager());
+    assert_eq!(status, Status::Ok);
+    assert_eq!(
+        report,
+        json!({ "site_id": id, "valid": true, "problems": [] })
+    );
+    client.terminate();
+
+    let mut state: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    state["sites"][0]["depends_on"] = json!([id]);
+    fs::write(&path, state.to_string()).unwrap();
+
+    let client = client_saving_to(&path);
+    assert_eq!(items(&client, "/sites")[0]["name"], "Depot");
+    let (_, report) = get(&client, &format!("/sites/{id}/validate"), &global_manager());
+    assert_eq!(report["valid"], false);
+    assert_eq!(
+        report["problems"],
+        json!([{ "field": "depends_on", "message": "must not contain the site itself" }])
+    );
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn validating_an_unknown_site_is_not_found() {
+    let client = client();
+    let (status, _) = get(&client, "/sites/42/validate", &global_manager());
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn stored_intersecting_reservations_are_reported() {
+    let path = state_path("intersecting");
+    let client = client_saving_to(&path);
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let (status, _) = send(
+        &client,
+        Method::Post,
+        &format!("/sites/{site}/vehicles/{truck}"),
+        &gina,
+        None,
+    );
+    assert_eq!(status, Status::Ok);
+    let days = json!({
+        "start_date": "2024-01-01",
+        "start_period": "Morning",
+        "end_date": "2024-01-02",
+        "end_period": "Afternoon",
+    });
+    let uri = format!("/sites/{site}/vehicles/{truck}/reserve");
+    let (status, _) = post(&client, &uri, &gina, days);
+    assert_eq!(status, Status::Ok);
+    client.terminate();
+
+    let mut state: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    let reserved = &mut state["vehicles"][0]["reserved_dates"];
+    let copy = reserved[0].clone();
+    reserved.as_array_mut().unwrap().push(copy);
+    fs::write(&path, state.to_string()).unwrap();
+
+    let client = client_saving_to(&path);
+    let (_, report) = get(&client, &format!("/sites/{site}/validate"), &gina);
+    assert_eq!(report["valid"], false);
+    let problems = report["problems"].as_array().unwrap();
+    assert_eq!(problems.len(), 1, "{report}");
+    assert_eq!(
+        problems[0]["field"],
+        format!("vehicles.{truck}.reserved_dates")
+    );
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn hold_ids_are_not_given_again_after_a_restart() {
+    let path = state_path("holds");
+    let client = client_saving_to(&path);
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let uri = format!("/sites/{site}/vehicles/{truck}");
+    let (status, _) = send(&client, Method::Post, &uri, &gina, None);
+    assert_eq!(status, Status::Ok);
+    let holds = format!("/vehicles/{truck}/holds");
+    let hold = |date: &str| {
+        json!({
+            "site_id": site,
+            "start_date": date,
+            "start_period": "Morning",
+            "end_date": date,
+            "end_period": "Afternoon",
+        })
+    };
+    let (status, first) = post(&client, &holds, &gina, hold("2024-01-01"));
+    assert_eq!(status, Status::Created, "{first}");
+    let confirm = format!("{holds}/{}/confirm", first["data"]["id"]);
+    let (status, _) = send(&client, Method::Post, &confirm, &gina, None);
+    assert_eq!(status, Status::Ok);
+    client.terminate();
+
+    let client = client_saving_to(&path);
+    let (status, second) = post(&client, &holds, &gina, hold("2024-01-02"));
+    assert_eq!(status, Status::Created, "{second}");
+    assert_ne!(second["data"]["id"], first["data"]["id"]);
+    let (status, _) = send(&client, Method::Post, &confirm, &gina, None);
+    assert_eq!(status, Status::NotFound);
+    client.terminate();
+    fs::remove_file(&path).unwrap();
+}
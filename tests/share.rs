@@ -0,0 +1,94 @@
+//! Links viewing a site without logging in, signed with the `share_secret`.
+
+mod common;
+
+use chrono::Duration;
+use rocket::http::Status;
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use common::*;
+
+fn get_shared(client: &Client, url: &str) -> (Status, Value) {
+    let response = client.get(url.to_owned()).dispatch();
+    let status = response.status();
+    (status, response.into_json().unwrap_or(Value::Null))
+}
+
+#[test]
+fn a_shared_site_is_viewed_without_logging_in() {
+    let (client, _) = client_with_settings(json!({ "share_secret": "s3cret" }));
+    let id = create_site(&client, "Depot", 0, 2);
+
+    let (status, link) = post(
+        &client,
+        &format!("/sites/{id}/share"),
+        &global_manager(),
+        json!(null),
+    );
+    assert_eq!(status, Status::Created, "{link}");
+
+    let (status, site) = get_shared(&client, link["url"].as_str().unwrap());
+    assert_eq!(status, Status::Ok, "{site}");
+    assert_eq!(site["site_id"], id);
+    assert_eq!(site["name"], "Depot");
+    assert_eq!(site["link_expires_at"], link["expires_at"]);
+}
+
+#[test]
+fn a_tampered_link_is_refused() {
+    let (client, _) = client_with_settings(json!({ "share_secret": "s3cret" }));
+    let id = create_site(&client, "Depot", 0, 2);
+    let other = create_site(&client, "Warehouse", 0, 2);
+    let (_, link) = post(
+        &client,
+        &format!("/sites/{id}/share"),
+        &global_manager(),
+        json!(null),
+    );
+
+    let (_, signed) = link["token"].as_str().unwrap().split_once('.').unwrap();
+    let tampered = format!("{other}.{signed}");
+    let (status, _) = get_shared(&client, &format!("/shared/{tampered}"));
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = get_shared(&client, "/shared/not-a-token");
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[test]
+fn an_expired_link_is_gone() {
+    let (client, clock) = client_with_settings(json!({ "share_secret": "s3cret" }));
+    let id = create_site(&client, "Depot", 0, 2);
+    let uri = format!("/sites/{id}/share?hours=1");
+    let (_, link) = post(&client, &uri, &global_manager(), json!(null));
+    let url = link["url"].as_str().unwrap();
+
+    clock.advance(Duration::minutes(59));
+    assert_eq!(get_shared(&client, url).0, Status::Ok);
+    clock.advance(Duration::minutes(1));
+    assert_eq!(get_shared(&client, url).0, Status::Gone);
+}
+
+#[test]
+fn links_are_refused_while_the_share_secret_is_unset() {
+    let (signing, _) = client_with_settings(json!({ "share_secret": "s3cret" }));
+    let id = create_site(&signing, "Depot", 0, 2);
+    let (_, link) = post(
+        &signing,
+        &format!("/sites/{id}/share"),
+        &global_manager(),
+        json!(null),
+    );
+
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 2);
+    let (status, _) = post(
+        &client,
+        &format!("/sites/{id}/share"),
+        &global_manager(),
+        json!(null),
+    );
+    assert_eq!(status, Status::ServiceUnavailable);
+    let (status, _) = get_shared(&client, link["url"].as_str().unwrap());
+    assert_eq!(status, Status::ServiceUnavailable);
+}
@@ -0,0 +1,758 @@
+//! Creating, listing, reading and moving sites through their statuses.
+
+mod common;
+
+use rocket::http::{Accept, ContentType, Header, Method, Status};
+use serde_json::json;
+
+use common::*;
+
+#[test]
+fn creating_a_site_makes_its_creator_responsible_and_manager() {
+    let client = client();
+    let (status, site) = post(
+        &client,
+        "/sites",
+        &global_manager(),
+        new_site("Depot", 0, 4),
+    );
+
+    assert_eq!(status, Status::Created);
+    assert_eq!(site["status"], "NotCarried");
+    assert_eq!(site["created_by"], "gina");
+    assert_eq!(site["responsible"], "gina");
+    assert_eq!(site["managers"], json!(["gina"]));
+}
+
+#[test]
+fn every_invalid_field_of_a_new_site_is_reported() {
+    let client = client();
+    let mut site = new_site(" ", 0, 0);
+    site["client"]["phone"] = json!("not a phone");
+    let (status, body) = post(&client, "/sites", &global_manager(), site);
+
+    assert_eq!(status, Status::UnprocessableEntity);
+    let pointers: Vec<&str> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| error["source"]["pointer"].as_str().unwrap())
+        .collect();
+    assert!(pointers.contains(&"/name"), "{body}");
+    assert!(pointers.contains(&"/duration/half_day"), "{body}");
+    assert!(pointers.contains(&"/client/phone"), "{body}");
+}
+
+#[test]
+fn sites_must_fit_in_the_planning_horizon() {
+    let client = client();
+    let (status, body) = post(
+        &client,
+        "/sites",
+        &global_manager(),
+        new_site("Far", 4000, 4),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["errors"][0]["source"]["pointer"], "/start_day");
+
+    let (status, body) = post(
+        &client,
+        "/sites",
+        &global_manager(),
+        new_site("Long", 3640, 40),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["errors"][0]["source"]["pointer"], "/duration/half_day");
+}
+
+fn listed_ids(client: &rocket::local::blocking::Client, uri: &str) -> Vec<u64> {
+    let (status, body) = get(client, uri, &global_manager());
+    assert_eq!(status, Status::Ok, "{body}");
+    body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|site| site["id"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn sites_are_sorted_by_start_day_in_either_direction() {
+    let client = client();
+    let late = create_site(&client, "Late", 10, 2);
+    let early_long = create_site(&client, "Early long", 2, 8);
+    let early_short = create_site(&client, "Early short", 2, 2);
+
+    assert_eq!(
+        listed_ids(&client, "/sites?sort=start_day"),
+        [early_short, early_long, late]
+    );
+    assert_eq!(
+        listed_ids(&client, "/sites?sort=start_day&dir=desc"),
+        [late, early_long, early_short]
+    );
+    let (status, _) = get(&client, "/sites?sort=name", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+    let (status, _) = get(&client, "/sites?dir=up", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn sites_are_filtered_by_status_along_with_the_other_parameters() {
+    let client = client();
+    let first = create_site(&client, "First", 0, 2);
+    let started = create_site(&client, "Started", 0, 2);
+    let third = create_site(&client, "Third", 4, 2);
+    let sam = site_manager("sam");
+    add_manager(&client, started, &sam);
+    let uri = format!("/sites/{started}/status");
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Ok);
+
+    assert_eq!(listed_ids(&client, "/sites?status=InProgress"), [started]);
+    assert_eq!(
+        listed_ids(&client, "/sites?status=NotCarried&sort=start_day&dir=desc"),
+        [third, first]
+    );
+    assert_eq!(
+        listed_ids(&client, "/sites?status=NotCarried&limit=1"),
+        [first]
+    );
+    let (status, _) = get(&client, "/sites?status=Paused", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn site_pages_carry_the_cursor_of_the_next_one() {
+    let client = client();
+    let ids: Vec<u64> = (0..3)
+        .map(|day| create_site(&client, "Site", day, 2))
+        .collect();
+
+    let response = request(&client, Method::Get, "/sites?limit=2", &global_manager()).dispatch();
+    assert_eq!(
+        response.headers().get_one("Next-Cursor"),
+        Some(ids[1].to_string().as_str())
+    );
+    let page: serde_json::Value = response.into_json().unwrap();
+    assert_eq!(page["items"].as_array().unwrap().len(), 2);
+    assert_eq!(page["next_cursor"], ids[1]);
+
+    let (_, last) = get(
+        &client,
+        &format!("/sites?limit=2&after={}", ids[1]),
+        &global_manager(),
+    );
+    assert_eq!(last["items"][0]["id"], ids[2]);
+    assert_eq!(last["next_cursor"], json!(null));
+
+    let (status, _) = get(&client, "/sites?after=1&offset=1", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+    let (status, _) = get(&client, "/sites?limit=0", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn sites_are_listed_as_csv_when_preferred() {
+    let client = client();
+    create_site(&client, "Depot, north", 0, 4);
+
+    let response = request(&client, Method::Get, "/sites", &global_manager())
+        .header(Accept::CSV)
+        .dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    let csv = response.into_string().unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,name,start_day,half_day,status,overdue,vehicles")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("1,\"Depot, north\",0,4,NotCarried,false,")
+    );
+}
+
+#[test]
+fn a_site_is_revalidated_with_its_etag() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 4);
+    let uri = format!("/sites/{id}");
+
+    let response = request(&client, Method::Get, &uri, &global_manager()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let etag = response.headers().get_one("ETag").unwrap().to_owned();
+
+    let response = request(&client, Method::Get, &uri, &global_manager())
+        .header(Header::new("If-None-Match", etag.clone()))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotModified);
+
+    let response = request(&client, Method::Head, &uri, &global_manager()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.headers().get_one("ETag"), Some(etag.as_str()));
+    assert!(response.into_string().unwrap_or_default().is_empty());
+}
+
+#[test]
+fn statuses_only_follow_allowed_transitions() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 4);
+    let sam = site_manager("sam");
+    add_manager(&client, id, &sam);
+    let uri = format!("/sites/{id}/status");
+
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "Completed" }));
+    assert_eq!(status, Status::Conflict);
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Ok);
+    let (status, body) = patch(&client, &uri, &sam, json!({ "status": "Interrupted" }));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["errors"][0]["source"]["pointer"], "/reason");
+
+    let interrupt = json!({ "status": "Interrupted", "reason": "Storm" });
+    let (status, site) = patch(&client, &uri, &sam, interrupt);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(site["status_history"][1]["reason"], "Storm");
+}
+
+#[test]
+fn setting_the_current_status_again_records_nothing() {
+    let (client, _clock) = client_with_clock();
+    let id = create_site(&client, "Depot", 0, 4);
+    let sam = site_manager("sam");
+    add_manager(&client, id, &sam);
+    let uri = format!("/sites/{id}/status");
+
+    let (_, started) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    let (status, again) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+
+    assert_eq!(status, Status::Ok);
+    assert_eq!(again["status_history"].as_array().unwrap().len(), 1);
+    assert_eq!(again["status_history"], started["status_history"]);
+}
+
+#[test]
+fn a_site_starts_once_its_dependencies_are_completed() {
+    let client = client();
+    let sam = site_manager("sam");
+    let first = create_site(&client, "Foundations", 0, 2);
+    let mut walls = new_site("Walls", 2, 2);
+    walls["depends_on"] = json!([first]);
+    let (_, walls) = post(&client, "/sites", &global_manager(), walls);
+    let walls = walls["id"].as_u64().unwrap();
+    add_manager(&client, first, &sam);
+    add_manager(&client, walls, &sam);
+
+    let start = json!({ "status": "InProgress" });
+    let (status, _) = patch(
+        &client,
+        &format!("/sites/{walls}/status"),
+        &sam,
+        start.clone(),
+    );
+    assert_eq!(status, Status::Conflict);
+
+    let first_status = format!("/sites/{first}/status");
+    patch(&client, &first_status, &sam, start.clone());
+    patch(
+        &client,
+        &first_status,
+        &sam,
+        json!({ "status": "Completed" }),
+    );
+    let (status, _) = patch(&client, &format!("/sites/{walls}/status"), &sam, start);
+    assert_eq!(status, Status::Ok);
+}
+
+#[test]
+fn site_managers_only_reach_the_sites_they_manage() {
+    let client = client();
+    let managed = create_site(&client, "Managed", 0, 4);
+    let other = create_site(&client, "Other", 0, 4);
+    let sam = site_manager("sam");
+    add_manager(&client, managed, &sam);
+
+    let (_, list) = get(&client, "/sites", &sam);
+    assert_eq!(list["items"].as_array().unwrap().len(), 1);
+    assert_eq!(list["items"][0]["id"], managed);
+
+    for uri in [
+        "",
+        "/summary",
+        "/labor",
+        "/crew?date=2024-01-01",
+        "/validate",
+    ] {
+        let (status, _) = get(&client, &format!("/sites/{other}{uri}"), &sam);
+        assert_eq!(status, Status::NotFound, "{uri}");
+    }
+    let (status, _) = patch(
+        &client,
+        &format!("/sites/{other}/status"),
+        &sam,
+        json!({ "status": "InProgress" }),
+    );
+    assert_eq!(status, Status::NotFound);
+    let (status, _) = post(
+        &client,
+        &format!("/sites/{other}/anomalies"),
+        &sam,
+        json!({ "reason": "Not mine" }),
+    );
+    assert_eq!(status, Status::NotFound);
+
+    let (status, _) = get(&client, &format!("/sites/{managed}/summary"), &sam);
+    assert_eq!(status, Status::Ok);
+}
+
+#[test]
+fn an_anomaly_interrupts_a_site_in_progress() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 4);
+    let sam = site_manager("sam");
+    add_manager(&client, id, &sam);
+    patch(
+        &client,
+        &format!("/sites/{id}/status"),
+        &sam,
+        json!({ "status": "InProgress" }),
+    );
+
+    let uri = format!("/sites/{id}/anomalies");
+    let (status, site) = post(&client, &uri, &sam, json!({ "reason": "Flooded trench" }));
+    assert_eq!(status, Status::Created);
+    assert_eq!(site["status"], "Interrupted");
+
+    let (_, interrupted) = get(&client, "/sites/interrupted", &sam);
+    assert_eq!(interrupted[0]["site_id"], id);
+    assert_eq!(interrupted[0]["reason"], "Flooded trench");
+    let (status, _) = post(&client, &uri, &sam, json!({ "reason": "  " }));
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn sites_past_their_end_are_overdue() {
+    let client = client();
+    // Two days from Monday 2024-01-01, the default project start.
+    let id = create_site(&client, "Depot", 0, 4);
+    create_site(&client, "Later", 20, 4);
+
+    let (_, overdue) = get(
+        &client,
+        "/sites/overdue?as_of=2024-01-05",
+        &global_manager(),
+    );
+    assert_eq!(overdue.as_array().unwrap().len(), 1);
+    assert_eq!(overdue[0]["site_id"], id);
+    assert_eq!(overdue[0]["end_date"], "2024-01-02");
+    assert_eq!(overdue[0]["days_overdue"], 3);
+
+    let (_, overdue) = get(
+        &client,
+        "/sites/overdue?as_of=2024-01-02",
+        &global_manager(),
+    );
+    assert_eq!(overdue, json!([]));
+    let (status, _) = get(&client, "/sites/overdue?as_of=soon", &global_manager());
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn the_summary_gathers_every_section_of_a_site() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_site(&client, "Depot", 0, 4);
+    let vehicle = create_vehicle(&client, "Truck 1", "Truck");
+    send(
+        &client,
+        Method::Post,
+        &format!("/sites/{id}/vehicles/{vehicle}"),
+        &gina,
+        None,
+    );
+    post(
+        &client,
+        &format!("/sites/{id}/workers"),
+        &gina,
+        json!({ "name": "Ana" }),
+    );
+
+    let (status, summary) = get(&client, &format!("/sites/{id}/summary"), &gina);
+    assert_eq!(status, Status::Ok);
+    for section in [
+        "name",
+        "status",
+        "client",
+        "coordinates",
+        "schedule",
+        "workers",
+        "vehicles",
+        "open_anomalies",
+    ] {
+        assert!(
+            !summary[section].is_null(),
+            "{section} missing from {summary}"
+        );
+    }
+    assert_eq!(summary["schedule"]["first_date"], "2024-01-01");
+    assert_eq!(summary["schedule"]["last_date"], "2024-01-02");
+    assert_eq!(summary["workers"][0]["name"], "Ana");
+    assert_eq!(summary["vehicles"][0]["vehicle_id"], vehicle);
+    assert_eq!(summary["labor_half_days"], 4);
+}
+
+#[test]
+fn labor_counts_the_half_days_of_every_worker() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_site(&client, "Depot", 0, 6);
+    for name in ["Ana", "Ben"] {
+        post(
+            &client,
+            &format!("/sites/{id}/workers"),
+            &gina,
+            json!({ "name": name }),
+        );
+    }
+
+    let (_, labor) = get(&client, &format!("/sites/{id}/labor"), &gina);
+    assert_eq!(labor["workers"], 2);
+    assert_eq!(labor["duration_half_days"], 6);
+    assert_eq!(labor["labor_half_days"], 12);
+}
+
+#[test]
+fn not_carried_sites_are_reordered_in_the_backlog() {
+    let client = client();
+    let first = create_site(&client, "First", 0, 2);
+    let second = create_site(&client, "Second", 0, 2);
+
+    let (status, reordered) = post(
+        &client,
+        "/sites/reorder",
+        &global_manager(),
+        json!([second, first]),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(reordered[0]["id"], second);
+    assert_eq!(reordered[0]["priority"], 0);
+    assert_eq!(listed_ids(&client, "/sites?sort=priority"), [second, first]);
+
+    let (status, _) = post(
+        &client,
+        "/sites/reorder",
+        &global_manager(),
+        json!([first, first]),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn sites_are_found_inside_a_bounding_box() {
+    let client = client();
+    let inside = create_site(&client, "Inside", 0, 2);
+    let mut far = new_site("Far", 0, 2);
+    far["coordinates"] = json!({ "latitude": 43.6, "longitude": 1.4 });
+    post(&client, "/sites", &global_manager(), far);
+
+    let uri = "/sites/in-bounds?min_lat=48&min_lon=-2&max_lat=49&max_lon=-1";
+    assert_eq!(listed_ids(&client, uri), [inside]);
+    let (status, _) = get(
+        &client,
+        "/sites/in-bounds?min_lat=49&min_lon=-2&max_lat=48&max_lon=-1",
+        &global_manager(),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn a_site_is_transferred_to_another_global_manager() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 2);
+
+    get(&client, "/sites", &global_manager_named("hugo"));
+
+    let uri = format!("/sites/{id}/transfer");
+    let (status, site) = post(&client, &uri, &global_manager(), json!("hugo"));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(site["responsible"], "hugo");
+    assert_eq!(listed_ids(&client, "/sites?responsible=hugo"), [id]);
+
+    let (status, _) = post(&client, &uri, &global_manager(), json!(" "));
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn a_site_is_not_transferred_to_an_unknown_user_or_a_site_manager() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 2);
+    get(&client, "/sites", &site_manager("sam"));
+
+    let uri = format!("/sites/{id}/transfer");
+    for to in ["nobody", "sam"] {
+        let (status, error) = post(&client, &uri, &global_manager(), json!(to));
+        assert_eq!(status, Status::UnprocessableEntity, "{error}");
+        assert!(
+            error["errors"][0]["detail"].as_str().unwrap().contains(to),
+            "{error}"
+        );
+    }
+    let (_, site) = get(&client, &format!("/sites/{id}"), &global_manager());
+    assert_eq!(site["responsible"], "gina");
+}
+
+#[test]
+fn a_site_is_edited_through_a_json_patch() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 2);
+    let uri = format!("/sites/{id}");
+    let patch_site = |operations: serde_json::Value| {
+        let response = request(&client, Method::Patch, &uri, &global_manager())
+            .header(ContentType::new("application", "json-patch+json"))
+            .body(operations.to_string())
+            .dispatch();
+        let status = response.status();
+        (status, response.into_json::<serde_json::Value>().unwrap())
+    };
+
+    let (status, site) = patch_site(json!([
+        { "op": "replace", "path": "/name", "value": "Warehouse" },
+        { "op": "replace", "path": "/duration/half_day", "value": 6 },
+    ]));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(site["name"], "Warehouse");
+    assert_eq!(site["duration"]["half_day"], 6);
+
+    let (status, _) = patch_site(json!([{ "op": "test", "path": "/name", "value": "Depot" }]));
+    assert_eq!(status, Status::Conflict);
+    let (status, _) = patch_site(json!([{ "op": "add", "path": "/status", "value": "Completed" }]));
+    assert_eq!(status, Status::UnprocessableEntity);
+    let (status, _) = patch_site(json!([{ "op": "replace", "path": "/name", "value": "" }]));
+    assert_eq!(status, Status::UnprocessableEntity);
+
+    let (_, site) = get(&client, &uri, &global_manager());
+    assert_eq!(site["name"], "Warehouse");
+}
+
+#[test]
+fn changes_since_a_version_are_listed() {
+    let client = client();
+    let id = create_site(&client, "Depot", 0, 2);
+    let edited = new_site("Warehouse", 0, 2);
+    let (status, _) = send(
+        &client,
+        Method::Put,
+        &format!("/sites/{id}"),
+        &global_manager(),
+        Some(edited),
+    );
+    assert_eq!(status, Status::Ok);
+
+    let (status, diff) = get(
+        &client,
+        &format!("/sites/{id}/diff?from_version=1"),
+        &global_manager(),
+    );
+    assert_eq!(status, Status::Ok, "{diff}");
+    assert!(diff["to_version"].as_u64().unwrap() > 1);
+    assert_eq!(
+        diff["changes"][0],
+        json!({ "change": "renamed", "from": "Depot", "to": "Warehouse" })
+    );
+
+    let (status, _) = get(
+        &client,
+        &format!("/sites/{id}/diff?from_version=99"),
+        &global_manager(),
+    );
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn projects_are_estimated_after_their_dependencies_on_working_days() {
+    let (client, _clock) = client_with_settings(json!({
+        "calendar": {
+            "project_start": "2024-01-01",
+            "working_weekdays": ["Mon", "Tue", "Wed", "Thu", "Fri"],
+            "holidays": ["2024-01-03"],
+        },
+    }));
+    let gina = global_manager();
+    let foundations = create_site(&client, "Foundations", 0, 3);
+    let mut walls = new_site("Walls", 0, 4);
+    walls["depends_on"] = json!([foundations]);
+    let (status, walls) = post(&client, "/sites", &gina, walls);
+    assert_eq!(status, Status::Created, "{walls}");
+
+    let uri = format!("/projects/estimate?sites={}", walls["id"]);
+    let (status, estimate) = get(&client, &uri, &gina);
+    assert_eq!(status, Status::Ok, "{estimate}");
+    let windows: Vec<_> = estimate["sites"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|site| {
+            (
+                site["name"].as_str().unwrap(),
+                site["start_date"].as_str().unwrap(),
+                site["start_period"]["period"].as_str().unwrap(),
+                site["end_date"].as_str().unwrap(),
+                site["end_period"]["period"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    // Walls wait for the foundations, then skip the Wednesday holiday.
+    assert_eq!(
+        windows,
+        [
+            (
+                "Foundations",
+                "2024-01-01",
+                "Morning",
+                "2024-01-02",
+                "Morning"
+            ),
+            ("Walls", "2024-01-02", "Afternoon", "2024-01-05", "Morning"),
+        ]
+    );
+    assert_eq!(estimate["completion_date"], "2024-01-05");
+    assert_eq!(estimate["completion_period"]["period"], "Morning");
+}
+
+#[test]
+fn the_gantt_chart_spans_each_site_over_its_working_days() {
+    let client = client();
+    // Day 4 of the default calendar is Friday 2024-01-05.
+    let bridge = create_site(&client, "Bridge", 4, 4);
+    let road = create_site(&client, "Road", 0, 2);
+    let sam = site_manager("sam");
+    add_manager(&client, road, &sam);
+    let uri = format!("/sites/{road}/status");
+    let (status, _) = patch(&client, &uri, &sam, json!({ "status": "InProgress" }));
+    assert_eq!(status, Status::Ok);
+
+    let (status, chart) = get(&client, "/schedule/gantt", &global_manager());
+    assert_eq!(status, Status::Ok, "{chart}");
+    assert_eq!(
+        chart["tasks"],
+        json!([
+            { "id": bridge, "name": "Bridge", "start": "2024-01-05", "end": "2024-01-08", "progress": 0 },
+            { "id": road, "name": "Road", "start": "2024-01-01", "end": "2024-01-01", "progress": 50 },
+        ])
+    );
+
+    let (_, chart) = get(&client, "/schedule/gantt", &sam);
+    assert_eq!(chart["tasks"].as_array().unwrap().len(), 1);
+    assert_eq!(chart["tasks"][0]["id"], road);
+}
+
+#[test]
+fn managers_only_take_sites_up_to_the_limit_not_counting_completed_ones() {
+    let (client, _clock) = client_with_settings(json!({ "max_sites_per_manager": 2 }));
+    let gina = global_manager();
+    let sam = site_manager("sam");
+    let first = create_site(&client, "First", 0, 2);
+    let second = create_site(&client, "Second", 0, 2);
+    let third = create_site(&client, "Third", 0, 2);
+    add_manager(&client, first, &sam);
+    add_manager(&client, second, &sam);
+
+    let body = json!({ "name": "sam" });
+    let (status, error) = post(
+        &client,
+        &format!("/sites/{third}/managers"),
+        &gina,
+        body.clone(),
+    );
+    assert_eq!(status, Status::Conflict, "{error}");
+
+    let uri = format!("/sites/{first}/status");
+    for status in ["InProgress", "Completed"] {
+        let (code, _) = patch(&client, &uri, &sam, json!({ "status": status }));
+        assert_eq!(code, Status::Ok, "{status}");
+    }
+    add_manager(&client, third, &sam);
+}
+
+#[test]
+fn tools_and_materials_are_added_and_removed() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_site(&client, "Depot", 0, 2);
+
+    for supplies in ["tools", "materials"] {
+        let uri = format!("/sites/{id}/{supplies}");
+        let (status, first) = post(
+            &client,
+            &uri,
+            &gina,
+            json!({ "name": "Drill", "quantity": 2 }),
+        );
+        assert_eq!(status, Status::Created, "{first}");
+        let (_, second) = post(
+            &client,
+            &uri,
+            &gina,
+            json!({ "name": "Saw", "quantity": 1 }),
+        );
+        assert_ne!(first["id"], second["id"]);
+        let invalid = json!({ "name": " ", "quantity": 0 });
+        let (status, error) = post(&client, &uri, &gina, invalid);
+        assert_eq!(status, Status::UnprocessableEntity, "{error}");
+        assert_eq!(error["errors"].as_array().unwrap().len(), 2);
+
+        let remove = format!("{uri}/{}", first["id"]);
+        let (status, _) = send(&client, Method::Delete, &remove, &gina, None);
+        assert_eq!(status, Status::NoContent);
+        let (status, _) = send(&client, Method::Delete, &remove, &gina, None);
+        assert_eq!(status, Status::NotFound);
+        let (_, site) = get(&client, &format!("/sites/{id}"), &gina);
+        assert_eq!(site["resources"][supplies], json!([second]));
+    }
+}
+
+#[test]
+fn a_single_photo_is_the_site_cover() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_site(&client, "Depot", 0, 2);
+    let photos = format!("/sites/{id}/photos");
+    let (status, first) = post(
+        &client,
+        &photos,
+        &gina,
+        json!({ "url": "https://example.com/1.jpg" }),
+    );
+    assert_eq!(status, Status::Created, "{first}");
+    assert_eq!(first["is_cover"], true);
+    let (_, second) = post(
+        &client,
+        &photos,
+        &gina,
+        json!({ "url": "https://example.com/2.jpg" }),
+    );
+    assert_eq!(second["is_cover"], false);
+
+    let cover = format!("{photos}/{}/cover", second["id"]);
+    let (status, photo) = send(&client, Method::Post, &cover, &gina, None);
+    assert_eq!(status, Status::Ok, "{photo}");
+    assert_eq!(photo["is_cover"], true);
+    let (_, site) = get(&client, &format!("/sites/{id}"), &gina);
+    let covers: Vec<_> = site["photos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|photo| photo["is_cover"].as_bool().unwrap())
+        .collect();
+    assert_eq!(covers, [false, true]);
+
+    let (status, _) = send(
+        &client,
+        Method::Post,
+        &format!("{photos}/99/cover"),
+        &gina,
+        None,
+    );
+    assert_eq!(status, Status::NotFound);
+}
@@ -0,0 +1,109 @@
+//! The `X-Test-User` and `X-Test-Role` headers log requests in only under the `test-auth`
+//! feature, e.g. `cargo test --features test-auth`.
+
+mod common;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::Client;
+
+use common::*;
+
+fn get_as(client: &Client, uri: &'static str, name: &str, role: &str) -> Status {
+    client
+        .get(uri)
+        .header(Header::new("X-Test-User", name.to_owned()))
+        .header(Header::new("X-Test-Role", role.to_owned()))
+        .dispatch()
+        .status()
+}
+
+fn create_site_as(client: &Client, name: &str, role: &str) -> Status {
+    client
+        .post("/sites")
+        .header(Header::new("X-Test-User", name.to_owned()))
+        .header(Header::new("X-Test-Role", role.to_owned()))
+        .header(ContentType::JSON)
+        .body(new_site("Depot", 0, 4).to_string())
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn requests_without_test_headers_are_not_logged_in() {
+    let client = client();
+
+    assert_eq!(
+        client.get("/sites").dispatch().status(),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        client
+            .get("/sites")
+            .header(Header::new("X-Test-User", "gina"))
+            .dispatch()
+            .status(),
+        Status::Unauthorized
+    );
+}
+
+#[cfg(feature = "test-auth")]
+mod enabled {
+    use super::*;
+
+    #[test]
+    fn test_headers_log_the_request_in() {
+        let client = client();
+
+        assert_eq!(
+            get_as(&client, "/sites", "gina", "SitesGlobalManager"),
+            Status::Ok
+        );
+        assert_eq!(
+            get_as(&client, "/vehicles", "sam", "SiteManager"),
+            Status::Ok
+        );
+    }
+
+    #[test]
+    fn test_headers_carry_the_role_permissions() {
+        let client = client();
+
+        assert_eq!(
+            create_site_as(&client, "gina", "SitesGlobalManager"),
+            Status::Created
+        );
+        assert_eq!(
+            create_site_as(&client, "sam", "SiteManager"),
+            Status::Forbidden
+        );
+    }
+
+    #[test]
+    fn an_unknown_test_role_is_not_logged_in() {
+        let client = client();
+
+        assert_eq!(
+            get_as(&client, "/sites", "gina", "Admin"),
+            Status::Unauthorized
+        );
+    }
+}
+
+#[cfg(not(feature = "test-auth"))]
+mod disabled {
+    use super::*;
+
+    #[test]
+    fn test_headers_are_ignored() {
+        let client = client();
+
+        assert_eq!(
+            get_as(&client, "/sites", "gina", "SitesGlobalManager"),
+            Status::Unauthorized
+        );
+        assert_eq!(
+            create_site_as(&client, "gina", "SitesGlobalManager"),
+            Status::Unauthorized
+        );
+    }
+}
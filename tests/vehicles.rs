@@ -0,0 +1,931 @@
+//! Fleet vehicles and their reservations, through the routes.
+
+mod common;
+
+use rocket::http::{ContentType, Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use common::*;
+
+/// A reservation body from `start` to `end`, the whole of both days.
+fn days(start: &str, end: &str) -> Value {
+    json!({
+        "start_date": start,
+        "start_period": "Morning",
+        "end_date": end,
+        "end_period": "Afternoon",
+    })
+}
+
+/// Makes vehicle `vehicle` available to site `site`.
+fn attach(client: &Client, site: u64, vehicle: u64) {
+    let uri = format!("/sites/{site}/vehicles/{vehicle}");
+    let (status, body) = send(client, Method::Post, &uri, &global_manager(), None);
+    assert_eq!(status, Status::Ok, "{body}");
+}
+
+fn reserve(client: &Client, site: u64, vehicle: u64, body: Value) -> (Status, Value) {
+    let uri = format!("/sites/{site}/vehicles/{vehicle}/reserve");
+    post(client, &uri, &global_manager(), body)
+}
+
+#[test]
+fn clones_keep_the_kind_but_not_the_reservations() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::Ok);
+
+    let uri = format!("/vehicles/{truck}/clone");
+    let (status, copy) = send(&client, Method::Post, &uri, &global_manager(), None);
+    assert_eq!(status, Status::Created, "{copy}");
+    assert_ne!(copy["id"], truck);
+    assert_eq!(copy["name"], "Truck 1 (copy)");
+    assert_eq!(copy["kind"], "Truck");
+    assert_eq!(copy["reserved_dates"], json!([]));
+}
+
+#[test]
+fn vehicles_are_listed_by_kind_and_free_date() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-02", "2024-01-02"));
+    assert_eq!(status, Status::Ok);
+
+    let gina = global_manager();
+    let (status, cranes) = get(&client, "/vehicles?kind=Crane", &gina);
+    assert_eq!(status, Status::Ok);
+    let ids: Vec<&Value> = cranes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| &v["vehicle"]["id"])
+        .collect();
+    assert_eq!(ids, [&json!(crane)]);
+
+    let (_, free) = get(&client, "/vehicles?free_on=2024-01-02", &gina);
+    let ids: Vec<&Value> = free
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| &v["vehicle"]["id"])
+        .collect();
+    assert_eq!(ids, [&json!(crane)]);
+
+    let (status, _) = get(&client, "/vehicles?free_on=tomorrow", &gina);
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn a_conflicting_reservation_offers_free_vehicles_of_the_same_kind() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let first = create_vehicle(&client, "Truck 1", "Truck");
+    let second = create_vehicle(&client, "Truck 2", "Truck");
+    create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, first);
+
+    let (status, receipt) = reserve(&client, site, first, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::Ok, "{receipt}");
+    assert_eq!(receipt["data"]["half_days"], 4);
+    assert_eq!(receipt["data"]["reserved"]["reserved_by"], "gina");
+    assert!(receipt["data"]["reserved"]["start_period_label"].is_string());
+
+    let (status, error) = reserve(&client, site, first, days("2024-01-02", "2024-01-03"));
+    assert_eq!(status, Status::Conflict, "{error}");
+    assert_eq!(error["errors"][0]["code"], "already_reserved");
+    assert_eq!(error["errors"][0]["meta"]["alternatives"], json!([second]));
+}
+
+#[test]
+fn a_conflict_lists_the_overlapping_half_days() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::Ok);
+
+    let (status, error) = reserve(&client, site, truck, days("2024-01-02", "2024-01-03"));
+    assert_eq!(status, Status::Conflict, "{error}");
+    assert_eq!(
+        error["errors"][0]["meta"]["overlapping"],
+        json!([["2024-01-02", "Morning"], ["2024-01-02", "Afternoon"]])
+    );
+    let detail = error["errors"][0]["detail"].as_str().unwrap();
+    assert!(
+        detail.ends_with("overlapping on 2024-01-02 Morning, 2024-01-02 Afternoon"),
+        "{detail}"
+    );
+}
+
+#[test]
+fn reservations_carry_the_configured_period_labels() {
+    let labels = json!({ "Morning": "Matin", "Afternoon": "Après-midi" });
+    let (french, _clock) = client_with_settings(json!({ "period_labels": labels }));
+    // Started after the French one, with the default labels.
+    let english = client();
+    let [(site, truck), (english_site, english_truck)] = [&french, &english].map(|client| {
+        let site = create_site(client, "Depot", 0, 10);
+        let truck = create_vehicle(client, "Truck 1", "Truck");
+        attach(client, site, truck);
+        (site, truck)
+    });
+
+    let (status, receipt) = reserve(&french, site, truck, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::Ok, "{receipt}");
+    let reserved = &receipt["data"]["reserved"];
+    assert_eq!(reserved["start_period"], "Morning");
+    assert_eq!(reserved["start_period_label"], "Matin");
+    assert_eq!(reserved["end_period"], "Afternoon");
+    assert_eq!(reserved["end_period_label"], "Après-midi");
+    let (_, vehicle) = get(&french, &format!("/vehicles/{truck}"), &global_manager());
+    assert_eq!(vehicle["reserved_dates"][0]["start_period_label"], "Matin");
+
+    let period = days("2024-01-01", "2024-01-02");
+    let (_, receipt) = reserve(&english, english_site, english_truck, period);
+    assert_eq!(receipt["data"]["reserved"]["start_period_label"], "Morning");
+    assert_eq!(receipt["data"]["reserved"]["end_period_label"], "Afternoon");
+}
+
+#[test]
+fn dry_runs_leave_the_vehicle_untouched() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let uri = format!("/sites/{site}/vehicles/{truck}/reserve?dry_run=true");
+
+    let (status, receipt) = post(
+        &client,
+        &uri,
+        &global_manager(),
+        days("2024-01-01", "2024-01-01"),
+    );
+    assert_eq!(status, Status::Ok, "{receipt}");
+    assert_eq!(receipt["data"]["dry_run"], true);
+    assert_eq!(receipt["data"]["available"], true);
+    let (_, vehicle) = get(&client, &format!("/vehicles/{truck}"), &global_manager());
+    assert_eq!(vehicle["reserved_dates"], json!([]));
+
+    reserve(&client, site, truck, days("2024-01-01", "2024-01-01"));
+    let (status, receipt) = post(
+        &client,
+        &uri,
+        &global_manager(),
+        days("2024-01-01", "2024-01-01"),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(receipt["data"]["available"], false);
+    assert!(receipt["data"]["conflict"].is_string());
+}
+
+#[test]
+fn only_vehicles_used_by_the_site_can_be_reserved_for_it() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-01"));
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn reservations_outside_the_site_window_are_warned_about() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 2);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let (status, receipt) = reserve(&client, site, truck, days("2024-01-08", "2024-01-08"));
+    assert_eq!(status, Status::Ok, "{receipt}");
+    assert_eq!(receipt["warnings"][0]["code"], "outside_site_window");
+}
+
+#[test]
+fn vehicles_in_maintenance_cannot_be_reserved_until_it_ends() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let maintenance = format!("/vehicles/{truck}/maintenance");
+    let (status, _) = post(&client, &maintenance, &gina, json!({}));
+    assert_eq!(status, Status::Ok);
+    let (status, error) = reserve(&client, site, truck, days("2024-01-01", "2024-01-01"));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(error["errors"][0]["code"], "vehicle_in_maintenance");
+
+    let (status, vehicle) = send(&client, Method::Delete, &maintenance, &gina, None);
+    assert_eq!(status, Status::Ok, "{vehicle}");
+    assert_eq!(vehicle["in_maintenance"], false);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-01"));
+    assert_eq!(status, Status::Ok);
+}
+
+#[test]
+fn ending_maintenance_serves_the_waitlist() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let maintenance = format!("/vehicles/{truck}/maintenance");
+    post(&client, &maintenance, &gina, json!({}));
+    let mut request = days("2024-01-01", "2024-01-01");
+    request["site_id"] = json!(site);
+    let (status, entry) = post(
+        &client,
+        &format!("/vehicles/{truck}/waitlist"),
+        &gina,
+        request,
+    );
+    assert_eq!(status, Status::Created, "{entry}");
+
+    let (_, vehicle) = send(&client, Method::Delete, &maintenance, &gina, None);
+    assert_eq!(vehicle["waitlist"], json!([]));
+    assert_eq!(vehicle["reserved_dates"][0]["site_id"], site);
+}
+
+#[test]
+fn maintenance_windows_block_reservations_in_them() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let windows = format!("/vehicles/{truck}/maintenance/windows");
+    let (status, _) = post(&client, &windows, &gina, days("2024-01-03", "2024-01-03"));
+    assert_eq!(status, Status::Created);
+    let (status, error) = reserve(&client, site, truck, days("2024-01-02", "2024-01-04"));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(error["errors"][0]["code"], "maintenance_scheduled");
+    let (status, _) = reserve(&client, site, truck, days("2024-01-04", "2024-01-04"));
+    assert_eq!(status, Status::Ok);
+
+    let (status, _) = post(&client, &windows, &gina, days("2024-01-04", "2024-01-04"));
+    assert_eq!(status, Status::Conflict);
+}
+
+#[test]
+fn maintenance_windows_keep_the_turnaround_buffer() {
+    // The afternoon starts 15 minutes after the morning ends.
+    let hours = json!({ "afternoon_start": "12:15:00" });
+    for (buffer, expected) in [(15, Status::Created), (30, Status::Conflict)] {
+        let (client, _clock) = client_with_settings(json!({
+            "turnaround_buffer_mins": buffer,
+            "work_hours": hours,
+        }));
+        let site = create_site(&client, "Depot", 0, 10);
+        let truck = create_vehicle(&client, "Truck 1", "Truck");
+        attach(&client, site, truck);
+        let mut morning = days("2024-01-02", "2024-01-02");
+        morning["end_period"] = json!("Morning");
+        let (status, _) = reserve(&client, site, truck, morning);
+        assert_eq!(status, Status::Ok);
+
+        let mut afternoon = days("2024-01-02", "2024-01-02");
+        afternoon["start_period"] = json!("Afternoon");
+        let windows = format!("/vehicles/{truck}/maintenance/windows");
+        let (status, body) = post(&client, &windows, &global_manager(), afternoon);
+        assert_eq!(status, expected, "{buffer} minutes: {body}");
+    }
+}
+
+#[test]
+fn releasing_a_reservation_serves_the_waitlist() {
+    let client = client();
+    let gina = global_manager();
+    let first = create_site(&client, "Depot", 0, 10);
+    let second = create_site(&client, "Warehouse", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, first, truck);
+    attach(&client, second, truck);
+    reserve(&client, first, truck, days("2024-01-01", "2024-01-02"));
+
+    let waitlist = format!("/vehicles/{truck}/waitlist");
+    let mut request = days("2024-01-02", "2024-01-02");
+    request["site_id"] = json!(second);
+    let (status, _) = post(&client, &waitlist, &gina, request.clone());
+    assert_eq!(status, Status::Created);
+    let mut free = days("2024-01-05", "2024-01-05");
+    free["site_id"] = json!(second);
+    let (status, _) = post(&client, &waitlist, &gina, free);
+    assert_eq!(status, Status::UnprocessableEntity);
+
+    let release = format!("/vehicles/{truck}/reservations/release");
+    let (status, _) = post(&client, &release, &gina, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::Ok);
+    let (_, vehicle) = get(&client, &format!("/vehicles/{truck}"), &gina);
+    assert_eq!(vehicle["waitlist"], json!([]));
+    assert_eq!(vehicle["reserved_dates"].as_array().unwrap().len(), 1);
+    assert_eq!(vehicle["reserved_dates"][0]["site_id"], second);
+
+    let (status, _) = post(&client, &release, &gina, days("2024-01-01", "2024-01-02"));
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn shortened_reservations_free_their_last_half_days() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    reserve(&client, site, truck, days("2024-01-01", "2024-01-03"));
+
+    let mut shorten = days("2024-01-01", "2024-01-03");
+    shorten["new_end_date"] = json!("2024-01-02");
+    shorten["new_end_period"] = json!("Morning");
+    let uri = format!("/vehicles/{truck}/reservations/shorten");
+    let (status, vehicle) = post(&client, &uri, &gina, shorten);
+    assert_eq!(status, Status::Ok, "{vehicle}");
+    assert_eq!(vehicle["reserved_dates"][0]["end_period"], "Morning");
+
+    let (status, _) = reserve(&client, site, truck, days("2024-01-03", "2024-01-03"));
+    assert_eq!(status, Status::Ok);
+}
+
+#[test]
+fn blackouts_block_every_vehicle() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let mut holiday = days("2024-01-02", "2024-01-02");
+    holiday["note"] = json!("Holiday");
+    let (status, blackout) = post(&client, "/blackouts", &gina, holiday);
+    assert_eq!(status, Status::Created, "{blackout}");
+    let (status, error) = reserve(&client, site, truck, days("2024-01-01", "2024-01-03"));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(error["errors"][0]["code"], "blackout");
+
+    let uri = format!("/blackouts/{}", blackout["id"]);
+    let (status, _) = send(&client, Method::Delete, &uri, &gina, None);
+    assert_eq!(status, Status::NoContent);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-03"));
+    assert_eq!(status, Status::Ok);
+}
+
+#[test]
+fn reservations_spanning_too_many_days_are_refused() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let (status, error) = reserve(&client, site, truck, days("2024-01-01", "2027-01-01"));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(error["errors"][0]["code"], "reservation_too_long");
+}
+
+#[test]
+fn csv_imports_report_an_outcome_per_row() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    let csv = format!(
+        "vehicle_id,start_date,start_period,end_date,end_period\n\
+         {truck},2024-01-01,Morning,2024-01-01,Afternoon\n\
+         {truck},2024-01-01,Afternoon,2024-01-02,Morning\n\
+         {truck},2024-01-03,Evening,2024-01-03,Afternoon\n"
+    );
+    let response = request(
+        &client,
+        Method::Post,
+        &format!("/sites/{site}/reservations/import"),
+        &global_manager(),
+    )
+    .header(ContentType::Text)
+    .body(csv)
+    .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let rows: Value = response.into_json().unwrap();
+    let outcomes: Vec<(&Value, &Value)> = rows["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| (&row["line"], &row["outcome"]))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            (&json!(2), &json!("Reserved")),
+            (&json!(3), &json!("Conflict")),
+            (&json!(4), &json!("Invalid")),
+        ]
+    );
+}
+
+#[test]
+fn clearing_a_site_cancels_only_its_reservations() {
+    let client = client();
+    let first = create_site(&client, "Depot", 0, 10);
+    let second = create_site(&client, "Warehouse", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, first, truck);
+    attach(&client, second, truck);
+    reserve(&client, first, truck, days("2024-01-01", "2024-01-01"));
+    reserve(&client, first, truck, days("2024-01-02", "2024-01-02"));
+    reserve(&client, second, truck, days("2024-01-03", "2024-01-03"));
+
+    let uri = format!("/sites/{first}/reservations/clear");
+    let (status, cleared) = send(&client, Method::Post, &uri, &global_manager(), None);
+    assert_eq!(status, Status::Ok, "{cleared}");
+    assert_eq!(cleared["cleared"], 2);
+    let (_, vehicle) = get(&client, &format!("/vehicles/{truck}"), &global_manager());
+    assert_eq!(vehicle["reserved_dates"].as_array().unwrap().len(), 1);
+    assert_eq!(vehicle["reserved_dates"][0]["site_id"], second);
+}
+
+#[test]
+fn reservations_are_listed_by_their_author() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    attach(&client, site, crane);
+    reserve(&client, site, truck, days("2024-01-03", "2024-01-03"));
+    reserve(&client, site, crane, days("2024-01-01", "2024-01-01"));
+
+    let (_, mine) = get(&client, "/reservations/mine", &gina);
+    let vehicles: Vec<&Value> = mine
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["vehicle_id"])
+        .collect();
+    assert_eq!(vehicles, [&json!(crane), &json!(truck)]);
+    let (_, none) = get(&client, "/reservations/mine", &global_manager_named("gabe"));
+    assert_eq!(none, json!([]));
+}
+
+#[test]
+fn reservations_are_searched_by_dates_across_the_fleet() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    attach(&client, site, crane);
+    reserve(&client, site, truck, days("2024-01-03", "2024-01-03"));
+    reserve(&client, site, crane, days("2024-01-01", "2024-01-01"));
+
+    let (_, found) = get(
+        &client,
+        "/reservations?from=2024-01-02&to=2024-01-05",
+        &gina,
+    );
+    let vehicles: Vec<&Value> = found
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["vehicle_id"])
+        .collect();
+    assert_eq!(vehicles, [&json!(truck)]);
+    let uri = "/reservations?from=2024-01-01&to=2024-01-05&vehicle_kind=Crane";
+    let (_, cranes) = get(&client, uri, &gina);
+    let vehicles: Vec<&Value> = cranes
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["vehicle_id"])
+        .collect();
+    assert_eq!(vehicles, [&json!(crane)]);
+}
+
+/// A global manager other than the one making the reservations.
+#[test]
+fn reservations_ending_soon_are_listed_by_end() {
+    let (client, _clock) = client_with_clock();
+    let site = create_site(&client, "Depot", 40, 20);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    attach(&client, site, crane);
+    reserve(&client, site, truck, days("2024-03-04", "2024-03-06"));
+    reserve(&client, site, crane, days("2024-03-01", "2024-03-05"));
+    reserve(&client, site, crane, days("2024-03-11", "2024-03-11"));
+
+    let (status, soon) = get(&client, "/reservations/ending-soon", &global_manager());
+    assert_eq!(status, Status::Ok);
+    let vehicles: Vec<&Value> = soon
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["vehicle_id"])
+        .collect();
+    assert_eq!(vehicles, [&json!(crane), &json!(truck)]);
+}
+
+#[test]
+fn reservations_ending_soon_look_a_bounded_number_of_days_ahead() {
+    let client = client();
+    let gina = global_manager();
+    let (status, soon) = get(&client, "/reservations/ending-soon?days=366", &gina);
+    assert_eq!(status, Status::Ok, "{soon}");
+
+    for days in [367u32, 4_000_000_000] {
+        let uri = format!("/reservations/ending-soon?days={days}");
+        let (status, error) = get(&client, &uri, &gina);
+        assert_eq!(status, Status::UnprocessableEntity, "{days}: {error}");
+    }
+}
+
+#[test]
+fn conflicts_say_how_the_reservations_overlap() {
+    // Afternoons start an hour after mornings end, within the 90 minutes buffer.
+    let (client, _clock) = client_with_settings(json!({ "turnaround_buffer_mins": 90 }));
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-02", "2024-01-03"));
+    assert_eq!(status, Status::Ok);
+    let mut morning = days("2024-01-05", "2024-01-05");
+    morning["end_period"] = json!("Morning");
+    let (status, _) = reserve(&client, site, truck, morning);
+    assert_eq!(status, Status::Ok);
+
+    let mut touching = days("2024-01-01", "2024-01-02");
+    touching["end_period"] = json!("Morning");
+    let mut too_close = days("2024-01-05", "2024-01-05");
+    too_close["start_period"] = json!("Afternoon");
+    for (requested, kind) in [
+        (days("2024-01-02", "2024-01-02"), "Full"),
+        (days("2024-01-01", "2024-01-02"), "Partial"),
+        (touching, "Touching"),
+        (too_close, "TooClose"),
+    ] {
+        let (status, error) = reserve(&client, site, truck, requested);
+        assert_eq!(status, Status::Conflict, "{kind}: {error}");
+        assert_eq!(error["errors"][0]["meta"]["kind"], kind, "{error}");
+    }
+}
+
+#[test]
+fn the_heatmap_counts_reserved_vehicles_per_half_day() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let first = create_vehicle(&client, "Truck 1", "Truck");
+    let second = create_vehicle(&client, "Truck 2", "Truck");
+    attach(&client, site, first);
+    attach(&client, site, second);
+    let (status, _) = reserve(&client, site, first, days("2024-01-02", "2024-01-02"));
+    assert_eq!(status, Status::Ok);
+    let mut morning = days("2024-01-02", "2024-01-02");
+    morning["end_period"] = json!("Morning");
+    let (status, _) = reserve(&client, site, second, morning);
+    assert_eq!(status, Status::Ok);
+
+    let uri = "/resources/heatmap?from=2024-01-02&to=2024-01-03";
+    let (status, heatmap) = get(&client, uri, &gina);
+    assert_eq!(status, Status::Ok, "{heatmap}");
+    let counts: Vec<_> = heatmap
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|slot| {
+            (
+                slot["date"].as_str().unwrap(),
+                slot["reserved"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            ("2024-01-02", 2),
+            ("2024-01-02", 1),
+            ("2024-01-03", 0),
+            ("2024-01-03", 0),
+        ]
+    );
+
+    let (status, _) = get(
+        &client,
+        "/resources/heatmap?from=2024-01-01&to=2024-03-30",
+        &gina,
+    );
+    assert_eq!(status, Status::Ok);
+    let (status, _) = get(
+        &client,
+        "/resources/heatmap?from=2024-01-01&to=2024-03-31",
+        &gina,
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn the_matrix_marks_reserved_held_and_maintained_slots_busy() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let first = create_vehicle(&client, "Truck 1", "Truck");
+    let second = create_vehicle(&client, "Truck 2", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    for vehicle in [first, second, crane] {
+        attach(&client, site, vehicle);
+    }
+    let mut morning = days("2024-01-02", "2024-01-02");
+    morning["end_period"] = json!("Morning");
+    let (status, _) = reserve(&client, site, first, morning);
+    assert_eq!(status, Status::Ok);
+    let mut afternoon = days("2024-01-02", "2024-01-02");
+    afternoon["start_period"] = json!("Afternoon");
+    let mut hold = afternoon.clone();
+    hold["site_id"] = json!(site);
+    let (status, _) = post(&client, &format!("/vehicles/{second}/holds"), &gina, hold);
+    assert_eq!(status, Status::Created);
+    let windows = format!("/vehicles/{crane}/maintenance/windows");
+    let (status, _) = post(&client, &windows, &gina, days("2024-01-03", "2024-01-03"));
+    assert_eq!(status, Status::Created);
+
+    let uri = "/resources/matrix?from=2024-01-02&to=2024-01-03";
+    let (status, matrix) = get(&client, uri, &gina);
+    assert_eq!(status, Status::Ok, "{matrix}");
+    assert_eq!(matrix["slots"].as_array().unwrap().len(), 4);
+    let rows: Vec<_> = matrix["vehicles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| (row["vehicle_id"].as_u64().unwrap(), row["free"].clone()))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            (first, json!([false, true, true, true])),
+            (second, json!([true, false, true, true])),
+            (crane, json!([true, true, false, false])),
+        ]
+    );
+
+    let (_, trucks) = get(&client, &format!("{uri}&kind=Truck"), &gina);
+    assert_eq!(trucks["vehicles"].as_array().unwrap().len(), 2);
+    let (status, _) = get(
+        &client,
+        "/resources/matrix?from=2024-01-01&to=2024-01-31",
+        &gina,
+    );
+    assert_eq!(status, Status::Ok);
+    let (status, _) = get(
+        &client,
+        "/resources/matrix?from=2024-01-01&to=2024-02-01",
+        &gina,
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn utilization_averages_the_reserved_share_of_each_vehicle() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let busy = create_vehicle(&client, "Truck 1", "Truck");
+    let idle = create_vehicle(&client, "Truck 2", "Truck");
+    attach(&client, site, busy);
+    attach(&client, site, idle);
+    let mut reserved = days("2024-01-02", "2024-01-03");
+    reserved["end_period"] = json!("Morning");
+    let (status, _) = reserve(&client, site, busy, reserved);
+    assert_eq!(status, Status::Ok);
+
+    // 3 of the 8 half-days from Monday to Thursday.
+    let uri = format!("/sites/{site}/utilization?from=2024-01-01&to=2024-01-04");
+    let (status, utilization) = get(&client, &uri, &global_manager());
+    assert_eq!(status, Status::Ok, "{utilization}");
+    assert_eq!(
+        utilization["vehicles"],
+        json!([
+            { "vehicle_id": busy, "utilization": 0.375 },
+            { "vehicle_id": idle, "utilization": 0.0 },
+        ])
+    );
+    assert_eq!(utilization["utilization"], 0.1875);
+}
+
+#[test]
+fn reservations_record_who_made_them_and_why() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let mut period = days("2024-01-02", "2024-01-02");
+    period["note"] = json!("Concrete delivery");
+
+    let (status, receipt) = reserve(&client, site, truck, period);
+    assert_eq!(status, Status::Ok, "{receipt}");
+    let (_, vehicle) = get(&client, &format!("/vehicles/{truck}"), &global_manager());
+    let reserved = &vehicle["reserved_dates"][0];
+    assert_eq!(reserved["reserved_by"], "gina");
+    assert_eq!(reserved["note"], "Concrete delivery");
+    assert_eq!(reserved["site_id"], site);
+}
+
+#[test]
+fn the_receipt_counts_calendar_half_days() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+
+    // Friday to Monday, the weekend included.
+    let (status, receipt) = reserve(&client, site, truck, days("2024-01-05", "2024-01-08"));
+    assert_eq!(status, Status::Ok, "{receipt}");
+    let receipt = &receipt["data"];
+    assert_eq!(receipt["vehicle_id"], truck);
+    assert_eq!(receipt["half_days"], 8);
+    assert_eq!(receipt["dry_run"], false);
+    assert_eq!(receipt["available"], true);
+    assert!(receipt.get("conflict").is_none(), "{receipt}");
+}
+
+#[test]
+fn holds_block_reservations_until_confirmed_or_released() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, site, truck);
+    let holds = format!("/vehicles/{truck}/holds");
+    let mut hold = days("2024-01-02", "2024-01-02");
+    hold["site_id"] = json!(site);
+
+    for ttl in [0, 61] {
+        let mut out_of_range = hold.clone();
+        out_of_range["ttl_minutes"] = json!(ttl);
+        let (status, error) = post(&client, &holds, &gina, out_of_range);
+        assert_eq!(status, Status::UnprocessableEntity, "{ttl}: {error}");
+    }
+
+    let (status, first) = post(&client, &holds, &gina, hold.clone());
+    assert_eq!(status, Status::Created, "{first}");
+    let (status, _) = reserve(&client, site, truck, days("2024-01-02", "2024-01-02"));
+    assert_eq!(status, Status::Conflict);
+    let uri = format!("{holds}/{}", first["data"]["id"]);
+    let (status, _) = send(&client, Method::Delete, &uri, &gina, None);
+    assert_eq!(status, Status::NoContent);
+    let (status, _) = send(&client, Method::Delete, &uri, &gina, None);
+    assert_eq!(status, Status::NotFound);
+
+    let (_, second) = post(&client, &holds, &gina, hold);
+    let uri = format!("{holds}/{}/confirm", second["data"]["id"]);
+    let (status, reserved) = send(&client, Method::Post, &uri, &gina, None);
+    assert_eq!(status, Status::Ok, "{reserved}");
+    assert_eq!(reserved["site_id"], site);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-02", "2024-01-02"));
+    assert_eq!(status, Status::Conflict);
+}
+
+#[test]
+fn completed_sites_take_no_more_vehicles() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    let (status, _) = reserve(&client, site, truck, days("2024-01-01", "2024-01-01"));
+    assert_eq!(status, Status::Ok);
+    let holds = format!("/vehicles/{truck}/holds");
+    let mut hold = days("2024-01-02", "2024-01-02");
+    hold["site_id"] = json!(site);
+    let (status, held) = post(&client, &holds, &gina, hold.clone());
+    assert_eq!(status, Status::Created, "{held}");
+
+    let sam = site_manager("sam");
+    add_manager(&client, site, &sam);
+    let uri = format!("/sites/{site}/status");
+    for status in ["InProgress", "Completed"] {
+        let (code, body) = patch(&client, &uri, &sam, json!({ "status": status }));
+        assert_eq!(code, Status::Ok, "{body}");
+    }
+
+    let (status, vehicle) = get(&client, &format!("/vehicles/{truck}"), &gina);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(vehicle["holds"], json!([]));
+    assert_eq!(vehicle["reserved_dates"].as_array().unwrap().len(), 1);
+    let confirm = format!("{holds}/{}/confirm", held["data"]["id"]);
+    let (status, _) = send(&client, Method::Post, &confirm, &gina, None);
+    assert_eq!(status, Status::NotFound);
+
+    let attach_crane = format!("/sites/{site}/vehicles/{crane}");
+    let refused = [
+        reserve(&client, site, truck, days("2024-01-03", "2024-01-03")),
+        post(&client, &holds, &gina, hold.clone()),
+        post(&client, &format!("/vehicles/{truck}/waitlist"), &gina, hold),
+        send(&client, Method::Post, &attach_crane, &gina, None),
+    ];
+    for (status, error) in refused {
+        assert_eq!(status, Status::Conflict, "{error}");
+        assert_eq!(error["errors"][0]["code"], "site_completed");
+    }
+
+    let response = request(
+        &client,
+        Method::Post,
+        &format!("/sites/{site}/reservations/import"),
+        &gina,
+    )
+    .header(ContentType::CSV)
+    .body(format!("{truck},2024-01-04,Morning,2024-01-04,Afternoon"))
+    .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[test]
+fn summaries_report_maintenance_planned_while_the_site_is_worked() {
+    let client = client();
+    let gina = global_manager();
+    let early = create_site(&client, "Depot", 0, 4);
+    let late = create_site(&client, "Bridge", 5, 4);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    attach(&client, early, truck);
+    attach(&client, late, truck);
+    let windows = format!("/vehicles/{truck}/maintenance/windows");
+    let (status, body) = post(&client, &windows, &gina, days("2024-01-02", "2024-01-02"));
+    assert_eq!(status, Status::Created, "{body}");
+    let (_, vehicle) = get(&client, &format!("/vehicles/{truck}"), &gina);
+
+    let (status, summary) = get(&client, &format!("/sites/{early}/summary"), &gina);
+    assert_eq!(status, Status::Ok, "{summary}");
+    let conflicts = summary["maintenance_conflicts"].as_array().unwrap();
+    assert_eq!(conflicts.len(), 1, "{summary}");
+    assert_eq!(conflicts[0]["vehicle_id"], truck);
+    assert_eq!(
+        conflicts[0]["maintenance"]["start_date"],
+        vehicle["maintenance_windows"][0]["start_date"]
+    );
+
+    let (_, summary) = get(&client, &format!("/sites/{late}/summary"), &gina);
+    assert_eq!(summary["maintenance_conflicts"], json!([]));
+}
+
+#[test]
+fn interrupted_sites_take_vehicles_with_a_warning() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 10);
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let crane = create_vehicle(&client, "Crane 1", "Crane");
+    attach(&client, site, truck);
+    let sam = site_manager("sam");
+    add_manager(&client, site, &sam);
+    let uri = format!("/sites/{site}/status");
+    let interrupt = json!({ "status": "Interrupted", "reason": "Storm" });
+    for body in [json!({ "status": "InProgress" }), interrupt] {
+        let (status, body) = patch(&client, &uri, &sam, body);
+        assert_eq!(status, Status::Ok, "{body}");
+    }
+
+    let mut hold = days("2024-01-02", "2024-01-02");
+    hold["site_id"] = json!(site);
+    let warned = [
+        (format!("/sites/{site}/vehicles/{crane}"), None, Status::Ok),
+        (
+            format!("/sites/{site}/vehicles/{truck}/reserve"),
+            Some(days("2024-01-01", "2024-01-01")),
+            Status::Ok,
+        ),
+        (
+            format!("/vehicles/{truck}/holds"),
+            Some(hold),
+            Status::Created,
+        ),
+    ];
+    for (uri, body, expected) in warned {
+        let mut request = request(&client, Method::Post, &uri, &gina);
+        if let Some(body) = body {
+            request = request.header(ContentType::JSON).body(body.to_string());
+        }
+        let response = request.dispatch();
+        assert_eq!(response.status(), expected, "{uri}");
+        let header = response.headers().get_one("Warning").map(str::to_owned);
+        assert_eq!(
+            header.as_deref(),
+            Some("199 - \"The site is interrupted\""),
+            "{uri}"
+        );
+        let body: Value = serde_json::from_str(&response.into_string().unwrap()).unwrap();
+        assert_eq!(
+            body["warnings"],
+            json!([{ "code": "site_interrupted", "message": "The site is interrupted" }]),
+            "{uri}"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Workers and their assignments to sites, through the routes.
+
+mod common;
+
+use rocket::http::{Method, Status};
+use rocket::local::blocking::Client;
+use serde_json::{json, Value};
+
+use common::*;
+
+fn create_worker(client: &Client, body: Value) -> u64 {
+    let (status, worker) = post(client, "/workers", &global_manager(), body);
+    assert_eq!(status, Status::Created, "{worker}");
+    worker["id"].as_u64().expect("worker id")
+}
+
+/// Creates a site as the global manager from a full body and returns its id.
+fn create_site_from(client: &Client, body: Value) -> u64 {
+    let (status, site) = post(client, "/sites", &global_manager(), body);
+    assert_eq!(status, Status::Created, "{site}");
+    site["id"].as_u64().expect("site id")
+}
+
+fn batch(client: &Client, site: u64, workers: Value) -> (Status, Value) {
+    post(
+        client,
+        &format!("/sites/{site}/workers/batch"),
+        &global_manager(),
+        workers,
+    )
+}
+
+/// Moves site `id` to Completed through its managing site manager.
+fn complete(client: &Client, id: u64) {
+    let sam = site_manager("sam");
+    add_manager(client, id, &sam);
+    let uri = format!("/sites/{id}/status");
+    for status in ["InProgress", "Completed"] {
+        let (code, body) = patch(client, &uri, &sam, json!({ "status": status }));
+        assert_eq!(code, Status::Ok, "{body}");
+    }
+}
+
+#[test]
+fn workers_are_created_and_read_back() {
+    let client = client();
+    let gina = global_manager();
+    let id = create_worker(&client, json!({ "name": "Ada", "trade": "Mason" }));
+
+    let (status, worker) = get(&client, &format!("/workers/{id}"), &gina);
+    assert_eq!(status, Status::Ok);
+    assert_eq!(worker["name"], "Ada");
+    assert_eq!(worker["trade"], "Mason");
+    assert_eq!(worker["certifications"], json!([]));
+
+    let (status, error) = post(&client, "/workers", &gina, json!({ "name": " " }));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(error["errors"][0]["source"]["pointer"], "/name");
+    let (status, _) = get(&client, "/workers/999", &gina);
+    assert_eq!(status, Status::NotFound);
+}
+
+#[test]
+fn batches_report_an_outcome_per_worker() {
+    let client = client();
+    let first = create_site(&client, "Depot", 0, 4);
+    let second = create_site(&client, "Warehouse", 0, 4);
+    let mut certified = new_site("Substation", 10, 4);
+    certified["required_certifications"] = json!(["High voltage"]);
+    let certified = create_site_from(&client, certified);
+    let busy = create_worker(&client, json!({ "name": "Ada" }));
+    let free = create_worker(&client, json!({ "name": "Bob" }));
+    let (status, _) = batch(&client, first, json!([busy]));
+    assert_eq!(status, Status::Ok);
+
+    let (status, assignments) = batch(&client, second, json!([free, free, 999, busy]));
+    assert_eq!(status, Status::Ok, "{assignments}");
+    let outcomes: Vec<&Value> = assignments
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|assignment| &assignment["outcome"])
+        .collect();
+    assert_eq!(
+        outcomes,
+        ["Assigned", "AlreadyAssigned", "NotFound", "Overlapping"]
+    );
+
+    let (_, assignments) = batch(&client, certified, json!([free]));
+    assert_eq!(assignments[0]["outcome"], "MissingCertifications");
+}
+
+#[test]
+fn moved_workers_leave_their_other_sites() {
+    let client = client();
+    let first = create_site(&client, "Depot", 0, 4);
+    let second = create_site(&client, "Warehouse", 0, 4);
+    let worker = create_worker(&client, json!({ "name": "Ada" }));
+    batch(&client, first, json!([worker]));
+
+    let uri = format!("/workers/{worker}/move");
+    let (status, moved) = post(&client, &uri, &global_manager(), json!({ "to": second }));
+    assert_eq!(status, Status::Ok, "{moved}");
+    let workers: Vec<(&Value, &Value)> = moved["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|site| (&site["id"], &site["workers"]))
+        .collect();
+    assert_eq!(
+        workers,
+        [
+            (&json!(first), &json!([])),
+            (&json!(second), &json!([worker])),
+        ]
+    );
+}
+
+#[test]
+fn missing_certifications_block_a_move() {
+    let client = client();
+    let mut site = new_site("Substation", 0, 4);
+    site["required_certifications"] = json!(["High voltage", "Working at height"]);
+    let site = create_site_from(&client, site);
+    let worker = create_worker(
+        &client,
+        json!({ "name": "Ada", "certifications": ["Working at height"] }),
+    );
+
+    let uri = format!("/workers/{worker}/move");
+    let (status, error) = post(&client, &uri, &global_manager(), json!({ "to": site }));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(
+        error["errors"][0]["meta"]["missing_certifications"],
+        json!(["High voltage"])
+    );
+}
+
+#[test]
+fn completed_sites_refuse_new_workers() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 4);
+    let worker = create_worker(&client, json!({ "name": "Ada" }));
+    complete(&client, site);
+
+    let (status, error) = batch(&client, site, json!([worker]));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(error["errors"][0]["code"], "site_completed");
+    let uri = format!("/workers/{worker}/move");
+    let (status, _) = post(&client, &uri, &gina, json!({ "to": site }));
+    assert_eq!(status, Status::Conflict);
+    let uri = format!("/sites/{site}/workers");
+    let (status, _) = post(&client, &uri, &gina, json!({ "name": "Bob" }));
+    assert_eq!(status, Status::Conflict);
+    let (_, workers) = get(&client, "/workers", &gina);
+    assert_eq!(workers.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn trades_count_assigned_workers_only() {
+    let client = client();
+    let site = create_site(&client, "Depot", 0, 4);
+    let mason = create_worker(&client, json!({ "name": "Ada", "trade": "Mason" }));
+    let other = create_worker(&client, json!({ "name": "Bob", "trade": "Mason" }));
+    let roofer = create_worker(&client, json!({ "name": "Cy", "trade": "Roofer" }));
+    create_worker(&client, json!({ "name": "Di", "trade": "Painter" }));
+    batch(&client, site, json!([mason, other, roofer]));
+
+    let (status, trades) = get(&client, "/workers/trades", &global_manager());
+    assert_eq!(status, Status::Ok);
+    assert_eq!(trades, json!({ "Mason": 2, "Roofer": 1 }));
+}
+
+#[test]
+fn matches_are_free_sites_needing_the_trade() {
+    let client = client();
+    let site_needing = |name: &str, start_day: usize, trade: &str| {
+        let mut site = new_site(name, start_day, 4);
+        site["required_trades"] = json!([trade]);
+        create_site_from(&client, site)
+    };
+    let assigned = site_needing("Depot", 0, "Electrician");
+    site_needing("Warehouse", 0, "Electrician");
+    let free = site_needing("Substation", 10, "Electrician");
+    site_needing("Roof", 20, "Roofer");
+    let worker = create_worker(&client, json!({ "name": "Ada", "trade": "Electrician" }));
+    batch(&client, assigned, json!([worker]));
+
+    let (status, matches) = get(
+        &client,
+        &format!("/workers/{worker}/matches"),
+        &global_manager(),
+    );
+    assert_eq!(status, Status::Ok);
+    let ids: Vec<&Value> = matches
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|site| &site["id"])
+        .collect();
+    assert_eq!(ids, [&json!(free)]);
+}
+
+#[test]
+fn the_crew_lists_workers_and_the_vehicles_reserved_that_day() {
+    let client = client();
+    let gina = global_manager();
+    let site = create_site(&client, "Depot", 0, 4);
+    let worker = create_worker(&client, json!({ "name": "Ada" }));
+    batch(&client, site, json!([worker]));
+    let truck = create_vehicle(&client, "Truck 1", "Truck");
+    let uri = format!("/sites/{site}/vehicles/{truck}");
+    let (status, _) = send(&client, Method::Post, &uri, &gina, None);
+    assert_eq!(status, Status::Ok);
+    let reservation = json!({
+        "start_date": "2024-01-02",
+        "start_period": "Afternoon",
+        "end_date": "2024-01-02",
+        "end_period": "Afternoon",
+    });
+    let (status, _) = post(&client, &format!("{uri}/reserve"), &gina, reservation);
+    assert_eq!(status, Status::Ok);
+
+    let (status, crew) = get(
+        &client,
+        &format!("/sites/{site}/crew?date=2024-01-02"),
+        &gina,
+    );
+    assert_eq!(status, Status::Ok, "{crew}");
+    assert_eq!(crew["workers"][0]["name"], "Ada");
+    assert_eq!(crew["vehicles"][0]["vehicle_id"], truck);
+    assert_eq!(crew["vehicles"][0]["periods"].as_array().unwrap().len(), 1);
+
+    let (_, crew) = get(
+        &client,
+        &format!("/sites/{site}/crew?date=2024-01-01"),
+        &gina,
+    );
+    assert_eq!(crew["vehicles"], json!([]));
+}